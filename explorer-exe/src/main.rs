@@ -3,7 +3,13 @@ use std::{fs, sync::Arc};
 use axum::Extension;
 use bitcoinsuite_chronik_client::ChronikClient;
 use bitcoinsuite_error::Result;
-use explorer_server::{config, server::Server};
+use explorer_server::{
+    config,
+    events::ChronikWsSource,
+    pools::{self, PoolRegistry},
+    reward::CoinbaseRules,
+    server::Server,
+};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -12,9 +18,24 @@ async fn main() -> Result<()> {
     let config_string = fs::read_to_string(config_path)?;
     let config = config::load_config(&config_string)?;
 
+    let (script_subscriptions, event_source) = ChronikWsSource::new(&config.chronik_api_url)?;
     let chronik = ChronikClient::new(config.chronik_api_url)?;
-    let base_dir = config.base_dir.unwrap_or_else(|| "../explorer-server".into());
-    let server = Arc::new(Server::setup(chronik, base_dir).await?);
+    let base_dir = config
+        .base_dir
+        .unwrap_or_else(|| "../explorer-server".into());
+    let pools = match &config.pools_file {
+        Some(pools_file) => pools::load_pool_registry(&fs::read_to_string(pools_file)?)?,
+        None => PoolRegistry::default(),
+    };
+    let server = Server::setup(chronik, base_dir, config.cache)
+        .await?
+        .with_pool_registry(pools)
+        .with_coinbase_rules(CoinbaseRules::new(config.coinbase_rules)?)
+        .with_script_subscriptions(script_subscriptions);
+    let server = Arc::new(server);
+    tokio::spawn(server.clone().relay_events(event_source));
+    tokio::spawn(server.clone().sync_chain_stats(config.charts));
+    tokio::spawn(server.clone().sync_miner_stats());
     let app = server.router().layer(Extension(server));
 
     axum::Server::bind(&config.host)
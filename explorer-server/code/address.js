@@ -128,7 +128,7 @@ const renderFee = (_value, _type, row) => {
     return '<div class="ui green horizontal label">Coinbase</div>';
   }
 
-  const fee = renderInteger(row.stats.satsInput - row.stats.satsOutput);
+  const fee = renderInteger(row.stats.fee);
   let markup = '';
 
   markup += `<span>${fee}</span>`
@@ -141,16 +141,14 @@ const renderFeePerByte = (_value, _type, row) => {
   if (row.isCoinbase) {
     return '';
   }
-  const fee = row.stats.satsInput - row.stats.satsOutput;
-  const feePerByte = fee / row.size;
-  return renderInteger(Math.round(feePerByte * 1000)) + '/kB';
+  return renderInteger(Math.round(row.stats.feeRate * 1000)) + '/kB';
 };
 
 const renderAmountXEC = (_value, _type, row) => renderSats(row.stats.deltaSats) + ' XEC';
 
 const renderToken = (_value, _type, row) => {
   if (row.token !== null) {
-    var ticker = ' <a href="/tx/' + row.token.tokenId + '">' + row.token.tokenTicker + '</a>';
+    var ticker = ' <a href="/token/' + row.token.tokenId + '">' + row.token.tokenTicker + '</a>';
     return renderAmount(row.stats.deltaTokens, row.token.decimals) + ticker;
   }
   return '';
@@ -242,7 +240,81 @@ const reRenderPage = params => {
   window.pagination.generatePaginationUI(currentPage, pageArray);
 };
 
+// balance history chart, one series per XEC and each token
+const MAX_BALANCE_AXIS_LABELS = 8;
+let balanceHistory = null;
+let balanceHistoryChart = null;
+
+const renderBalanceHistory = key => {
+  const series = key === 'xec'
+    ? { points: balanceHistory.xec, decimals: 2, ticker: 'XEC' }
+    : balanceHistory.tokens
+        .filter(token => token.tokenId === key)
+        .map(token => ({
+          points: token.points,
+          decimals: token.token ? token.token.decimals : 0,
+          ticker: token.token ? token.token.tokenTicker : key.substr(0, 8),
+        }))[0];
+  const labelStep = Math.max(1, Math.ceil(series.points.length / MAX_BALANCE_AXIS_LABELS));
+
+  if (balanceHistoryChart !== null) {
+    balanceHistoryChart.destructor();
+  }
+  balanceHistoryChart = webix.ui({
+    container: 'balance-history-chart',
+    view: 'chart',
+    type: 'line',
+    height: 250,
+    value: '#balance#',
+    item: { radius: 0 },
+    line: { color: '#0074c2', width: 2 },
+    xAxis: { template: '#axisLabel#', lines: false },
+    yAxis: {},
+    tooltip: { template: row => row.date + ': ' + row.balance + ' ' + series.ticker },
+    data: series.points.map((point, idx) => ({
+      date: moment(point.timestamp * 1000).format('ll'),
+      axisLabel: idx % labelStep === 0 ? moment(point.timestamp * 1000).format('ll') : '',
+      balance: Number(point.balance) / Math.pow(10, series.decimals),
+    })),
+  });
+};
+
+const loadBalanceHistory = () => {
+  $.getJSON(`/api/address/${getAddress()}/balance-history`).done(history => {
+    balanceHistory = history;
+    const select = $('#balance-history-select').empty();
+    select.append($('<option>').val('xec').text('XEC'));
+    history.tokens.forEach(token => {
+      const label = token.token ? token.token.tokenTicker : token.tokenId;
+      select.append($('<option>').val(token.tokenId).text(label));
+    });
+    $('#balance-history-notice').toggleClass('display-none', history.isComplete);
+    renderBalanceHistory(select.val());
+  });
+};
+
+$('#balance-history-select').on('change', event => renderBalanceHistory(event.target.value));
+
 $(document).ready(() => {
   datatable();
   reRenderPage();
+  loadBalanceHistory();
+});
+
+// live updates
+const seenTxs = new Set();
+window.events.subscribe([getAddress()], message => {
+  if (message.type !== 'addressTx') {
+    return;
+  }
+
+  // Txs are reported once when they enter the mempool and again when mined
+  if (!seenTxs.has(message.txHash)) {
+    seenTxs.add(message.txHash);
+    $('#pagination').data('total-entries', window.state.getPaginationTotalEntries() + 1);
+  }
+
+  $('#new-txs-notice').removeClass('display-none');
+  reRenderPage();
+  loadBalanceHistory();
 });
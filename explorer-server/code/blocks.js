@@ -45,6 +45,7 @@ const renderDifficulty = difficulty => {
     return (estHashrate / 1e18).toFixed(2) + ' EH/s';
   }
 };
+const renderMiner = miner => miner ? $('<span>').text(miner).html() : 'Unknown';
 const renderTimestamp = timestamp => moment(timestamp * 1000).format('ll, LTS');
 
 const updateLoading = (status) => {
@@ -117,6 +118,7 @@ const dataTable = () => {
       { data: 'hash', orderable: false, className: 'hash', render: renderHash },
       { data: 'size', orderable: false, render: renderSize },
       { data: 'difficulty', orderable: false, render: renderDifficulty },
+      { data: 'miner', orderable: false, render: renderMiner },
       { name: 'timestamp', data: 'timestamp', render: renderTimestamp },
       { name: 'responsive', render: () => '' },
     ]
@@ -174,3 +176,20 @@ webix.ready(() => {
   // global state update
   reRenderPage();
 });
+
+// live updates
+window.events.subscribe([], message => {
+  if (message.type !== 'blockConnected' && message.type !== 'blockDisconnected') {
+    return;
+  }
+
+  const lastBlockHeight = message.type === 'blockConnected'
+    ? message.block.height
+    : message.height - 1;
+  $('#pagination').data('total-entries', lastBlockHeight);
+
+  // Only the first page shows the tip, other pages would just shift
+  if (window.state.getParameters().page === 0) {
+    reRenderPage();
+  }
+});
@@ -0,0 +1,69 @@
+// chart rendering for /charts, one webix line chart per /api/stats series
+const CHART_COLORS = ['#0074c2', '#ff7a00', '#21ba45', '#db2828'];
+const MAX_AXIS_LABELS = 12;
+
+const renderChartValue = value => {
+  if (value >= 1e15) {
+    return (value / 1e15).toFixed(2) + 'P';
+  } else if (value >= 1e12) {
+    return (value / 1e12).toFixed(2) + 'T';
+  } else if (value >= 1e9) {
+    return (value / 1e9).toFixed(2) + 'G';
+  } else if (value >= 1e6) {
+    return (value / 1e6).toFixed(2) + 'M';
+  } else if (value >= 1e3) {
+    return (value / 1e3).toFixed(2) + 'k';
+  }
+  return Math.round(value * 100) / 100;
+};
+
+const renderChart = (container, series) => {
+  const labelStep = Math.max(1, Math.ceil(series.points.length / MAX_AXIS_LABELS));
+  const data = series.points.map((point, idx) => {
+    const row = {
+      date: moment(point.timestamp * 1000).format('ll'),
+      axisLabel: idx % labelStep === 0 ? moment(point.timestamp * 1000).format('MMM D') : '',
+    };
+    point.values.forEach((value, valueIdx) => {
+      row['v' + valueIdx] = value;
+    });
+    return row;
+  });
+
+  webix.ui({
+    container,
+    view: 'chart',
+    type: 'line',
+    height: 300,
+    xAxis: {
+      template: '#axisLabel#',
+      lines: false,
+    },
+    yAxis: {
+      template: renderChartValue,
+    },
+    legend: series.labels.length > 1 ? {
+      values: series.labels.map((label, idx) => ({ text: label, color: CHART_COLORS[idx] })),
+      align: 'right',
+      valign: 'top',
+      layout: 'x',
+    } : undefined,
+    series: series.labels.map((label, idx) => ({
+      value: '#v' + idx + '#',
+      item: { radius: 0 },
+      line: { color: CHART_COLORS[idx], width: 2 },
+      tooltip: {
+        template: row => row.date + ': ' + renderChartValue(row['v' + idx]) + ' ' + label,
+      },
+    })),
+    data,
+  });
+};
+
+webix.ready(() => {
+  $('.chart').each((_, element) => {
+    $.getJSON('/api/stats/' + element.dataset.series)
+      .done(series => renderChart(element.id, series))
+      .fail(() => $(element).text('Failed to load chart'));
+  });
+});
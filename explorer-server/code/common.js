@@ -355,3 +355,24 @@ const scrollToBottom = () => {
   };
 
 }(window.pagination = window.pagination || {}, jQuery));
+
+(function(events, $) {
+  const RECONNECT_DELAY_MS = 5000;
+
+  // Opens /ws and calls `onMessage` with every decoded event, reconnecting
+  // whenever the connection drops. `addresses` are the addresses to receive
+  // addressTx events for; block events are always sent.
+  events.subscribe = (addresses, onMessage) => {
+    const protocol = window.location.protocol === 'https:' ? 'wss:' : 'ws:';
+    const query = addresses.length ? '?addresses=' + encodeURIComponent(addresses.join(',')) : '';
+    const url = `${protocol}//${window.location.host}/ws${query}`;
+
+    const connect = () => {
+      const socket = new WebSocket(url);
+      socket.onmessage = event => onMessage(JSON.parse(event.data));
+      socket.onclose = () => setTimeout(connect, RECONNECT_DELAY_MS);
+    };
+    connect();
+  };
+
+}(window.events = window.events || {}, jQuery));
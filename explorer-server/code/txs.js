@@ -6,7 +6,7 @@ const renderFee = (_value, _type, row) => {
     return '<div class="ui green horizontal label">Coinbase</div>';
   }
 
-  const fee = renderInteger(row.stats.satsInput - row.stats.satsOutput);
+  const fee = renderInteger(row.stats.fee);
   let markup = '';
 
   markup += `<span>${fee}</span>`
@@ -18,13 +18,11 @@ const renderFeePerByte = (_value, _type, row) => {
   if (row.isCoinbase) {
     return '';
   }
-  const fee = row.stats.satsInput - row.stats.satsOutput;
-  const feePerByte = fee / row.size;
-  return renderInteger(Math.round(feePerByte * 1000)) + '/kB';
+  return renderInteger(Math.round(row.stats.feeRate * 1000)) + '/kB';
 };
 const renderOutput = (satsOutput, _type, row) => {
   if (row.token) {
-    var ticker = ' <a href="/tx/' + row.token.tokenId + '">' + row.token.tokenTicker + '</a>';
+    var ticker = ' <a href="/token/' + row.token.tokenId + '">' + row.token.tokenTicker + '</a>';
     return renderAmount(row.stats.tokenOutput, row.token.decimals) + ticker;
   }
   return renderSats(row.stats.satsOutput) + ' XEC';
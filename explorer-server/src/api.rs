@@ -1,41 +1,95 @@
 use std::collections::HashMap;
 
-use bitcoinsuite_chronik_client::proto::{Block, SlpGenesisInfo, Token, Tx, TxHistoryPage};
+use bitcoinsuite_chronik_client::proto::{
+    Block, BlockInfo, SlpGenesisInfo, SlpMeta, SlpToken, SlpTokenType, SlpTxType, Token, Tx,
+    TxHistoryPage,
+};
 use bitcoinsuite_core::CashAddress;
 use bitcoinsuite_error::Result;
+use eyre::eyre;
 
 use crate::{
-    blockchain::to_be_hex,
-    server_primitives::{JsonToken, JsonTx, JsonTxStats},
+    blockchain::{
+        calculate_block_difficulty, destination_from_script, destination_from_spent_script,
+        estimate_hashrate, redeem_script, to_be_hex, Destination,
+    },
+    mempool::FEE_RATE_BUCKETS,
+    op_return::{DecodedOpReturn, OpReturnRegistry},
+    pools::PoolRegistry,
+    reward::CoinbaseRules,
+    script::{classify_script_sig, disassemble, opcode_name, ScriptSig},
+    server_primitives::{
+        JsonBalance, JsonBalanceHistoryResponse, JsonBalancePoint, JsonBlock, JsonBlockStats,
+        JsonCoinbaseBreakdown, JsonCoinbaseOutput, JsonDestination, JsonFeeRateBucket,
+        JsonLargestTx, JsonMinerStatsWindow, JsonMultisig, JsonOpReturn, JsonOpReturnField,
+        JsonPoolShare, JsonScriptSig, JsonToken, JsonTokenBalance, JsonTokenBalanceHistory,
+        JsonTokenTx, JsonTokenTxCounts, JsonTx, JsonTxDetails, JsonTxInput, JsonTxOutput,
+        JsonTxStats,
+    },
 };
 
 pub fn tokens_to_json(tokens: &HashMap<String, Token>) -> Result<HashMap<String, JsonToken>> {
     let mut json_tokens = HashMap::new();
 
     for (token_id, token) in tokens.iter() {
-        if let Some(slp_tx_data) = &token.slp_tx_data {
-            if let (Some(slp_meta), Some(genesis_info)) =
-                (&slp_tx_data.slp_meta, &slp_tx_data.genesis_info)
-            {
-                let token_ticker = String::from_utf8_lossy(&genesis_info.token_ticker).to_string();
-                let token_name = String::from_utf8_lossy(&genesis_info.token_name).to_string();
-
-                let json_token = JsonToken {
-                    token_id: token_id.clone(),
-                    token_type: slp_meta.token_type as u32,
-                    token_ticker,
-                    token_name,
-                    decimals: genesis_info.decimals,
-                    group_id: Some(hex::encode(&slp_meta.group_token_id)),
-                };
-                json_tokens.insert(token_id.clone(), json_token.clone());
-            }
+        if let Some(json_token) = token_to_json(token_id, token) {
+            json_tokens.insert(token_id.clone(), json_token);
         }
     }
 
     Ok(json_tokens)
 }
 
+/// Token balances among `json_balances`, optionally only that of
+/// `token_id_filter`, sorted by token id.
+pub fn token_balances_to_json(
+    json_balances: HashMap<String, JsonBalance>,
+    json_tokens: &HashMap<String, JsonToken>,
+    token_id_filter: Option<&String>,
+) -> Vec<JsonTokenBalance> {
+    let mut token_balances = json_balances
+        .into_values()
+        .filter_map(|balance| {
+            let token_id = balance.token_id?;
+            if token_id_filter
+                .map(|filter| filter != &token_id)
+                .unwrap_or(false)
+            {
+                return None;
+            }
+            let token = json_tokens.get(&token_id).cloned();
+            let decimals = token.as_ref().map(|token| token.decimals).unwrap_or(0);
+            Some(JsonTokenBalance {
+                token_amount_str: format_token_amount(balance.token_amount, decimals),
+                token_id,
+                token,
+                token_amount: balance.token_amount,
+                sats_amount: balance.sats_amount,
+                num_utxos: balance.utxos.len(),
+            })
+        })
+        .collect::<Vec<_>>();
+    token_balances.sort_by(|a, b| a.token_id.cmp(&b.token_id));
+    token_balances
+}
+
+pub fn token_to_json(token_id: &str, token: &Token) -> Option<JsonToken> {
+    let slp_tx_data = token.slp_tx_data.as_ref()?;
+    let slp_meta = slp_tx_data.slp_meta.as_ref()?;
+    let genesis_info = slp_tx_data.genesis_info.as_ref()?;
+    let token_ticker = String::from_utf8_lossy(&genesis_info.token_ticker).to_string();
+    let token_name = String::from_utf8_lossy(&genesis_info.token_name).to_string();
+
+    Some(JsonToken {
+        token_id: token_id.to_string(),
+        token_type: slp_meta.token_type as u32,
+        token_ticker,
+        token_name,
+        decimals: genesis_info.decimals,
+        group_id: Some(hex::encode(&slp_meta.group_token_id)),
+    })
+}
+
 pub fn tx_history_to_json(
     address: &CashAddress,
     address_tx_history: TxHistoryPage,
@@ -45,42 +99,143 @@ pub fn tx_history_to_json(
     let address_bytes = address.to_script().bytecode().to_vec();
 
     for tx in address_tx_history.txs.iter() {
-        let (block_height, timestamp) = match &tx.block {
-            Some(block) => (Some(block.height), block.timestamp),
-            None => (None, tx.time_first_seen),
-        };
+        json_txs.push(tx_to_json(tx, Some(&address_bytes), json_tokens));
+    }
 
-        let (token_id, token) = match &tx.slp_tx_data {
-            Some(slp_tx_data) => {
-                let slp_meta = slp_tx_data.slp_meta.as_ref().expect("Impossible");
-                let token_id = hex::encode(&slp_meta.token_id);
-                let json_token = json_tokens.get(&token_id);
+    Ok(json_txs)
+}
 
-                match json_token {
-                    Some(json_token) => (Some(token_id.clone()), Some(json_token.clone())),
-                    None => (Some(token_id.clone()), None),
-                }
+/// Summarizes `tx`; the stats' deltas are relative to `address_bytes` if set.
+pub fn tx_to_json(
+    tx: &Tx,
+    address_bytes: Option<&[u8]>,
+    json_tokens: &HashMap<String, JsonToken>,
+) -> JsonTx {
+    let (block_height, timestamp) = match &tx.block {
+        Some(block) => (Some(block.height), block.timestamp),
+        None => (None, tx.time_first_seen),
+    };
+
+    let (token_id, token) = match &tx.slp_tx_data {
+        Some(slp_tx_data) => {
+            let slp_meta = slp_tx_data.slp_meta.as_ref().expect("Impossible");
+            let token_id = hex::encode(&slp_meta.token_id);
+            let json_token = json_tokens.get(&token_id);
+
+            match json_token {
+                Some(json_token) => (Some(token_id.clone()), Some(json_token.clone())),
+                None => (Some(token_id.clone()), None),
             }
-            None => (None, None),
-        };
+        }
+        None => (None, None),
+    };
 
-        let stats = calc_tx_stats(tx, Some(&address_bytes));
+    let stats = calc_tx_stats(tx, address_bytes);
 
-        json_txs.push(JsonTx {
-            tx_hash: to_be_hex(&tx.txid),
-            block_height,
-            timestamp,
-            is_coinbase: tx.is_coinbase,
-            size: tx.size as i32,
-            num_inputs: tx.inputs.len() as u32,
-            num_outputs: tx.outputs.len() as u32,
-            stats,
-            token_id,
-            token,
+    JsonTx {
+        tx_hash: to_be_hex(&tx.txid),
+        block_height,
+        timestamp,
+        is_coinbase: tx.is_coinbase,
+        size: tx.size as i32,
+        num_inputs: tx.inputs.len() as u32,
+        num_outputs: tx.outputs.len() as u32,
+        stats,
+        token_id,
+        token,
+    }
+}
+
+/// Buckets `txs`, given with their stats, by fee rate.
+pub fn fee_rate_histogram<'a>(
+    txs: impl IntoIterator<Item = (&'a Tx, &'a JsonTxStats)>,
+) -> Vec<JsonFeeRateBucket> {
+    let mut min_fee_rate = 0.0;
+    let mut buckets = Vec::with_capacity(FEE_RATE_BUCKETS.len() + 1);
+    for max_fee_rate in FEE_RATE_BUCKETS
+        .iter()
+        .copied()
+        .map(Some)
+        .chain(std::iter::once(None))
+    {
+        buckets.push(JsonFeeRateBucket {
+            min_fee_rate,
+            max_fee_rate,
+            num_txs: 0,
+            total_bytes: 0,
         });
+        min_fee_rate = max_fee_rate.unwrap_or_default();
+    }
+    for (tx, stats) in txs {
+        let bucket_idx = FEE_RATE_BUCKETS
+            .iter()
+            .position(|&max_fee_rate| stats.fee_rate < max_fee_rate)
+            .unwrap_or(FEE_RATE_BUCKETS.len());
+        buckets[bucket_idx].num_txs += 1;
+        buckets[bucket_idx].total_bytes += tx.size as u64;
     }
+    buckets
+}
 
-    Ok(json_txs)
+pub fn block_info_to_json(block: &BlockInfo, miner: Option<String>) -> JsonBlock {
+    JsonBlock {
+        hash: to_be_hex(&block.hash),
+        height: block.height,
+        timestamp: block.timestamp,
+        difficulty: calculate_block_difficulty(block.n_bits),
+        size: block.block_size,
+        num_txs: block.num_txs,
+        miner,
+    }
+}
+
+/// Share of `blocks` each pool mined, most blocks first; `miners` holds the
+/// pool name of each block.
+pub fn miner_stats_window_to_json(
+    label: String,
+    blocks: &[BlockInfo],
+    miners: &[Option<String>],
+    pools: &PoolRegistry,
+) -> JsonMinerStatsWindow {
+    let num_blocks = blocks.len() as u64;
+    let avg_difficulty = blocks
+        .iter()
+        .map(|block| calculate_block_difficulty(block.n_bits))
+        .sum::<f64>()
+        / num_blocks.max(1) as f64;
+    let hashrate = estimate_hashrate(avg_difficulty);
+
+    let mut pool_blocks = HashMap::<Option<&str>, u64>::new();
+    for miner in miners {
+        *pool_blocks.entry(miner.as_deref()).or_default() += 1;
+    }
+    let mut shares = pool_blocks
+        .into_iter()
+        .map(|(name, pool_num_blocks)| {
+            let share = pool_num_blocks as f64 / num_blocks as f64;
+            JsonPoolShare {
+                name: name.map(str::to_string),
+                url: pools
+                    .pools()
+                    .iter()
+                    .find(|pool| Some(pool.name.as_str()) == name)
+                    .and_then(|pool| pool.url.clone()),
+                num_blocks: pool_num_blocks,
+                share,
+                hashrate: share * hashrate,
+            }
+        })
+        .collect::<Vec<_>>();
+    shares.sort_by(|a, b| b.num_blocks.cmp(&a.num_blocks).then(a.name.cmp(&b.name)));
+
+    JsonMinerStatsWindow {
+        label,
+        start_height: blocks.first().map(|block| block.height).unwrap_or(0),
+        end_height: blocks.last().map(|block| block.height).unwrap_or(0),
+        num_blocks,
+        hashrate,
+        pools: shares,
+    }
 }
 
 pub fn block_txs_to_json(
@@ -120,14 +275,14 @@ pub fn block_txs_to_json(
                 let token_name = String::from_utf8_lossy(&genesis_info.token_name).to_string();
 
                 (
-                    Some(token_id_hex),
+                    Some(token_id_hex.clone()),
                     Some(JsonToken {
-                        token_id: to_be_hex(&slp_meta.token_id),
+                        token_id: token_id_hex,
                         token_type: slp_meta.token_type as u32,
                         token_ticker,
                         token_name,
                         decimals: genesis_info.decimals,
-                        group_id: Some(to_be_hex(&slp_meta.group_token_id)),
+                        group_id: Some(hex::encode(&slp_meta.group_token_id)),
                     }),
                 )
             }
@@ -154,6 +309,14 @@ pub fn block_txs_to_json(
 }
 
 pub fn calc_tx_stats(tx: &Tx, address_bytes: Option<&[u8]>) -> JsonTxStats {
+    calc_tx_stats_matching(tx, |script| {
+        address_bytes.map_or(true, |address_bytes| address_bytes == script)
+    })
+}
+
+/// Like `calc_tx_stats`, with deltas relative to every output script
+/// `is_ours` accepts, e.g. all addresses of a wallet.
+pub fn calc_tx_stats_matching(tx: &Tx, is_ours: impl Fn(&[u8]) -> bool) -> JsonTxStats {
     let sats_input = tx.inputs.iter().map(|input| input.value).sum();
     let sats_output = tx.outputs.iter().map(|output| output.value).sum();
     let token_input: i128 = tx
@@ -174,10 +337,8 @@ pub fn calc_tx_stats(tx: &Tx, address_bytes: Option<&[u8]>) -> JsonTxStats {
     let mut delta_tokens: i64 = 0;
 
     for input in &tx.inputs {
-        if let Some(address_bytes) = address_bytes {
-            if address_bytes != input.output_script {
-                continue;
-            }
+        if !is_ours(&input.output_script) {
+            continue;
         }
         delta_sats -= input.value;
         if let Some(slp) = &input.slp_token {
@@ -186,10 +347,8 @@ pub fn calc_tx_stats(tx: &Tx, address_bytes: Option<&[u8]>) -> JsonTxStats {
     }
 
     for output in &tx.outputs {
-        if let Some(address_bytes) = address_bytes {
-            if address_bytes != output.output_script {
-                continue;
-            }
+        if !is_ours(&output.output_script) {
+            continue;
         }
         delta_sats += output.value;
         if let Some(slp) = &output.slp_token {
@@ -197,6 +356,17 @@ pub fn calc_tx_stats(tx: &Tx, address_bytes: Option<&[u8]>) -> JsonTxStats {
         }
     }
 
+    let fee = if tx.is_coinbase {
+        0
+    } else {
+        (sats_input - sats_output).max(0)
+    };
+    let fee_rate = if tx.size > 0 {
+        fee as f64 / tx.size as f64
+    } else {
+        0.0
+    };
+
     JsonTxStats {
         sats_input,
         sats_output,
@@ -205,5 +375,515 @@ pub fn calc_tx_stats(tx: &Tx, address_bytes: Option<&[u8]>) -> JsonTxStats {
         token_input,
         token_output,
         does_burn_slp,
+        fee,
+        fee_rate,
+    }
+}
+
+/// Fee, value and token stats of `block`; fee rates ignore the coinbase tx.
+pub fn block_stats_to_json(
+    block: &Block,
+    coinbase_rules: &CoinbaseRules,
+    satoshi_addr_prefix: &str,
+) -> Result<JsonBlockStats> {
+    let block_info = block
+        .block_info
+        .as_ref()
+        .ok_or_else(|| eyre!("Block has no info"))?;
+    let tx_stats = block
+        .txs
+        .iter()
+        .map(|tx| calc_tx_stats(tx, None))
+        .collect::<Vec<_>>();
+
+    let mut fee_rates = block
+        .txs
+        .iter()
+        .zip(&tx_stats)
+        .filter(|(tx, _)| !tx.is_coinbase)
+        .map(|(_, stats)| stats.fee_rate)
+        .collect::<Vec<_>>();
+    fee_rates.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let median_fee_rate = match fee_rates.len() {
+        0 => 0.0,
+        len if len % 2 == 0 => (fee_rates[len / 2 - 1] + fee_rates[len / 2]) / 2.0,
+        len => fee_rates[len / 2],
+    };
+
+    let num_token_txs = token_tx_counts(&block.txs);
+
+    let largest_tx = block
+        .txs
+        .iter()
+        .max_by_key(|tx| tx.size)
+        .map(|tx| JsonLargestTx {
+            tx_hash: to_be_hex(&tx.txid),
+            size: tx.size,
+        });
+
+    let total_fee = tx_stats.iter().map(|stats| stats.fee).sum();
+    let coinbase_tx = block
+        .txs
+        .first()
+        .ok_or_else(|| eyre!("Block has no coinbase tx"))?;
+    let coinbase = coinbase_rules.breakdown(block_info.height, coinbase_tx, total_fee);
+
+    Ok(JsonBlockStats {
+        hash: to_be_hex(&block_info.hash),
+        height: block_info.height,
+        total_fee,
+        min_fee_rate: fee_rates.first().copied().unwrap_or(0.0),
+        median_fee_rate,
+        max_fee_rate: fee_rates.last().copied().unwrap_or(0.0),
+        total_output: tx_stats.iter().map(|stats| stats.sats_output).sum(),
+        num_inputs: block.txs.iter().map(|tx| tx.inputs.len() as u64).sum(),
+        num_outputs: block.txs.iter().map(|tx| tx.outputs.len() as u64).sum(),
+        num_token_txs,
+        largest_tx,
+        coinbase: JsonCoinbaseBreakdown {
+            subsidy: coinbase.subsidy,
+            fees: coinbase.fees,
+            outputs: coinbase
+                .outputs
+                .iter()
+                .map(|output| {
+                    let output_script = &coinbase_tx.outputs[output.out_idx].output_script;
+                    JsonCoinbaseOutput {
+                        out_idx: output.out_idx,
+                        kind: output.kind,
+                        address: match destination_from_script(satoshi_addr_prefix, output_script) {
+                            Destination::Address(address) => Some(address.as_str().to_string()),
+                            _ => None,
+                        },
+                        value: output.value,
+                    }
+                })
+                .collect(),
+            warnings: coinbase.warnings,
+        },
+    })
+}
+
+pub fn token_tx_counts(txs: &[Tx]) -> JsonTokenTxCounts {
+    let mut num_token_txs = JsonTokenTxCounts::default();
+    for tx in txs {
+        let tx_type = tx
+            .slp_tx_data
+            .as_ref()
+            .and_then(|slp_tx_data| slp_tx_data.slp_meta.as_ref())
+            .and_then(|slp_meta| SlpTxType::from_i32(slp_meta.tx_type));
+        match tx_type {
+            Some(SlpTxType::Genesis) => num_token_txs.genesis += 1,
+            Some(SlpTxType::Mint) => num_token_txs.mint += 1,
+            Some(SlpTxType::Send) => num_token_txs.send += 1,
+            _ => {}
+        }
+        let is_burn = tx_type == Some(SlpTxType::Burn)
+            || tx.inputs.iter().any(|input| input.slp_burn.is_some());
+        if is_burn {
+            num_token_txs.burn += 1;
+        }
+    }
+    num_token_txs
+}
+
+/// Change of an address's balances in one tx.
+pub struct BalanceDelta {
+    pub timestamp: i64,
+    pub tx_hash: String,
+    pub delta_sats: i64,
+    pub token_id: Option<String>,
+    pub delta_tokens: i64,
+}
+
+/// Reconstructs the balances after each of `deltas` (newest first) by walking
+/// back from the current balances, so a truncated history still ends at the
+/// right balance.
+pub fn balance_history_to_json(
+    deltas: &[BalanceDelta],
+    current_sats: i64,
+    current_tokens: &HashMap<String, i128>,
+    json_tokens: &HashMap<String, JsonToken>,
+    is_complete: bool,
+) -> JsonBalanceHistoryResponse {
+    let mut sats = current_sats as i128;
+    let mut xec_points = Vec::new();
+    let mut token_balances = current_tokens.clone();
+    let mut token_points = HashMap::<&str, Vec<JsonBalancePoint>>::new();
+
+    for delta in deltas {
+        if delta.delta_sats != 0 {
+            xec_points.push(JsonBalancePoint {
+                timestamp: delta.timestamp,
+                tx_hash: delta.tx_hash.clone(),
+                balance: sats,
+            });
+            sats -= delta.delta_sats as i128;
+        }
+        if let Some(token_id) = &delta.token_id {
+            if delta.delta_tokens != 0 {
+                let balance = token_balances.entry(token_id.clone()).or_default();
+                token_points
+                    .entry(token_id)
+                    .or_default()
+                    .push(JsonBalancePoint {
+                        timestamp: delta.timestamp,
+                        tx_hash: delta.tx_hash.clone(),
+                        balance: *balance,
+                    });
+                *balance -= delta.delta_tokens as i128;
+            }
+        }
+    }
+
+    xec_points.reverse();
+    let mut tokens = token_points
+        .into_iter()
+        .map(|(token_id, mut points)| {
+            points.reverse();
+            JsonTokenBalanceHistory {
+                token_id: token_id.to_string(),
+                token: json_tokens.get(token_id).cloned(),
+                points,
+            }
+        })
+        .collect::<Vec<_>>();
+    tokens.sort_by(|a, b| b.points.len().cmp(&a.points.len()));
+
+    JsonBalanceHistoryResponse {
+        xec: xec_points,
+        tokens,
+        num_txs: deltas.len(),
+        is_complete,
+    }
+}
+
+/// Renders a base token amount as a plain decimal string, e.g. 1250 with two
+/// decimals becomes "12.50".
+pub fn format_token_amount(base_amount: i128, decimals: u32) -> String {
+    let decimals = decimals as usize;
+    if decimals == 0 {
+        return base_amount.to_string();
+    }
+    let sign = if base_amount < 0 { "-" } else { "" };
+    let digits = format!(
+        "{:0width$}",
+        base_amount.unsigned_abs(),
+        width = decimals + 1
+    );
+    let (integer_part, fract_part) = digits.split_at(digits.len() - decimals);
+    format!("{}{}.{}", sign, integer_part, fract_part)
+}
+
+pub fn slp_action_str(slp_meta: &SlpMeta) -> Result<&'static str> {
+    let token_type =
+        SlpTokenType::from_i32(slp_meta.token_type).ok_or_else(|| eyre!("Malformed slp_meta"))?;
+    let tx_type =
+        SlpTxType::from_i32(slp_meta.tx_type).ok_or_else(|| eyre!("Malformed slp_meta"))?;
+
+    let action_str = match (token_type, tx_type) {
+        (SlpTokenType::Fungible, SlpTxType::Genesis) => "GENESIS",
+        (SlpTokenType::Fungible, SlpTxType::Mint) => "MINT",
+        (SlpTokenType::Fungible, SlpTxType::Send) => "SEND",
+        (SlpTokenType::Fungible, SlpTxType::Burn) => "BURN",
+        (SlpTokenType::Nft1Group, SlpTxType::Genesis) => "NFT1 GROUP GENESIS",
+        (SlpTokenType::Nft1Group, SlpTxType::Mint) => "NFT1 GROUP MINT",
+        (SlpTokenType::Nft1Group, SlpTxType::Send) => "NFT1 GROUP SEND",
+        (SlpTokenType::Nft1Group, SlpTxType::Burn) => "NFT1 GROUP BURN",
+        (SlpTokenType::Nft1Child, SlpTxType::Genesis) => "NFT1 Child GENESIS",
+        (SlpTokenType::Nft1Child, SlpTxType::Send) => "NFT1 Child SEND",
+        (SlpTokenType::Nft1Child, SlpTxType::Burn) => "NFT1 Child BURN",
+        _ => "Unknown",
+    };
+
+    Ok(action_str)
+}
+
+pub fn slp_token_type_str(token_type: SlpTokenType) -> &'static str {
+    match token_type {
+        SlpTokenType::Fungible => "Type1",
+        SlpTokenType::Nft1Group => "NFT1 Group",
+        SlpTokenType::Nft1Child => "NFT1 Child",
+        _ => "Unknown",
+    }
+}
+
+/// Summarizes `tx` from the perspective of the token `token_id`; txs that
+/// spend the token without being valid token txs of it (or, for NFT1 groups,
+/// child geneses) are burns.
+pub fn token_tx_to_json(tx: &Tx, token_id: &[u8]) -> Result<JsonTokenTx> {
+    let (block_height, timestamp) = match &tx.block {
+        Some(block) => (Some(block.height), block.timestamp),
+        None => (None, tx.time_first_seen),
+    };
+    let slp_meta = tx
+        .slp_tx_data
+        .as_ref()
+        .and_then(|slp_tx_data| slp_tx_data.slp_meta.as_ref())
+        .filter(|slp_meta| slp_meta.token_id == token_id);
+    let child_genesis_meta = tx
+        .slp_tx_data
+        .as_ref()
+        .and_then(|slp_tx_data| slp_tx_data.slp_meta.as_ref())
+        .filter(|slp_meta| slp_meta.group_token_id == token_id);
+    let stats = calc_tx_stats(tx, None);
+
+    let json_token_tx = match (slp_meta, child_genesis_meta) {
+        (Some(slp_meta), _) => JsonTokenTx {
+            tx_hash: to_be_hex(&tx.txid),
+            block_height,
+            timestamp,
+            token_action: slp_action_str(slp_meta)?.to_string(),
+            token_input: stats.token_input,
+            token_output: stats.token_output,
+            is_valid: true,
+        },
+        // Spends one group token into the genesis of a child NFT
+        (None, Some(child_genesis_meta)) => JsonTokenTx {
+            tx_hash: to_be_hex(&tx.txid),
+            block_height,
+            timestamp,
+            token_action: slp_action_str(child_genesis_meta)?.to_string(),
+            token_input: stats.token_input,
+            token_output: 0,
+            is_valid: true,
+        },
+        (None, None) => JsonTokenTx {
+            tx_hash: to_be_hex(&tx.txid),
+            block_height,
+            timestamp,
+            token_action: "BURN".to_string(),
+            token_input: 0,
+            token_output: 0,
+            is_valid: false,
+        },
+    };
+
+    Ok(json_token_tx)
+}
+
+/// Prefixes addresses are encoded with, depending on whether they hold XEC
+/// or tokens.
+#[derive(Clone, Copy)]
+pub struct AddressPrefixes<'a> {
+    pub satoshi: &'a str,
+    pub tokens: &'a str,
+}
+
+pub fn tx_details_to_json(
+    tx: &Tx,
+    token: Option<&Token>,
+    raw_tx: String,
+    tip_height: i32,
+    redeem_scripts: &HashMap<usize, Vec<u8>>,
+    op_returns: &OpReturnRegistry,
+    prefixes: AddressPrefixes,
+) -> Result<JsonTxDetails> {
+    let slp_meta = tx
+        .slp_tx_data
+        .as_ref()
+        .and_then(|slp_tx_data| slp_tx_data.slp_meta.as_ref());
+    let token_action = match slp_meta {
+        Some(slp_meta) => Some(slp_action_str(slp_meta)?.to_string()),
+        None => None,
+    };
+    let token = match (slp_meta, token) {
+        (Some(slp_meta), Some(token)) => token_to_json(&hex::encode(&slp_meta.token_id), token),
+        _ => None,
+    };
+
+    let (confirmations, timestamp) = match &tx.block {
+        Some(block) => (tip_height - block.height + 1, block.timestamp),
+        None => (0, tx.time_first_seen),
+    };
+
+    let inputs = tx
+        .inputs
+        .iter()
+        .map(|input| {
+            let prev_out = input.prev_out.as_ref().filter(|_| !tx.is_coinbase);
+            JsonTxInput {
+                prev_tx_hash: prev_out.map(|prev_out| to_be_hex(&prev_out.txid)),
+                prev_out_idx: prev_out.map(|prev_out| prev_out.out_idx),
+                input_script: hex::encode(&input.input_script),
+                input_script_asm: disassemble(&input.input_script),
+                script_sig: if tx.is_coinbase {
+                    JsonScriptSig::Coinbase
+                } else {
+                    script_sig_to_json(classify_script_sig(
+                        &input.input_script,
+                        &input.output_script,
+                    ))
+                },
+                output_script: hex::encode(&input.output_script),
+                destination: destination_to_json(
+                    prefixes,
+                    &input.output_script,
+                    &input.slp_token,
+                    redeem_script(&input.input_script, &input.output_script).as_deref(),
+                ),
+                sats_amount: input.value,
+                token_amount: input.slp_token.as_ref().map(|slp| slp.amount).unwrap_or(0),
+                is_mint_baton: input
+                    .slp_token
+                    .as_ref()
+                    .map(|slp| slp.is_mint_baton)
+                    .unwrap_or(false),
+                sequence_no: input.sequence_no,
+            }
+        })
+        .collect();
+
+    let outputs = tx
+        .outputs
+        .iter()
+        .enumerate()
+        .map(|(idx, output)| JsonTxOutput {
+            output_script: hex::encode(&output.output_script),
+            output_script_asm: disassemble(&output.output_script),
+            destination: destination_to_json(
+                prefixes,
+                &output.output_script,
+                &output.slp_token,
+                redeem_scripts.get(&idx).map(Vec::as_slice),
+            ),
+            op_return: op_returns
+                .decode(&output.output_script)
+                .map(op_return_to_json),
+            sats_amount: output.value,
+            token_amount: output.slp_token.as_ref().map(|slp| slp.amount).unwrap_or(0),
+            is_mint_baton: output
+                .slp_token
+                .as_ref()
+                .map(|slp| slp.is_mint_baton)
+                .unwrap_or(false),
+            spent_by_tx_hash: output
+                .spent_by
+                .as_ref()
+                .map(|outpoint| to_be_hex(&outpoint.txid)),
+            spent_by_idx: output.spent_by.as_ref().map(|outpoint| outpoint.out_idx),
+        })
+        .collect();
+
+    let stats = calc_tx_stats(tx, None);
+
+    Ok(JsonTxDetails {
+        tx_hash: to_be_hex(&tx.txid),
+        version: tx.version,
+        lock_time: tx.lock_time,
+        block_height: tx.block.as_ref().map(|block| block.height),
+        block_hash: tx.block.as_ref().map(|block| to_be_hex(&block.hash)),
+        timestamp,
+        time_first_seen: tx.time_first_seen,
+        confirmations,
+        is_coinbase: tx.is_coinbase,
+        size: tx.size,
+        fee: stats.fee,
+        stats,
+        token_id: slp_meta.map(|slp_meta| hex::encode(&slp_meta.token_id)),
+        token,
+        token_action,
+        slp_error_msg: Some(tx.slp_error_msg.clone()).filter(|msg| !msg.is_empty()),
+        inputs,
+        outputs,
+        raw_tx,
+    })
+}
+
+fn destination_to_json(
+    prefixes: AddressPrefixes,
+    script: &[u8],
+    slp_token: &Option<SlpToken>,
+    redeem_script: Option<&[u8]>,
+) -> JsonDestination {
+    let is_token = slp_token
+        .as_ref()
+        .map(|slp| slp.amount > 0 || slp.is_mint_baton)
+        .unwrap_or(false);
+    let prefix = if is_token {
+        prefixes.tokens
+    } else {
+        prefixes.satoshi
+    };
+    destination_json(destination_from_spent_script(prefix, script, redeem_script))
+}
+
+fn destination_json(destination: Destination) -> JsonDestination {
+    match destination {
+        Destination::Address(address) => JsonDestination::Address {
+            address: address.as_str().to_string(),
+        },
+        Destination::P2PK(pubkey) => JsonDestination::P2pk {
+            pubkey: hex::encode(pubkey),
+        },
+        Destination::Multisig(num_required, pubkeys) => JsonDestination::Multisig {
+            num_required,
+            pubkeys: pubkeys.iter().map(hex::encode).collect(),
+        },
+        Destination::P2SHWrapped(address, redeem) => JsonDestination::P2shWrapped {
+            address: address.as_str().to_string(),
+            redeem_script: Box::new(destination_json(*redeem)),
+        },
+        Destination::TimeLocked(locktime, destination) => JsonDestination::TimeLocked {
+            locktime,
+            destination: Box::new(destination_json(*destination)),
+        },
+        Destination::HashPuzzle(hash_op, hash) => JsonDestination::HashPuzzle {
+            hash_type: opcode_name(hash_op),
+            hash: hex::encode(hash),
+        },
+        Destination::AnyoneCanSpend => JsonDestination::AnyoneCanSpend,
+        Destination::Nulldata(_) => JsonDestination::Nulldata,
+        Destination::Malformed(_) => JsonDestination::Malformed,
+        Destination::Unknown(_) => JsonDestination::Unknown,
+    }
+}
+
+fn op_return_to_json(op_return: DecodedOpReturn) -> JsonOpReturn {
+    JsonOpReturn {
+        protocol: op_return.protocol.map(str::to_string),
+        fields: op_return
+            .fields
+            .into_iter()
+            .map(|field| JsonOpReturnField {
+                name: field.name,
+                value: field.value,
+            })
+            .collect(),
+        hex: op_return.hex,
+        utf8_preview: op_return.utf8_preview,
+    }
+}
+
+fn script_sig_to_json(script_sig: ScriptSig) -> JsonScriptSig {
+    match script_sig {
+        ScriptSig::P2pkh { signature, pubkey } => JsonScriptSig::P2pkh {
+            signature: hex::encode(signature),
+            pubkey: hex::encode(pubkey),
+        },
+        ScriptSig::P2pk { signature } => JsonScriptSig::P2pk {
+            signature: hex::encode(signature),
+        },
+        ScriptSig::Multisig {
+            num_required,
+            num_keys,
+            signatures,
+        } => JsonScriptSig::Multisig {
+            num_required,
+            num_keys,
+            signatures: signatures.iter().map(hex::encode).collect(),
+        },
+        ScriptSig::P2sh {
+            redeem_script,
+            multisig,
+        } => JsonScriptSig::P2sh {
+            redeem_script_asm: disassemble(&redeem_script),
+            redeem_script: hex::encode(redeem_script),
+            multisig: multisig.map(|(num_required, num_keys)| JsonMultisig {
+                num_required,
+                num_keys,
+            }),
+        },
+        ScriptSig::Unknown => JsonScriptSig::Unknown,
     }
 }
@@ -0,0 +1,131 @@
+use async_trait::async_trait;
+use bitcoinsuite_chronik_client::{
+    proto::{Block, BlockInfo, BlockchainInfo, ScriptUtxos, Token, Tx, TxHistoryPage},
+    ChronikClient, ChronikClientError, ScriptType,
+};
+use bitcoinsuite_core::{Bytes, Sha256d};
+use bitcoinsuite_error::Result;
+use eyre::Report;
+
+use crate::server_error::ServerError;
+
+mod fixture;
+
+pub use fixture::FixtureBackend;
+
+/// Every query `Server` makes against the indexer.
+///
+/// `ChronikClient` is the production implementation; `FixtureBackend` serves
+/// the same data from memory so the server can run without a node.
+#[async_trait]
+pub trait ExplorerBackend: Send + Sync {
+    async fn blockchain_info(&self) -> Result<BlockchainInfo>;
+
+    async fn block_by_height(&self, height: i32) -> Result<Block>;
+
+    async fn block_by_hash(&self, hash: &Sha256d) -> Result<Block>;
+
+    async fn blocks(&self, start_height: i32, end_height: i32) -> Result<Vec<BlockInfo>>;
+
+    async fn tx(&self, txid: &Sha256d) -> Result<Tx>;
+
+    async fn raw_tx(&self, txid: &Sha256d) -> Result<Bytes>;
+
+    async fn token(&self, token_id: &Sha256d) -> Result<Token>;
+
+    async fn script_history(
+        &self,
+        script_type: ScriptType,
+        script_payload: &[u8],
+        page: usize,
+        page_size: usize,
+    ) -> Result<TxHistoryPage>;
+
+    async fn script_utxos(
+        &self,
+        script_type: ScriptType,
+        script_payload: &[u8],
+    ) -> Result<Vec<ScriptUtxos>>;
+}
+
+#[async_trait]
+impl ExplorerBackend for ChronikClient {
+    async fn blockchain_info(&self) -> Result<BlockchainInfo> {
+        ChronikClient::blockchain_info(self)
+            .await
+            .map_err(upstream_error)
+    }
+
+    async fn block_by_height(&self, height: i32) -> Result<Block> {
+        ChronikClient::block_by_height(self, height)
+            .await
+            .map_err(upstream_error)
+    }
+
+    async fn block_by_hash(&self, hash: &Sha256d) -> Result<Block> {
+        ChronikClient::block_by_hash(self, hash)
+            .await
+            .map_err(upstream_error)
+    }
+
+    async fn blocks(&self, start_height: i32, end_height: i32) -> Result<Vec<BlockInfo>> {
+        ChronikClient::blocks(self, start_height, end_height)
+            .await
+            .map_err(upstream_error)
+    }
+
+    async fn tx(&self, txid: &Sha256d) -> Result<Tx> {
+        ChronikClient::tx(self, txid).await.map_err(upstream_error)
+    }
+
+    async fn raw_tx(&self, txid: &Sha256d) -> Result<Bytes> {
+        ChronikClient::raw_tx(self, txid)
+            .await
+            .map_err(upstream_error)
+    }
+
+    async fn token(&self, token_id: &Sha256d) -> Result<Token> {
+        ChronikClient::token(self, token_id)
+            .await
+            .map_err(upstream_error)
+    }
+
+    async fn script_history(
+        &self,
+        script_type: ScriptType,
+        script_payload: &[u8],
+        page: usize,
+        page_size: usize,
+    ) -> Result<TxHistoryPage> {
+        self.script(script_type, script_payload)
+            .history_with_page_size(page, page_size)
+            .await
+            .map_err(upstream_error)
+    }
+
+    async fn script_utxos(
+        &self,
+        script_type: ScriptType,
+        script_payload: &[u8],
+    ) -> Result<Vec<ScriptUtxos>> {
+        self.script(script_type, script_payload)
+            .utxos()
+            .await
+            .map_err(upstream_error)
+    }
+}
+
+/// Classifies a failed Chronik request: 4xx answers are passed on to the
+/// user, anything else means Chronik is unavailable.
+fn upstream_error(err: Report) -> Report {
+    let message = err.to_string();
+    let server_error = match err.downcast_ref::<ChronikClientError>() {
+        Some(ChronikClientError::ChronikError { status_code, .. }) => match status_code.as_u16() {
+            404 => ServerError::NotFound(message),
+            400 => ServerError::BadRequest(message),
+            _ => ServerError::UpstreamUnavailable(message),
+        },
+        _ => ServerError::UpstreamUnavailable(message),
+    };
+    server_error.into()
+}
@@ -0,0 +1,651 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::Path,
+};
+
+use async_trait::async_trait;
+use bitcoinsuite_chronik_client::{
+    proto::{
+        Block, BlockDetails, BlockInfo, BlockMetadata, BlockchainInfo, OutPoint, ScriptUtxos,
+        SlpGenesisInfo, SlpMeta, SlpToken, SlpTokenType, SlpTxData, SlpTxType, Token, Tx,
+        TxHistoryPage, TxInput, TxOutput, Utxo,
+    },
+    ScriptType,
+};
+use bitcoinsuite_core::{Bytes, CashAddress, Hashed, Sha256d};
+use bitcoinsuite_error::Result;
+use eyre::{bail, eyre};
+use prost::Message;
+use serde::Deserialize;
+
+use super::ExplorerBackend;
+use crate::{blockchain::script_from_type_payload, server_error::ServerError};
+
+/// In-memory backend serving blocks, txs and tokens inserted up front.
+///
+/// Script history and UTXOs are derived from the stored txs, and raw txs are
+/// re-serialized from their decoded form, so a fixture only has to provide
+/// blocks, txs and tokens.
+#[derive(Default)]
+pub struct FixtureBackend {
+    blocks: BTreeMap<i32, Block>,
+    txs: HashMap<Sha256d, Tx>,
+    tokens: HashMap<Sha256d, Token>,
+}
+
+impl FixtureBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads protobuf-encoded messages from `blocks/`, `txs/` and `tokens/`
+    /// subdirectories of `dir`; missing subdirectories are skipped.
+    pub fn load_dir(dir: &Path) -> Result<Self> {
+        let mut backend = FixtureBackend::new();
+        for bytes in read_dir_files(&dir.join("blocks"))? {
+            backend.insert_block(Block::decode(bytes.as_slice())?);
+        }
+        for bytes in read_dir_files(&dir.join("txs"))? {
+            backend.insert_tx(Tx::decode(bytes.as_slice())?);
+        }
+        for bytes in read_dir_files(&dir.join("tokens"))? {
+            backend.insert_token(Token::decode(bytes.as_slice())?);
+        }
+        Ok(backend)
+    }
+
+    /// Loads a JSON fixture, see `JsonFixture` for its format.
+    ///
+    /// Unlike protobuf fixtures, JSON ones only list blocks and mempool txs:
+    /// the txs' block metadata, the block infos and the tokens of GENESIS txs
+    /// are derived from them.
+    pub fn load_json(path: &Path) -> Result<Self> {
+        let fixture: JsonFixture = serde_json::from_str(&fs::read_to_string(path)?)?;
+        let mut backend = FixtureBackend::new();
+        for json_block in fixture.blocks {
+            backend.insert_block(json_block.into_block()?);
+        }
+        for json_tx in fixture.mempool {
+            backend.insert_tx(json_tx.into_tx(None)?);
+        }
+        let geneses = backend
+            .txs
+            .values()
+            .filter_map(genesis_token)
+            .collect::<Vec<_>>();
+        for token in geneses {
+            backend.insert_token(token);
+        }
+        Ok(backend)
+    }
+
+    /// Inserts a block, along with all the txs it contains.
+    pub fn insert_block(&mut self, block: Block) {
+        for tx in block.txs.iter() {
+            self.insert_tx(tx.clone());
+        }
+        let height = block
+            .block_info
+            .as_ref()
+            .map(|block_info| block_info.height)
+            .unwrap_or_default();
+        self.blocks.insert(height, block);
+    }
+
+    pub fn insert_tx(&mut self, tx: Tx) {
+        let txid = Sha256d::from_slice(&tx.txid).expect("Invalid txid");
+        self.txs.insert(txid, tx);
+    }
+
+    pub fn insert_token(&mut self, token: Token) {
+        let token_id = token
+            .slp_tx_data
+            .as_ref()
+            .and_then(|slp_tx_data| slp_tx_data.slp_meta.as_ref())
+            .map(|slp_meta| Sha256d::from_slice_be_or_null(&slp_meta.token_id))
+            .expect("Token has no slp_meta");
+        self.tokens.insert(token_id, token);
+    }
+
+    /// Txs touching `script`, newest first, with mempool txs at the top.
+    fn script_txs(&self, script: &[u8]) -> Vec<&Tx> {
+        let mut txs = self
+            .txs
+            .values()
+            .filter(|tx| {
+                tx.inputs.iter().any(|input| input.output_script == script)
+                    || tx
+                        .outputs
+                        .iter()
+                        .any(|output| output.output_script == script)
+            })
+            .collect::<Vec<_>>();
+        txs.sort_by_key(|tx| {
+            let height = tx
+                .block
+                .as_ref()
+                .map(|block| block.height)
+                .unwrap_or(i32::MAX);
+            std::cmp::Reverse((height, tx.time_first_seen))
+        });
+        txs
+    }
+}
+
+#[async_trait]
+impl ExplorerBackend for FixtureBackend {
+    async fn blockchain_info(&self) -> Result<BlockchainInfo> {
+        let (&tip_height, tip) = self
+            .blocks
+            .iter()
+            .next_back()
+            .ok_or_else(|| eyre!("Fixture has no blocks"))?;
+        let tip_hash = tip
+            .block_info
+            .as_ref()
+            .map(|block_info| block_info.hash.clone())
+            .unwrap_or_default();
+        Ok(BlockchainInfo {
+            tip_hash,
+            tip_height,
+        })
+    }
+
+    async fn block_by_height(&self, height: i32) -> Result<Block> {
+        self.blocks
+            .get(&height)
+            .cloned()
+            .ok_or_else(|| ServerError::NotFound(format!("Block not found: {}", height)).into())
+    }
+
+    async fn block_by_hash(&self, hash: &Sha256d) -> Result<Block> {
+        self.blocks
+            .values()
+            .find(|block| match &block.block_info {
+                Some(block_info) => block_info.hash == hash.as_slice(),
+                None => false,
+            })
+            .cloned()
+            .ok_or_else(|| {
+                ServerError::NotFound(format!("Block not found: {}", hash.to_hex_be())).into()
+            })
+    }
+
+    async fn blocks(&self, start_height: i32, end_height: i32) -> Result<Vec<BlockInfo>> {
+        Ok(self
+            .blocks
+            .range(start_height..=end_height)
+            .filter_map(|(_, block)| block.block_info.clone())
+            .collect())
+    }
+
+    async fn tx(&self, txid: &Sha256d) -> Result<Tx> {
+        self.txs.get(txid).cloned().ok_or_else(|| {
+            ServerError::NotFound(format!("Tx not found: {}", txid.to_hex_be())).into()
+        })
+    }
+
+    async fn raw_tx(&self, txid: &Sha256d) -> Result<Bytes> {
+        let tx = self.tx(txid).await?;
+        Ok(Bytes::from_bytes(serialize_tx(&tx)))
+    }
+
+    async fn token(&self, token_id: &Sha256d) -> Result<Token> {
+        self.tokens.get(token_id).cloned().ok_or_else(|| {
+            ServerError::NotFound(format!("Token not found: {}", token_id.to_hex_be())).into()
+        })
+    }
+
+    async fn script_history(
+        &self,
+        script_type: ScriptType,
+        script_payload: &[u8],
+        page: usize,
+        page_size: usize,
+    ) -> Result<TxHistoryPage> {
+        if page_size == 0 {
+            bail!("Page size must be positive");
+        }
+        let script = script_from_type_payload(script_type, script_payload);
+        let txs = self.script_txs(&script);
+        let num_pages = (txs.len() + page_size - 1) / page_size;
+        let txs = txs
+            .into_iter()
+            .skip(page * page_size)
+            .take(page_size)
+            .cloned()
+            .collect();
+        Ok(TxHistoryPage {
+            txs,
+            num_pages: num_pages as u32,
+        })
+    }
+
+    async fn script_utxos(
+        &self,
+        script_type: ScriptType,
+        script_payload: &[u8],
+    ) -> Result<Vec<ScriptUtxos>> {
+        let script = script_from_type_payload(script_type, script_payload);
+        let mut utxos = Vec::new();
+        for tx in self.script_txs(&script) {
+            for (out_idx, output) in tx.outputs.iter().enumerate() {
+                if output.output_script != script || output.spent_by.is_some() {
+                    continue;
+                }
+                utxos.push(Utxo {
+                    outpoint: Some(OutPoint {
+                        txid: tx.txid.clone(),
+                        out_idx: out_idx as u32,
+                    }),
+                    block_height: tx.block.as_ref().map(|block| block.height).unwrap_or(-1),
+                    is_coinbase: tx.is_coinbase,
+                    value: output.value,
+                    slp_meta: tx
+                        .slp_tx_data
+                        .as_ref()
+                        .and_then(|slp_tx_data| slp_tx_data.slp_meta.clone())
+                        .filter(|_| output.slp_token.is_some()),
+                    slp_token: output.slp_token.clone(),
+                    ..Default::default()
+                });
+            }
+        }
+        if utxos.is_empty() {
+            return Ok(vec![]);
+        }
+        Ok(vec![ScriptUtxos {
+            output_script: script,
+            utxos,
+        }])
+    }
+}
+
+/// JSON fixture, with hashes as big-endian hex like in URLs, and scripts and
+/// other byte strings as hex.
+#[derive(Deserialize)]
+struct JsonFixture {
+    #[serde(default)]
+    blocks: Vec<JsonFixtureBlock>,
+    #[serde(default)]
+    mempool: Vec<JsonFixtureTx>,
+}
+
+#[derive(Deserialize)]
+struct JsonFixtureBlock {
+    hash: String,
+    prev_hash: String,
+    height: i32,
+    n_bits: u32,
+    timestamp: i64,
+    #[serde(default = "default_version")]
+    version: i32,
+    #[serde(default)]
+    nonce: u64,
+    txs: Vec<JsonFixtureTx>,
+}
+
+#[derive(Deserialize)]
+struct JsonFixtureTx {
+    txid: String,
+    #[serde(default = "default_version")]
+    version: i32,
+    inputs: Vec<JsonFixtureInput>,
+    outputs: Vec<JsonFixtureOutput>,
+    #[serde(default)]
+    lock_time: u32,
+    #[serde(default)]
+    time_first_seen: i64,
+    #[serde(default)]
+    is_coinbase: bool,
+    slp: Option<JsonFixtureSlp>,
+}
+
+/// Input of a tx, spending `prev_txid:prev_out_idx` (a null outpoint if
+/// omitted, as in coinbase txs).
+#[derive(Deserialize)]
+struct JsonFixtureInput {
+    prev_txid: Option<String>,
+    #[serde(default)]
+    prev_out_idx: u32,
+    input_script: String,
+    #[serde(flatten)]
+    destination: JsonFixtureDestination,
+    #[serde(default)]
+    value: i64,
+    #[serde(default = "default_sequence_no")]
+    sequence_no: u32,
+    slp_token: Option<JsonFixtureSlpToken>,
+}
+
+#[derive(Deserialize)]
+struct JsonFixtureOutput {
+    value: i64,
+    #[serde(flatten)]
+    destination: JsonFixtureDestination,
+    spent_by: Option<JsonFixtureOutPoint>,
+    slp_token: Option<JsonFixtureSlpToken>,
+}
+
+/// Output script, given either as hex or as the address it pays to.
+#[derive(Deserialize)]
+struct JsonFixtureDestination {
+    output_script: Option<String>,
+    address: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct JsonFixtureOutPoint {
+    txid: String,
+    out_idx: u32,
+}
+
+#[derive(Deserialize)]
+struct JsonFixtureSlpToken {
+    #[serde(default)]
+    amount: u64,
+    #[serde(default)]
+    is_mint_baton: bool,
+}
+
+#[derive(Deserialize)]
+struct JsonFixtureSlp {
+    token_type: JsonFixtureTokenType,
+    tx_type: JsonFixtureTxType,
+    token_id: String,
+    group_token_id: Option<String>,
+    genesis_info: Option<JsonFixtureGenesisInfo>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+enum JsonFixtureTokenType {
+    Fungible,
+    Nft1Group,
+    Nft1Child,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+enum JsonFixtureTxType {
+    Genesis,
+    Send,
+    Mint,
+    Burn,
+}
+
+#[derive(Deserialize)]
+struct JsonFixtureGenesisInfo {
+    token_ticker: String,
+    token_name: String,
+    #[serde(default)]
+    token_document_url: String,
+    #[serde(default)]
+    decimals: u32,
+}
+
+fn default_version() -> i32 {
+    1
+}
+
+fn default_sequence_no() -> u32 {
+    0xffff_ffff
+}
+
+impl JsonFixtureBlock {
+    fn into_block(self) -> Result<Block> {
+        let hash = parse_fixture_hash(&self.hash)?;
+        let prev_hash = parse_fixture_hash(&self.prev_hash)?;
+        let block_meta = BlockMetadata {
+            height: self.height,
+            hash: hash.clone(),
+            timestamp: self.timestamp,
+        };
+        let txs = self
+            .txs
+            .into_iter()
+            .map(|json_tx| json_tx.into_tx(Some(block_meta.clone())))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut raw_header = Vec::with_capacity(80);
+        raw_header.extend_from_slice(&self.version.to_le_bytes());
+        raw_header.extend_from_slice(&prev_hash);
+        // Fixtures don't commit to their txs, so the merkle root is null
+        raw_header.extend_from_slice(&[0; 32]);
+        raw_header.extend_from_slice(&(self.timestamp as u32).to_le_bytes());
+        raw_header.extend_from_slice(&self.n_bits.to_le_bytes());
+        raw_header.extend_from_slice(&(self.nonce as u32).to_le_bytes());
+
+        let mut num_txs_size = Vec::new();
+        write_var_int(&mut num_txs_size, txs.len() as u64);
+        let block_size = raw_header.len()
+            + num_txs_size.len()
+            + txs.iter().map(|tx| tx.size as usize).sum::<usize>();
+
+        Ok(Block {
+            block_info: Some(BlockInfo {
+                hash,
+                prev_hash,
+                height: self.height,
+                n_bits: self.n_bits,
+                timestamp: self.timestamp,
+                block_size: block_size as u64,
+                num_txs: txs.len() as u64,
+                ..Default::default()
+            }),
+            block_details: Some(BlockDetails {
+                version: self.version,
+                nonce: self.nonce,
+                ..Default::default()
+            }),
+            raw_header,
+            txs,
+        })
+    }
+}
+
+impl JsonFixtureTx {
+    fn into_tx(self, block: Option<BlockMetadata>) -> Result<Tx> {
+        let inputs = self
+            .inputs
+            .into_iter()
+            .map(|input| {
+                let prev_out = match &input.prev_txid {
+                    Some(prev_txid) => OutPoint {
+                        txid: parse_fixture_hash(prev_txid)?,
+                        out_idx: input.prev_out_idx,
+                    },
+                    None => OutPoint {
+                        txid: vec![0; 32],
+                        out_idx: 0xffff_ffff,
+                    },
+                };
+                Ok(TxInput {
+                    prev_out: Some(prev_out),
+                    input_script: hex::decode(&input.input_script)?,
+                    output_script: input.destination.to_script()?,
+                    value: input.value,
+                    sequence_no: input.sequence_no,
+                    slp_token: input.slp_token.map(JsonFixtureSlpToken::into_slp_token),
+                    ..Default::default()
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let outputs = self
+            .outputs
+            .into_iter()
+            .map(|output| {
+                let spent_by = match &output.spent_by {
+                    Some(spent_by) => Some(OutPoint {
+                        txid: parse_fixture_hash(&spent_by.txid)?,
+                        out_idx: spent_by.out_idx,
+                    }),
+                    None => None,
+                };
+                Ok(TxOutput {
+                    value: output.value,
+                    output_script: output.destination.to_script()?,
+                    slp_token: output.slp_token.map(JsonFixtureSlpToken::into_slp_token),
+                    spent_by,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let slp_tx_data = match self.slp {
+            Some(slp) => Some(slp.into_slp_tx_data()?),
+            None => None,
+        };
+
+        let mut tx = Tx {
+            txid: parse_fixture_hash(&self.txid)?,
+            version: self.version,
+            inputs,
+            outputs,
+            lock_time: self.lock_time,
+            slp_tx_data,
+            block,
+            time_first_seen: self.time_first_seen,
+            is_coinbase: self.is_coinbase,
+            ..Default::default()
+        };
+        tx.size = serialize_tx(&tx).len() as u32;
+        Ok(tx)
+    }
+}
+
+impl JsonFixtureDestination {
+    fn to_script(&self) -> Result<Vec<u8>> {
+        match (&self.output_script, &self.address) {
+            (Some(output_script), None) => Ok(hex::decode(output_script)?),
+            (None, Some(address)) => {
+                let address = CashAddress::parse_cow(address.into())?;
+                Ok(address.to_script().bytecode().to_vec())
+            }
+            (None, None) => Ok(vec![]),
+            (Some(_), Some(_)) => bail!("Give either output_script or address, not both"),
+        }
+    }
+}
+
+impl JsonFixtureSlpToken {
+    fn into_slp_token(self) -> SlpToken {
+        SlpToken {
+            amount: self.amount,
+            is_mint_baton: self.is_mint_baton,
+        }
+    }
+}
+
+impl JsonFixtureSlp {
+    fn into_slp_tx_data(self) -> Result<SlpTxData> {
+        let token_type = match self.token_type {
+            JsonFixtureTokenType::Fungible => SlpTokenType::Fungible,
+            JsonFixtureTokenType::Nft1Group => SlpTokenType::Nft1Group,
+            JsonFixtureTokenType::Nft1Child => SlpTokenType::Nft1Child,
+        };
+        let tx_type = match self.tx_type {
+            JsonFixtureTxType::Genesis => SlpTxType::Genesis,
+            JsonFixtureTxType::Send => SlpTxType::Send,
+            JsonFixtureTxType::Mint => SlpTxType::Mint,
+            JsonFixtureTxType::Burn => SlpTxType::Burn,
+        };
+        let group_token_id = match &self.group_token_id {
+            Some(group_token_id) => hex::decode(group_token_id)?,
+            None => vec![],
+        };
+        Ok(SlpTxData {
+            slp_meta: Some(SlpMeta {
+                token_type: token_type as i32,
+                tx_type: tx_type as i32,
+                // Token IDs are big-endian, unlike txids
+                token_id: hex::decode(&self.token_id)?,
+                group_token_id,
+            }),
+            genesis_info: self.genesis_info.map(|genesis_info| SlpGenesisInfo {
+                token_ticker: genesis_info.token_ticker.into_bytes(),
+                token_name: genesis_info.token_name.into_bytes(),
+                token_document_url: genesis_info.token_document_url.into_bytes(),
+                decimals: genesis_info.decimals,
+                ..Default::default()
+            }),
+        })
+    }
+}
+
+/// Token created by `tx`, if it's a GENESIS tx.
+fn genesis_token(tx: &Tx) -> Option<Token> {
+    let slp_tx_data = tx.slp_tx_data.as_ref()?;
+    let slp_meta = slp_tx_data.slp_meta.as_ref()?;
+    if slp_meta.tx_type() != SlpTxType::Genesis {
+        return None;
+    }
+    let initial_token_quantity = tx
+        .outputs
+        .iter()
+        .filter_map(|output| output.slp_token.as_ref())
+        .map(|slp_token| slp_token.amount)
+        .sum();
+    Some(Token {
+        slp_tx_data: Some(slp_tx_data.clone()),
+        block: tx.block.clone(),
+        time_first_seen: tx.time_first_seen,
+        initial_token_quantity,
+        ..Default::default()
+    })
+}
+
+/// Parses a big-endian hex hash into the little-endian bytes Chronik uses.
+fn parse_fixture_hash(hash: &str) -> Result<Vec<u8>> {
+    Ok(Sha256d::from_hex_be(hash)?.as_slice().to_vec())
+}
+
+fn read_dir_files(dir: &Path) -> Result<Vec<Vec<u8>>> {
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut paths = fs::read_dir(dir)?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<_>>>()?;
+    paths.sort();
+    paths.into_iter().map(|path| Ok(fs::read(path)?)).collect()
+}
+
+fn serialize_tx(tx: &Tx) -> Vec<u8> {
+    let mut raw = Vec::new();
+    raw.extend_from_slice(&tx.version.to_le_bytes());
+    write_var_int(&mut raw, tx.inputs.len() as u64);
+    for input in tx.inputs.iter() {
+        let prev_out = input.prev_out.clone().unwrap_or_default();
+        raw.extend_from_slice(&prev_out.txid);
+        raw.extend_from_slice(&prev_out.out_idx.to_le_bytes());
+        write_var_int(&mut raw, input.input_script.len() as u64);
+        raw.extend_from_slice(&input.input_script);
+        raw.extend_from_slice(&input.sequence_no.to_le_bytes());
+    }
+    write_var_int(&mut raw, tx.outputs.len() as u64);
+    for output in tx.outputs.iter() {
+        raw.extend_from_slice(&output.value.to_le_bytes());
+        write_var_int(&mut raw, output.output_script.len() as u64);
+        raw.extend_from_slice(&output.output_script);
+    }
+    raw.extend_from_slice(&tx.lock_time.to_le_bytes());
+    raw
+}
+
+fn write_var_int(raw: &mut Vec<u8>, value: u64) {
+    match value {
+        0..=0xfc => raw.push(value as u8),
+        0xfd..=0xffff => {
+            raw.push(0xfd);
+            raw.extend_from_slice(&(value as u16).to_le_bytes());
+        }
+        0x10000..=0xffff_ffff => {
+            raw.push(0xfe);
+            raw.extend_from_slice(&(value as u32).to_le_bytes());
+        }
+        _ => {
+            raw.push(0xff);
+            raw.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+}
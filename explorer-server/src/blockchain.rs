@@ -2,6 +2,19 @@ use bitcoinsuite_chronik_client::ScriptType;
 use bitcoinsuite_core::{AddressType, CashAddress, Hashed, Op, Script, ShaRmd160};
 use bitcoinsuite_error::Result;
 
+use crate::script::{
+    classify_script_sig, decode_script_num, parse_multisig, parse_script, ScriptOp, ScriptSig,
+    OP_1, OP_16, OP_CHECKLOCKTIMEVERIFY, OP_CHECKSIG, OP_DROP, OP_DUP, OP_EQUAL, OP_EQUALVERIFY,
+    OP_HASH160, OP_HASH256, OP_RETURN, OP_RIPEMD160, OP_SHA1, OP_SHA256,
+};
+
+/// OP_CHECKLOCKTIMEVERIFY fails for locktime operands longer than this.
+const MAX_LOCKTIME_SIZE: u8 = 5;
+
+/// Max. number of stacked time locks unwrapped, the script behind any
+/// further ones is shown as unknown.
+const MAX_STACKED_TIME_LOCKS: usize = 16;
+
 pub fn to_be_hex(slice: &[u8]) -> String {
     let mut vec = slice.to_vec();
     vec.reverse();
@@ -19,38 +32,130 @@ pub enum Destination<'a> {
     Nulldata(Vec<Op>),
     Address(CashAddress<'a>),
     P2PK(Vec<u8>),
+    /// Bare m-of-n multisig; holds m and the n pubkeys.
+    Multisig(u8, Vec<Vec<u8>>),
+    /// P2SH address whose redeem script was revealed by the spending input.
+    P2SHWrapped(CashAddress<'a>, Box<Destination<'a>>),
+    /// `<locktime> OP_CHECKLOCKTIMEVERIFY OP_DROP` in front of another script.
+    TimeLocked(i64, Box<Destination<'a>>),
+    /// Hash lock like `OP_SHA256 <hash> OP_EQUAL`; holds the hash opcode and hash.
+    HashPuzzle(u8, Vec<u8>),
+    /// Empty script or a lone `OP_TRUE`.
+    AnyoneCanSpend,
+    /// Script with a push running past its end.
+    Malformed(Vec<u8>),
     Unknown(Vec<u8>),
 }
 
 pub fn destination_from_script<'a>(prefix: &'a str, script: &[u8]) -> Destination<'a> {
-    const OP_RETURN: u8 = 106;
-    const OP_DUP: u8 = 118;
-    const OP_EQUAL: u8 = 135;
-    const OP_EQUALVERIFY: u8 = 136;
-    const OP_HASH160: u8 = 169;
-    const OP_CHECKSIG: u8 = 172;
+    let mut locktimes = Vec::new();
+    let mut locked_script = script;
+    while locktimes.len() < MAX_STACKED_TIME_LOCKS {
+        match split_time_lock(locked_script) {
+            Some((locktime, rest)) => {
+                locktimes.push(locktime);
+                locked_script = rest;
+            }
+            None => break,
+        }
+    }
+    let destination = unlocked_destination(prefix, locked_script);
+    locktimes
+        .into_iter()
+        .rev()
+        .fold(destination, |destination, locktime| {
+            Destination::TimeLocked(locktime, Box::new(destination))
+        })
+}
+
+/// Splits `<locktime> OP_CHECKLOCKTIMEVERIFY OP_DROP` off the front of
+/// `script`, returning the locktime and the script it locks.
+fn split_time_lock(script: &[u8]) -> Option<(i64, &[u8])> {
+    let (locktime, rest) = match script {
+        [opcode @ OP_1..=OP_16, rest @ ..] => ((opcode - OP_1 + 1) as i64, rest),
+        [len @ 1..=MAX_LOCKTIME_SIZE, rest @ ..] if rest.len() >= *len as usize => {
+            let (data, rest) = rest.split_at(*len as usize);
+            (decode_script_num(data)?, rest)
+        }
+        _ => return None,
+    };
+    match rest {
+        [OP_CHECKLOCKTIMEVERIFY, OP_DROP, locked_script @ ..] => Some((locktime, locked_script)),
+        _ => None,
+    }
+}
 
+fn unlocked_destination<'a>(prefix: &'a str, script: &[u8]) -> Destination<'a> {
     match script {
-        [OP_DUP, OP_HASH160, 20, hash @ .., OP_EQUALVERIFY, OP_CHECKSIG] => {
+        [OP_DUP, OP_HASH160, 20, hash @ .., OP_EQUALVERIFY, OP_CHECKSIG] if hash.len() == 20 => {
             Destination::Address(CashAddress::from_hash(
                 prefix,
                 AddressType::P2PKH,
                 ShaRmd160::from_slice(hash).expect("Invalid hash"),
             ))
         }
-        [OP_HASH160, 20, hash @ .., OP_EQUAL] => Destination::Address(CashAddress::from_hash(
-            prefix,
-            AddressType::P2SH,
-            ShaRmd160::from_slice(hash).expect("Invalid hash"),
-        )),
-        [33, pk @ .., OP_CHECKSIG] => Destination::P2PK(pk.to_vec()),
-        [65, pk @ .., OP_CHECKSIG] => Destination::P2PK(pk.to_vec()),
+        [OP_HASH160, 20, hash @ .., OP_EQUAL] if hash.len() == 20 => {
+            Destination::Address(CashAddress::from_hash(
+                prefix,
+                AddressType::P2SH,
+                ShaRmd160::from_slice(hash).expect("Invalid hash"),
+            ))
+        }
+        [33, pk @ .., OP_CHECKSIG] if pk.len() == 33 => Destination::P2PK(pk.to_vec()),
+        [65, pk @ .., OP_CHECKSIG] if pk.len() == 65 => Destination::P2PK(pk.to_vec()),
         [OP_RETURN, data @ ..] => {
             let ops = Script::from_slice(data);
-            let ops = ops.ops().into_iter().map(|op| op.unwrap()).collect();
-            Destination::Nulldata(ops)
+            match ops
+                .ops()
+                .into_iter()
+                .collect::<std::result::Result<Vec<_>, _>>()
+            {
+                Ok(ops) => Destination::Nulldata(ops),
+                Err(_) => Destination::Malformed(script.to_vec()),
+            }
+        }
+        [] | [OP_1] => Destination::AnyoneCanSpend,
+        [hash_op @ (OP_SHA256 | OP_HASH256), 32, hash @ .., OP_EQUAL] if hash.len() == 32 => {
+            Destination::HashPuzzle(*hash_op, hash.to_vec())
+        }
+        [hash_op @ (OP_RIPEMD160 | OP_SHA1), 20, hash @ .., OP_EQUAL] if hash.len() == 20 => {
+            Destination::HashPuzzle(*hash_op, hash.to_vec())
+        }
+        _ => {
+            if let Some((num_required, _, pubkeys)) = parse_multisig(script) {
+                return Destination::Multisig(num_required, pubkeys);
+            }
+            match parse_script(script).last() {
+                Some(ScriptOp::Truncated(_)) => Destination::Malformed(script.to_vec()),
+                _ => Destination::Unknown(script.to_vec()),
+            }
         }
-        _ => Destination::Unknown(script.to_vec()),
+    }
+}
+
+/// Like `destination_from_script`, but unwraps P2SH outputs whose
+/// `redeem_script` is known from the input spending them.
+pub fn destination_from_spent_script<'a>(
+    prefix: &'a str,
+    script: &[u8],
+    redeem_script: Option<&[u8]>,
+) -> Destination<'a> {
+    match (destination_from_script(prefix, script), redeem_script) {
+        (Destination::Address(address), Some(redeem_script))
+            if matches!(address.addr_type(), AddressType::P2SH) =>
+        {
+            let redeem = destination_from_script(prefix, redeem_script);
+            Destination::P2SHWrapped(address, Box::new(redeem))
+        }
+        (destination, _) => destination,
+    }
+}
+
+/// Redeem script revealed by `input_script` if it spends the P2SH `output_script`.
+pub fn redeem_script(input_script: &[u8], output_script: &[u8]) -> Option<Vec<u8>> {
+    match classify_script_sig(input_script, output_script) {
+        ScriptSig::P2sh { redeem_script, .. } => Some(redeem_script),
+        _ => None,
     }
 }
 
@@ -77,6 +182,11 @@ pub fn calculate_block_difficulty(n_bits: u32) -> f64 {
     max_target / (n_word * 2f64.powi(8 * (n_size as i32 - 3)))
 }
 
+/// Hashrate needed to find a block of `difficulty` every 10 minutes.
+pub fn estimate_hashrate(difficulty: f64) -> f64 {
+    difficulty * 2f64.powi(32) / 600.0
+}
+
 pub fn cash_addr_to_script_type_payload(addr: &CashAddress) -> (ScriptType, [u8; 20]) {
     let script_type = match addr.addr_type() {
         AddressType::P2PKH => ScriptType::P2pkh,
@@ -86,3 +196,213 @@ pub fn cash_addr_to_script_type_payload(addr: &CashAddress) -> (ScriptType, [u8;
 
     (script_type, *script_payload)
 }
+
+/// Script type and payload Chronik indexes `script` under; scripts of no
+/// known type are indexed as a whole.
+pub fn script_type_payload(script: &[u8]) -> (ScriptType, Vec<u8>) {
+    match script {
+        [OP_DUP, OP_HASH160, 20, hash @ .., OP_EQUALVERIFY, OP_CHECKSIG] if hash.len() == 20 => {
+            (ScriptType::P2pkh, hash.to_vec())
+        }
+        [OP_HASH160, 20, hash @ .., OP_EQUAL] if hash.len() == 20 => {
+            (ScriptType::P2sh, hash.to_vec())
+        }
+        [33, pk @ .., OP_CHECKSIG] if pk.len() == 33 => (ScriptType::P2pk, pk.to_vec()),
+        [65, pk @ .., OP_CHECKSIG] if pk.len() == 65 => (ScriptType::P2pk, pk.to_vec()),
+        _ => (ScriptType::Other, script.to_vec()),
+    }
+}
+
+/// Name of `script_type` in `/script/:type/:payload` URLs.
+pub fn script_type_name(script_type: ScriptType) -> &'static str {
+    match script_type {
+        ScriptType::P2pkh => "p2pkh",
+        ScriptType::P2sh => "p2sh",
+        ScriptType::P2pk => "p2pk",
+        ScriptType::Other => "other",
+    }
+}
+
+pub fn parse_script_type(name: &str) -> Option<ScriptType> {
+    match name {
+        "p2pkh" => Some(ScriptType::P2pkh),
+        "p2sh" => Some(ScriptType::P2sh),
+        "p2pk" => Some(ScriptType::P2pk),
+        "other" => Some(ScriptType::Other),
+        _ => None,
+    }
+}
+
+/// Reconstructs the output script Chronik indexes under `script_type`.
+pub fn script_from_type_payload(script_type: ScriptType, payload: &[u8]) -> Vec<u8> {
+    match script_type {
+        ScriptType::P2pkh => [
+            &[OP_DUP, OP_HASH160, 20],
+            payload,
+            &[OP_EQUALVERIFY, OP_CHECKSIG],
+        ]
+        .concat(),
+        ScriptType::P2sh => [&[OP_HASH160, 20], payload, &[OP_EQUAL]].concat(),
+        ScriptType::P2pk => [&[payload.len() as u8], payload, &[OP_CHECKSIG]].concat(),
+        ScriptType::Other => payload.to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoinsuite_core::AddressType;
+
+    use super::{destination_from_script, destination_from_spent_script, Destination};
+    use crate::script::{OP_RIPEMD160, OP_SHA256};
+
+    const P2PKH_ADDRESS: &str = "ecash:qqg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyquz9y96w";
+
+    fn script(script_hex: &str) -> Vec<u8> {
+        hex::decode(script_hex.replace(' ', "")).unwrap()
+    }
+
+    fn p2pkh() -> String {
+        format!("76 a9 14 {} 88 ac", "11".repeat(20))
+    }
+
+    #[test]
+    fn test_destination_address() {
+        match destination_from_script("ecash", &script(&p2pkh())) {
+            Destination::Address(address) => assert_eq!(address.as_str(), P2PKH_ADDRESS),
+            destination => panic!("Unexpected {:?}", destination),
+        }
+        let p2sh = format!("a9 14 {} 87", "22".repeat(20));
+        match destination_from_script("etoken", &script(&p2sh)) {
+            Destination::Address(address) => {
+                assert!(matches!(address.addr_type(), AddressType::P2SH));
+                assert!(address.as_str().starts_with("etoken:p"));
+            }
+            destination => panic!("Unexpected {:?}", destination),
+        }
+    }
+
+    #[test]
+    fn test_destination_simple_scripts() {
+        let pubkey = "02".repeat(33);
+        let cases = vec![
+            (format!("21 {} ac", pubkey), "P2PK"),
+            (format!("41 {} ac", "04".repeat(65)), "P2PK"),
+            (format!("51 21 {} 21 {} 52 ae", pubkey, pubkey), "Multisig"),
+            ("6a 04 007461 62".to_string(), "Nulldata"),
+            ("".to_string(), "AnyoneCanSpend"),
+            ("51".to_string(), "AnyoneCanSpend"),
+            (format!("a8 20 {} 87", "33".repeat(32)), "HashPuzzle"),
+            (format!("a6 14 {} 87", "44".repeat(20)), "HashPuzzle"),
+            ("02 aa".to_string(), "Malformed"),
+            ("6a 02 aa".to_string(), "Malformed"),
+            ("52 53".to_string(), "Unknown"),
+            (format!("a8 14 {} 87", "44".repeat(20)), "Unknown"),
+        ];
+        for (script_hex, expected) in cases {
+            let destination = destination_from_script("ecash", &script(&script_hex));
+            let name = match &destination {
+                Destination::P2PK(_) => "P2PK",
+                Destination::Multisig(..) => "Multisig",
+                Destination::Nulldata(_) => "Nulldata",
+                Destination::AnyoneCanSpend => "AnyoneCanSpend",
+                Destination::HashPuzzle(..) => "HashPuzzle",
+                Destination::Malformed(_) => "Malformed",
+                Destination::Unknown(_) => "Unknown",
+                _ => "other",
+            };
+            assert_eq!(name, expected, "{}", script_hex);
+        }
+
+        match destination_from_script("ecash", &script(&format!("51 21 {} 51 ae", pubkey))) {
+            Destination::Multisig(1, pubkeys) => assert_eq!(pubkeys, vec![script(&pubkey)]),
+            destination => panic!("Unexpected {:?}", destination),
+        }
+        match destination_from_script("ecash", &script(&format!("a8 20 {} 87", "33".repeat(32)))) {
+            Destination::HashPuzzle(OP_SHA256, hash) => assert_eq!(hash, vec![0x33; 32]),
+            destination => panic!("Unexpected {:?}", destination),
+        }
+        match destination_from_script("ecash", &script(&format!("a6 14 {} 87", "44".repeat(20)))) {
+            Destination::HashPuzzle(OP_RIPEMD160, hash) => assert_eq!(hash, vec![0x44; 20]),
+            destination => panic!("Unexpected {:?}", destination),
+        }
+    }
+
+    #[test]
+    fn test_destination_time_locked() {
+        // Height lock in front of a P2PKH script
+        let locked = format!("03 a08601 b1 75 {}", p2pkh());
+        match destination_from_script("ecash", &script(&locked)) {
+            Destination::TimeLocked(100_000, inner) => match *inner {
+                Destination::Address(address) => assert_eq!(address.as_str(), P2PKH_ADDRESS),
+                destination => panic!("Unexpected {:?}", destination),
+            },
+            destination => panic!("Unexpected {:?}", destination),
+        }
+
+        // Small int locktime, and the largest operand CLTV accepts
+        let locked = format!("60 b1 75 05 ffffffff7f b1 75 {}", p2pkh());
+        match destination_from_script("ecash", &script(&locked)) {
+            Destination::TimeLocked(16, inner) => match *inner {
+                Destination::TimeLocked(0x7f_ffff_ffff, inner) => {
+                    assert!(matches!(*inner, Destination::Address(_)))
+                }
+                destination => panic!("Unexpected {:?}", destination),
+            },
+            destination => panic!("Unexpected {:?}", destination),
+        }
+
+        // CLTV fails for operands longer than 5 bytes
+        let locked = format!("08 ffffffffffffff7f b1 75 {}", p2pkh());
+        assert!(matches!(
+            destination_from_script("ecash", &script(&locked)),
+            Destination::Unknown(_)
+        ));
+
+        // Missing OP_DROP
+        let locked = format!("51 b1 {}", p2pkh());
+        assert!(matches!(
+            destination_from_script("ecash", &script(&locked)),
+            Destination::Unknown(_)
+        ));
+
+        // Deeply stacked locks are only unwrapped up to a limit
+        let locked = format!("{}{}", "51 b1 75 ".repeat(100_000), p2pkh());
+        let mut destination = destination_from_script("ecash", &script(&locked));
+        let mut num_locks = 0;
+        while let Destination::TimeLocked(1, inner) = destination {
+            num_locks += 1;
+            destination = *inner;
+        }
+        assert_eq!(num_locks, super::MAX_STACKED_TIME_LOCKS);
+        assert!(matches!(destination, Destination::Unknown(_)));
+    }
+
+    #[test]
+    fn test_destination_p2sh_wrapped() {
+        let p2sh = script(&format!("a9 14 {} 87", "22".repeat(20)));
+        let redeem_script = script(&format!("51 b1 75 {}", p2pkh()));
+        match destination_from_spent_script("ecash", &p2sh, Some(&redeem_script)) {
+            Destination::P2SHWrapped(address, redeem) => {
+                assert_eq!(address.as_str(), destination_address(&p2sh));
+                assert!(matches!(*redeem, Destination::TimeLocked(1, _)));
+            }
+            destination => panic!("Unexpected {:?}", destination),
+        }
+        // Only P2SH outputs have a redeem script
+        assert!(matches!(
+            destination_from_spent_script("ecash", &script(&p2pkh()), Some(&redeem_script)),
+            Destination::Address(_)
+        ));
+        assert!(matches!(
+            destination_from_spent_script("ecash", &p2sh, None),
+            Destination::Address(_)
+        ));
+    }
+
+    fn destination_address(script: &[u8]) -> String {
+        match destination_from_script("ecash", script) {
+            Destination::Address(address) => address.as_str().to_string(),
+            destination => panic!("Unexpected {:?}", destination),
+        }
+    }
+}
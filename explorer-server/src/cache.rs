@@ -0,0 +1,626 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use bitcoinsuite_chronik_client::{
+    proto::{Block, BlockInfo, BlockchainInfo, ScriptUtxos, Token, Tx, TxHistoryPage},
+    ScriptType,
+};
+use bitcoinsuite_core::{Bytes, Sha256d};
+use bitcoinsuite_error::Result;
+use lru::LruCache;
+use prost::Message;
+
+use crate::{backend::ExplorerBackend, config::CacheConfig};
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum CacheKey {
+    Block(Sha256d),
+    BlockHash(i32),
+    BlockInfo(i32),
+    Tx(Sha256d),
+    RawTx(Sha256d),
+    Token(Sha256d),
+}
+
+#[derive(Clone)]
+enum CacheValue {
+    Block(Block),
+    BlockHash(Sha256d),
+    BlockInfo(BlockInfo),
+    Tx(Tx),
+    RawTx(Bytes),
+    Token(Token),
+}
+
+struct CacheEntry {
+    value: CacheValue,
+    size: usize,
+    /// Height of the block the entry belongs to, used to drop it on reorgs.
+    height: Option<i32>,
+    expires_at: Option<Instant>,
+}
+
+/// LRU map bounded by the encoded size of its values rather than entry count.
+struct Tier {
+    entries: LruCache<CacheKey, CacheEntry>,
+    num_bytes: usize,
+    capacity_bytes: usize,
+}
+
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub immutable_entries: usize,
+    pub immutable_bytes: usize,
+    pub volatile_entries: usize,
+    pub volatile_bytes: usize,
+}
+
+/// Caching layer in front of an `ExplorerBackend`.
+///
+/// Data that can no longer change (raw txs, blocks and token geneses buried
+/// `finality_depth` blocks deep) lives in an immutable tier without expiry.
+/// Everything near the tip or in the mempool goes into a volatile tier whose
+/// entries expire after `volatile_ttl_secs`. Script history, UTXOs and the
+/// blockchain info, from which reorgs are detected, are never cached.
+pub struct CachedBackend {
+    inner: Box<dyn ExplorerBackend>,
+    config: CacheConfig,
+    immutable: Mutex<Tier>,
+    volatile: Mutex<Tier>,
+    tip: Mutex<Option<BlockchainInfo>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl Tier {
+    fn new(capacity_bytes: usize) -> Self {
+        Tier {
+            entries: LruCache::unbounded(),
+            num_bytes: 0,
+            capacity_bytes,
+        }
+    }
+
+    fn insert(&mut self, key: CacheKey, entry: CacheEntry) {
+        if entry.size > self.capacity_bytes {
+            return;
+        }
+        self.num_bytes += entry.size;
+        if let Some(old_entry) = self.entries.put(key, entry) {
+            self.num_bytes -= old_entry.size;
+        }
+        while self.num_bytes > self.capacity_bytes {
+            match self.entries.pop_lru() {
+                Some((_, evicted)) => self.num_bytes -= evicted.size,
+                None => break,
+            }
+        }
+    }
+
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(entry) = self.entries.pop(key) {
+            self.num_bytes -= entry.size;
+        }
+    }
+
+    fn remove_from_height(&mut self, height: i32) {
+        let keys = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.height.map(|h| h >= height).unwrap_or(false))
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        for key in keys.iter() {
+            self.remove(key);
+        }
+    }
+}
+
+impl CachedBackend {
+    pub fn new(inner: impl ExplorerBackend + 'static, config: CacheConfig) -> Self {
+        CachedBackend {
+            inner: Box::new(inner),
+            immutable: Mutex::new(Tier::new(config.immutable_capacity_bytes)),
+            volatile: Mutex::new(Tier::new(config.volatile_capacity_bytes)),
+            config,
+            tip: Mutex::new(None),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// The wrapped backend, for queries that must not be served stale.
+    pub fn uncached(&self) -> &dyn ExplorerBackend {
+        self.inner.as_ref()
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let immutable = self.immutable.lock().unwrap();
+        let volatile = self.volatile.lock().unwrap();
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            immutable_entries: immutable.entries.len(),
+            immutable_bytes: immutable.num_bytes,
+            volatile_entries: volatile.entries.len(),
+            volatile_bytes: volatile.num_bytes,
+        }
+    }
+
+    /// Drops every cached entry belonging to a block at `height` or above,
+    /// e.g. after those blocks have been disconnected.
+    pub fn invalidate_from_height(&self, height: i32) {
+        self.immutable.lock().unwrap().remove_from_height(height);
+        self.volatile.lock().unwrap().remove_from_height(height);
+    }
+
+    fn get(&self, key: &CacheKey) -> Option<CacheValue> {
+        let value = self.get_entry(key);
+        match value {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        value
+    }
+
+    fn get_entry(&self, key: &CacheKey) -> Option<CacheValue> {
+        if let Some(entry) = self.immutable.lock().unwrap().entries.get(key) {
+            return Some(entry.value.clone());
+        }
+        let mut volatile = self.volatile.lock().unwrap();
+        let is_expired = match volatile.entries.get(key) {
+            Some(entry) => entry
+                .expires_at
+                .map(|t| t <= Instant::now())
+                .unwrap_or(false),
+            None => return None,
+        };
+        if is_expired {
+            volatile.remove(key);
+            return None;
+        }
+        volatile.entries.get(key).map(|entry| entry.value.clone())
+    }
+
+    /// Stores `value`, in the immutable tier if `height` is final and in the
+    /// volatile tier otherwise.
+    fn insert(&self, key: CacheKey, value: CacheValue, size: usize, height: Option<i32>) {
+        let is_final = match (height, self.tip_height()) {
+            (Some(height), Some(tip_height)) => {
+                tip_height - height + 1 >= self.config.finality_depth
+            }
+            _ => false,
+        };
+        if is_final {
+            let entry = CacheEntry {
+                value,
+                size,
+                height,
+                expires_at: None,
+            };
+            self.immutable.lock().unwrap().insert(key, entry);
+        } else {
+            self.insert_volatile(key, value, size, height);
+        }
+    }
+
+    fn insert_volatile(&self, key: CacheKey, value: CacheValue, size: usize, height: Option<i32>) {
+        let entry = CacheEntry {
+            value,
+            size,
+            height,
+            expires_at: Some(Instant::now() + Duration::from_secs(self.config.volatile_ttl_secs)),
+        };
+        self.volatile.lock().unwrap().insert(key, entry);
+    }
+
+    fn insert_immutable(&self, key: CacheKey, value: CacheValue, size: usize) {
+        let entry = CacheEntry {
+            value,
+            size,
+            height: None,
+            expires_at: None,
+        };
+        self.immutable.lock().unwrap().insert(key, entry);
+    }
+
+    fn tip_height(&self) -> Option<i32> {
+        self.tip.lock().unwrap().as_ref().map(|tip| tip.tip_height)
+    }
+
+    /// Remembers the latest tip and invalidates recent blocks if the chain
+    /// switched to a different branch since the last observation, i.e. if
+    /// the previous tip isn't part of the chain anymore.
+    async fn observe_tip(&self, blockchain_info: &BlockchainInfo) -> Result<()> {
+        let prev_tip = self.tip.lock().unwrap().clone();
+        if let Some(prev_tip) = prev_tip {
+            let is_reorg = if blockchain_info.tip_hash == prev_tip.tip_hash {
+                false
+            } else if blockchain_info.tip_height > prev_tip.tip_height {
+                let prev_tip_blocks = self
+                    .inner
+                    .blocks(prev_tip.tip_height, prev_tip.tip_height)
+                    .await?;
+                prev_tip_blocks.first().map(|block_info| &block_info.hash)
+                    != Some(&prev_tip.tip_hash)
+            } else {
+                true
+            };
+            if is_reorg {
+                let fork_height = blockchain_info.tip_height.min(prev_tip.tip_height)
+                    - self.config.finality_depth;
+                self.immutable
+                    .lock()
+                    .unwrap()
+                    .remove_from_height(fork_height);
+                self.volatile
+                    .lock()
+                    .unwrap()
+                    .remove_from_height(fork_height);
+            }
+        }
+        *self.tip.lock().unwrap() = Some(blockchain_info.clone());
+        Ok(())
+    }
+
+    fn insert_block(&self, block: &Block) {
+        if let Some(block_info) = &block.block_info {
+            let block_hash = Sha256d::from_slice(&block_info.hash).expect("Impossible");
+            self.insert(
+                CacheKey::Block(block_hash),
+                CacheValue::Block(block.clone()),
+                block.encoded_len(),
+                Some(block_info.height),
+            );
+        }
+    }
+}
+
+#[async_trait]
+impl ExplorerBackend for CachedBackend {
+    async fn blockchain_info(&self) -> Result<BlockchainInfo> {
+        let blockchain_info = self.inner.blockchain_info().await?;
+        self.observe_tip(&blockchain_info).await?;
+        Ok(blockchain_info)
+    }
+
+    async fn block_by_height(&self, height: i32) -> Result<Block> {
+        if let Some(CacheValue::BlockHash(hash)) = self.get(&CacheKey::BlockHash(height)) {
+            return self.block_by_hash(&hash).await;
+        }
+        let block = self.inner.block_by_height(height).await?;
+        if let Some(block_info) = &block.block_info {
+            let block_hash = Sha256d::from_slice(&block_info.hash).expect("Impossible");
+            self.insert(
+                CacheKey::BlockHash(height),
+                CacheValue::BlockHash(block_hash),
+                block_info.hash.len(),
+                Some(height),
+            );
+        }
+        self.insert_block(&block);
+        Ok(block)
+    }
+
+    async fn block_by_hash(&self, hash: &Sha256d) -> Result<Block> {
+        if let Some(CacheValue::Block(block)) = self.get(&CacheKey::Block(hash.clone())) {
+            return Ok(block);
+        }
+        let block = self.inner.block_by_hash(hash).await?;
+        self.insert_block(&block);
+        Ok(block)
+    }
+
+    async fn blocks(&self, start_height: i32, end_height: i32) -> Result<Vec<BlockInfo>> {
+        let mut cached_blocks = Vec::new();
+        for height in start_height..=end_height {
+            match self.get(&CacheKey::BlockInfo(height)) {
+                Some(CacheValue::BlockInfo(block_info)) => cached_blocks.push(block_info),
+                _ => break,
+            }
+        }
+        if cached_blocks.len() as i32 == (end_height - start_height + 1).max(0) {
+            return Ok(cached_blocks);
+        }
+        let blocks = self.inner.blocks(start_height, end_height).await?;
+        for block_info in blocks.iter() {
+            self.insert(
+                CacheKey::BlockInfo(block_info.height),
+                CacheValue::BlockInfo(block_info.clone()),
+                block_info.encoded_len(),
+                Some(block_info.height),
+            );
+        }
+        Ok(blocks)
+    }
+
+    async fn tx(&self, txid: &Sha256d) -> Result<Tx> {
+        if let Some(CacheValue::Tx(tx)) = self.get(&CacheKey::Tx(txid.clone())) {
+            return Ok(tx);
+        }
+        let tx = self.inner.tx(txid).await?;
+        // Always volatile: `spent_by` changes long after confirmation.
+        self.insert_volatile(
+            CacheKey::Tx(txid.clone()),
+            CacheValue::Tx(tx.clone()),
+            tx.encoded_len(),
+            tx.block.as_ref().map(|block| block.height),
+        );
+        Ok(tx)
+    }
+
+    async fn raw_tx(&self, txid: &Sha256d) -> Result<Bytes> {
+        if let Some(CacheValue::RawTx(raw_tx)) = self.get(&CacheKey::RawTx(txid.clone())) {
+            return Ok(raw_tx);
+        }
+        let raw_tx = self.inner.raw_tx(txid).await?;
+        self.insert_immutable(
+            CacheKey::RawTx(txid.clone()),
+            CacheValue::RawTx(raw_tx.clone()),
+            raw_tx.as_ref().len(),
+        );
+        Ok(raw_tx)
+    }
+
+    async fn token(&self, token_id: &Sha256d) -> Result<Token> {
+        if let Some(CacheValue::Token(token)) = self.get(&CacheKey::Token(token_id.clone())) {
+            return Ok(token);
+        }
+        let token = self.inner.token(token_id).await?;
+        // Genesis info is fixed once mined; `token_stats` may lag behind, so
+        // callers needing live stats should go through `uncached`.
+        let height = token.block.as_ref().map(|block| block.height);
+        self.insert(
+            CacheKey::Token(token_id.clone()),
+            CacheValue::Token(token.clone()),
+            token.encoded_len(),
+            height,
+        );
+        Ok(token)
+    }
+
+    async fn script_history(
+        &self,
+        script_type: ScriptType,
+        script_payload: &[u8],
+        page: usize,
+        page_size: usize,
+    ) -> Result<TxHistoryPage> {
+        self.inner
+            .script_history(script_type, script_payload, page, page_size)
+            .await
+    }
+
+    async fn script_utxos(
+        &self,
+        script_type: ScriptType,
+        script_payload: &[u8],
+    ) -> Result<Vec<ScriptUtxos>> {
+        self.inner.script_utxos(script_type, script_payload).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+    use bitcoinsuite_chronik_client::{
+        proto::{Block, BlockInfo, BlockchainInfo, ScriptUtxos, Token, Tx, TxHistoryPage},
+        ScriptType,
+    };
+    use bitcoinsuite_core::{Bytes, Sha256d};
+    use bitcoinsuite_error::Result;
+    use prost::Message;
+    use tokio::sync::RwLock;
+
+    use super::CachedBackend;
+    use crate::{
+        backend::{ExplorerBackend, FixtureBackend},
+        config::CacheConfig,
+    };
+
+    /// `FixtureBackend` that can still be changed once wrapped in the cache.
+    #[derive(Clone, Default)]
+    struct SharedBackend(Arc<RwLock<FixtureBackend>>);
+
+    #[async_trait]
+    impl ExplorerBackend for SharedBackend {
+        async fn blockchain_info(&self) -> Result<BlockchainInfo> {
+            self.0.read().await.blockchain_info().await
+        }
+
+        async fn block_by_height(&self, height: i32) -> Result<Block> {
+            self.0.read().await.block_by_height(height).await
+        }
+
+        async fn block_by_hash(&self, hash: &Sha256d) -> Result<Block> {
+            self.0.read().await.block_by_hash(hash).await
+        }
+
+        async fn blocks(&self, start_height: i32, end_height: i32) -> Result<Vec<BlockInfo>> {
+            self.0.read().await.blocks(start_height, end_height).await
+        }
+
+        async fn tx(&self, txid: &Sha256d) -> Result<Tx> {
+            self.0.read().await.tx(txid).await
+        }
+
+        async fn raw_tx(&self, txid: &Sha256d) -> Result<Bytes> {
+            self.0.read().await.raw_tx(txid).await
+        }
+
+        async fn token(&self, token_id: &Sha256d) -> Result<Token> {
+            self.0.read().await.token(token_id).await
+        }
+
+        async fn script_history(
+            &self,
+            script_type: ScriptType,
+            script_payload: &[u8],
+            page: usize,
+            page_size: usize,
+        ) -> Result<TxHistoryPage> {
+            let backend = self.0.read().await;
+            backend
+                .script_history(script_type, script_payload, page, page_size)
+                .await
+        }
+
+        async fn script_utxos(
+            &self,
+            script_type: ScriptType,
+            script_payload: &[u8],
+        ) -> Result<Vec<ScriptUtxos>> {
+            let backend = self.0.read().await;
+            backend.script_utxos(script_type, script_payload).await
+        }
+    }
+
+    impl SharedBackend {
+        async fn insert_chain(&self, heights: std::ops::RangeInclusive<i32>, fork: u8) {
+            let mut backend = self.0.write().await;
+            for height in heights {
+                backend.insert_block(block(height, fork));
+            }
+        }
+    }
+
+    fn block(height: i32, fork: u8) -> Block {
+        let mut hash = [fork; 32];
+        hash[..4].copy_from_slice(&height.to_le_bytes());
+        Block {
+            block_info: Some(BlockInfo {
+                hash: hash.to_vec(),
+                height,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn hash(height: i32, fork: u8) -> Sha256d {
+        Sha256d::from_slice(&block(height, fork).block_info.unwrap().hash).unwrap()
+    }
+
+    fn fork_of(block: &Block) -> u8 {
+        block.block_info.as_ref().unwrap().hash[31]
+    }
+
+    fn config() -> CacheConfig {
+        CacheConfig {
+            volatile_ttl_secs: 3600,
+            finality_depth: 3,
+            ..CacheConfig::default()
+        }
+    }
+
+    async fn setup(config: CacheConfig) -> (SharedBackend, CachedBackend) {
+        let backend = SharedBackend::default();
+        backend.insert_chain(0..=10, 0).await;
+        let cache = CachedBackend::new(backend.clone(), config);
+        cache.blockchain_info().await.unwrap();
+        (backend, cache)
+    }
+
+    #[tokio::test]
+    async fn test_lru_size_eviction() -> Result<()> {
+        let block_size = block(1, 0).encoded_len();
+        let (_, cache) = setup(CacheConfig {
+            immutable_capacity_bytes: 2 * block_size,
+            ..config()
+        })
+        .await;
+        cache.block_by_hash(&hash(1, 0)).await?;
+        cache.block_by_hash(&hash(2, 0)).await?;
+        // Used most recently, so block 2 gets evicted instead
+        cache.block_by_hash(&hash(1, 0)).await?;
+        cache.block_by_hash(&hash(3, 0)).await?;
+        let stats = cache.stats();
+        assert_eq!(stats.immutable_entries, 2);
+        assert_eq!(stats.immutable_bytes, 2 * block_size);
+        assert_eq!((stats.hits, stats.misses), (1, 3));
+
+        cache.block_by_hash(&hash(1, 0)).await?;
+        cache.block_by_hash(&hash(2, 0)).await?;
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (2, 4));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_volatile_ttl_expiry() -> Result<()> {
+        for (volatile_ttl_secs, expected_hits) in [(0, 0), (3600, 1)] {
+            let (_, cache) = setup(CacheConfig {
+                volatile_ttl_secs,
+                ..config()
+            })
+            .await;
+            cache.block_by_hash(&hash(10, 0)).await?;
+            cache.block_by_hash(&hash(10, 0)).await?;
+            assert_eq!(cache.stats().hits, expected_hits, "{}", volatile_ttl_secs);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_finality_depth() -> Result<()> {
+        let (_, cache) = setup(config()).await;
+        // 3 confirmations at height 8 with the tip at 10
+        cache.block_by_hash(&hash(8, 0)).await?;
+        let stats = cache.stats();
+        assert_eq!((stats.immutable_entries, stats.volatile_entries), (1, 0));
+
+        cache.block_by_hash(&hash(9, 0)).await?;
+        let stats = cache.stats();
+        assert_eq!((stats.immutable_entries, stats.volatile_entries), (1, 1));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_on_same_height_reorg() -> Result<()> {
+        let (backend, cache) = setup(config()).await;
+        assert_eq!(fork_of(&cache.block_by_height(9).await?), 0);
+        assert_eq!(fork_of(&cache.block_by_height(10).await?), 0);
+
+        backend.insert_chain(9..=10, 1).await;
+        cache.blockchain_info().await?;
+        assert_eq!(fork_of(&cache.block_by_height(9).await?), 1);
+        assert_eq!(fork_of(&cache.block_by_height(10).await?), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_on_longer_chain_reorg() -> Result<()> {
+        let (backend, cache) = setup(config()).await;
+        assert_eq!(fork_of(&cache.block_by_height(9).await?), 0);
+        assert_eq!(fork_of(&cache.block_by_height(10).await?), 0);
+
+        // The new tip is higher, but the old one isn't part of its chain
+        backend.insert_chain(9..=12, 1).await;
+        let blockchain_info = cache.blockchain_info().await?;
+        assert_eq!(blockchain_info.tip_height, 12);
+        assert_eq!(fork_of(&cache.block_by_height(9).await?), 1);
+        assert_eq!(fork_of(&cache.block_by_height(10).await?), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_keep_cache_on_extension() -> Result<()> {
+        let (backend, cache) = setup(config()).await;
+        cache.block_by_height(9).await?;
+        let num_entries = cache.stats().volatile_entries;
+
+        backend.insert_chain(11..=12, 0).await;
+        cache.blockchain_info().await?;
+        assert_eq!(cache.stats().volatile_entries, num_entries);
+        Ok(())
+    }
+}
@@ -0,0 +1,209 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use bitcoinsuite_chronik_client::proto::Block;
+use bitcoinsuite_error::Result;
+use eyre::{eyre, Report};
+
+use crate::{
+    api::{calc_tx_stats, token_tx_counts},
+    blockchain::{calculate_block_difficulty, estimate_hashrate},
+    server_primitives::{JsonChartPoint, JsonChartSeries, JsonTokenTxCounts},
+};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Time series charted on the charts page, aggregated per UTC day.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChartSeries {
+    Blocks,
+    Txs,
+    BlockSize,
+    Difficulty,
+    Hashrate,
+    Fees,
+    TokenTxs,
+}
+
+pub const ALL_CHART_SERIES: [ChartSeries; 7] = [
+    ChartSeries::Blocks,
+    ChartSeries::Txs,
+    ChartSeries::BlockSize,
+    ChartSeries::Difficulty,
+    ChartSeries::Hashrate,
+    ChartSeries::Fees,
+    ChartSeries::TokenTxs,
+];
+
+/// Numbers of a single block the series are aggregated from.
+#[derive(Clone)]
+pub struct BlockSummary {
+    timestamp: i64,
+    size: u64,
+    num_txs: u64,
+    difficulty: f64,
+    total_fee: i64,
+    num_token_txs: JsonTokenTxCounts,
+}
+
+/// Summaries of recent blocks by height, filled in as blocks arrive so
+/// charts never have to refetch full blocks.
+#[derive(Default)]
+pub struct ChainStats {
+    blocks: BTreeMap<i32, BlockSummary>,
+}
+
+#[derive(Default)]
+struct DayTotals {
+    num_blocks: u64,
+    num_txs: u64,
+    total_size: u64,
+    total_difficulty: f64,
+    total_fee: i64,
+    num_token_txs: JsonTokenTxCounts,
+}
+
+impl ChartSeries {
+    pub fn title(&self) -> &'static str {
+        match self {
+            ChartSeries::Blocks => "Blocks per day",
+            ChartSeries::Txs => "Transactions per day",
+            ChartSeries::BlockSize => "Average block size (bytes)",
+            ChartSeries::Difficulty => "Average difficulty",
+            ChartSeries::Hashrate => "Estimated hashrate (H/s)",
+            ChartSeries::Fees => "Fees per day (sats)",
+            ChartSeries::TokenTxs => "Token transactions per day",
+        }
+    }
+
+    fn labels(&self) -> Vec<String> {
+        let labels: &[&str] = match self {
+            ChartSeries::Blocks => &["Blocks"],
+            ChartSeries::Txs => &["Transactions"],
+            ChartSeries::BlockSize => &["Size"],
+            ChartSeries::Difficulty => &["Difficulty"],
+            ChartSeries::Hashrate => &["Hashrate"],
+            ChartSeries::Fees => &["Fees"],
+            ChartSeries::TokenTxs => &["GENESIS", "MINT", "SEND", "BURN"],
+        };
+        labels.iter().map(|label| label.to_string()).collect()
+    }
+
+    fn values(&self, day: &DayTotals) -> Vec<f64> {
+        let num_blocks = day.num_blocks.max(1) as f64;
+        match self {
+            ChartSeries::Blocks => vec![day.num_blocks as f64],
+            ChartSeries::Txs => vec![day.num_txs as f64],
+            ChartSeries::BlockSize => vec![day.total_size as f64 / num_blocks],
+            ChartSeries::Difficulty => vec![day.total_difficulty / num_blocks],
+            ChartSeries::Hashrate => vec![estimate_hashrate(day.total_difficulty / num_blocks)],
+            ChartSeries::Fees => vec![day.total_fee as f64],
+            ChartSeries::TokenTxs => vec![
+                day.num_token_txs.genesis as f64,
+                day.num_token_txs.mint as f64,
+                day.num_token_txs.send as f64,
+                day.num_token_txs.burn as f64,
+            ],
+        }
+    }
+}
+
+impl FromStr for ChartSeries {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        ALL_CHART_SERIES
+            .iter()
+            .copied()
+            .find(|series| series.to_string() == s)
+            .ok_or_else(|| eyre!("Unknown series: {}", s))
+    }
+}
+
+impl fmt::Display for ChartSeries {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ChartSeries::Blocks => "blocks",
+            ChartSeries::Txs => "txs",
+            ChartSeries::BlockSize => "block-size",
+            ChartSeries::Difficulty => "difficulty",
+            ChartSeries::Hashrate => "hashrate",
+            ChartSeries::Fees => "fees",
+            ChartSeries::TokenTxs => "token-txs",
+        })
+    }
+}
+
+impl BlockSummary {
+    pub fn new(block: &Block) -> Result<(i32, Self)> {
+        let block_info = block
+            .block_info
+            .as_ref()
+            .ok_or_else(|| eyre!("Block has no info"))?;
+        let summary = BlockSummary {
+            timestamp: block_info.timestamp,
+            size: block_info.block_size,
+            num_txs: block_info.num_txs,
+            difficulty: calculate_block_difficulty(block_info.n_bits),
+            total_fee: block.txs.iter().map(|tx| calc_tx_stats(tx, None).fee).sum(),
+            num_token_txs: token_tx_counts(&block.txs),
+        };
+        Ok((block_info.height, summary))
+    }
+}
+
+impl ChainStats {
+    pub fn insert(&mut self, height: i32, summary: BlockSummary) {
+        self.blocks.insert(height, summary);
+    }
+
+    /// Drops the blocks at and above `height`, after they got reorged.
+    pub fn remove_from_height(&mut self, height: i32) {
+        self.blocks.split_off(&height);
+    }
+
+    /// Drops the blocks below `height`, which are out of the charted range.
+    pub fn remove_below_height(&mut self, height: i32) {
+        self.blocks = self.blocks.split_off(&height);
+    }
+
+    /// Heights between `start_height` and `end_height` (inclusive) that have
+    /// no summary yet, newest first.
+    pub fn missing_heights(&self, start_height: i32, end_height: i32) -> Vec<i32> {
+        (start_height..=end_height)
+            .rev()
+            .filter(|height| !self.blocks.contains_key(height))
+            .collect()
+    }
+
+    /// `series` over the last `num_days` days that have blocks, oldest first.
+    pub fn series(&self, series: ChartSeries, num_days: usize) -> JsonChartSeries {
+        let mut days = BTreeMap::<i64, DayTotals>::new();
+        for summary in self.blocks.values() {
+            let day_start = summary.timestamp - summary.timestamp.rem_euclid(SECONDS_PER_DAY);
+            let day = days.entry(day_start).or_default();
+            day.num_blocks += 1;
+            day.num_txs += summary.num_txs;
+            day.total_size += summary.size;
+            day.total_difficulty += summary.difficulty;
+            day.total_fee += summary.total_fee;
+            day.num_token_txs.genesis += summary.num_token_txs.genesis;
+            day.num_token_txs.mint += summary.num_token_txs.mint;
+            day.num_token_txs.send += summary.num_token_txs.send;
+            day.num_token_txs.burn += summary.num_token_txs.burn;
+        }
+        let num_skipped = days.len().saturating_sub(num_days);
+        JsonChartSeries {
+            series: series.to_string(),
+            title: series.title(),
+            labels: series.labels(),
+            points: days
+                .iter()
+                .skip(num_skipped)
+                .map(|(&timestamp, day)| JsonChartPoint {
+                    timestamp,
+                    values: series.values(day),
+                })
+                .collect(),
+        }
+    }
+}
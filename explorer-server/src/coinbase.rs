@@ -0,0 +1,43 @@
+use crate::script::{decode_script_num, parse_script, ScriptOp, OP_1, OP_16};
+
+/// Minimum length of a printable ASCII run to count as a coinbase tag.
+const MIN_TAG_LEN: usize = 4;
+
+/// Data miners put into the coinbase input script.
+#[derive(Clone, Debug, Default)]
+pub struct CoinbaseInfo {
+    /// Height committed to as required by BIP34.
+    pub height: Option<i64>,
+    /// First push after the height that isn't a tag.
+    pub extranonce: Option<Vec<u8>>,
+    /// Runs of printable ASCII, usually naming the pool.
+    pub tags: Vec<String>,
+}
+
+pub fn parse_coinbase(script: &[u8]) -> CoinbaseInfo {
+    let ops = parse_script(script);
+    let (height, height_len) = match ops.first() {
+        Some(ScriptOp::Push { opcode, data }) if *opcode as usize == data.len() => {
+            (decode_script_num(data), 1 + data.len())
+        }
+        Some(ScriptOp::Code(opcode @ OP_1..=OP_16)) => (Some((opcode - OP_1 + 1) as i64), 1),
+        _ => (None, 0),
+    };
+    let rest = &script[height_len..];
+    let extranonce = parse_script(rest).into_iter().find_map(|op| match op {
+        ScriptOp::Push { data, .. } if ascii_runs(&data).is_empty() => Some(data),
+        _ => None,
+    });
+    CoinbaseInfo {
+        height,
+        extranonce,
+        tags: ascii_runs(rest),
+    }
+}
+
+fn ascii_runs(data: &[u8]) -> Vec<String> {
+    data.split(|byte| !(byte.is_ascii_graphic() || *byte == b' '))
+        .map(|run| String::from_utf8_lossy(run).trim().to_string())
+        .filter(|run| run.len() >= MIN_TAG_LEN)
+        .collect()
+}
@@ -3,11 +3,61 @@ use std::{net::SocketAddr, path::PathBuf};
 use bitcoinsuite_error::Result;
 use serde::Deserialize;
 
+use crate::reward::CoinbaseRule;
+
 #[derive(Deserialize)]
 pub struct Config {
     pub host: SocketAddr,
     pub chronik_api_url: String,
     pub base_dir: Option<PathBuf>,
+    /// TOML file listing known mining pools, see `pools::PoolRegistry`.
+    pub pools_file: Option<PathBuf>,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    #[serde(default)]
+    pub coinbase_rules: Vec<CoinbaseRule>,
+    #[serde(default)]
+    pub charts: ChartsConfig,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct CacheConfig {
+    pub immutable_capacity_bytes: usize,
+    pub volatile_capacity_bytes: usize,
+    pub volatile_ttl_secs: u64,
+    /// Number of confirmations after which a block is assumed to never be
+    /// reorged and can be cached indefinitely.
+    pub finality_depth: i32,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            immutable_capacity_bytes: 256 * 1024 * 1024,
+            volatile_capacity_bytes: 32 * 1024 * 1024,
+            volatile_ttl_secs: 5,
+            finality_depth: 10,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct ChartsConfig {
+    /// Number of days of blocks summarized for the charts page.
+    pub history_days: i32,
+    /// Seconds between checks for blocks missing from the charts.
+    pub sync_interval_secs: u64,
+}
+
+impl Default for ChartsConfig {
+    fn default() -> Self {
+        ChartsConfig {
+            history_days: 90,
+            sync_interval_secs: 60,
+        }
+    }
 }
 
 pub fn load_config(config_string: &str) -> Result<Config> {
@@ -0,0 +1,268 @@
+use bitcoinsuite_chronik_client::proto::BlockInfo;
+
+use crate::blockchain::calculate_block_difficulty;
+
+/// Target time between blocks, in seconds.
+const TARGET_BLOCK_TIME: i64 = 600;
+
+/// Time after which ASERT halves or doubles the difficulty if no block or a
+/// block every second respectively is found, in seconds.
+const ASERT_HALF_LIFE: i64 = 2 * 24 * 60 * 60;
+
+/// Block ASERT difficulty adjustments are anchored to (the first block of the
+/// November 2020 upgrade), its nBits and the timestamp of its parent.
+const MAINNET_ANCHOR: AsertAnchor = AsertAnchor {
+    height: 661_647,
+    bits: 0x1804_dafe,
+    parent_time: 1_605_447_844,
+};
+
+/// nBits of the highest allowed target.
+const POW_LIMIT_BITS: u32 = 0x1d00_ffff;
+
+/// Targets of `1 << POW_LIMIT_SHIFT` and above exceed the pow limit.
+const POW_LIMIT_SHIFT: i64 = 224;
+
+struct AsertAnchor {
+    height: i32,
+    bits: u32,
+    parent_time: i64,
+}
+
+/// What ASERT expects of the block following the tip.
+#[derive(Clone, Copy, Debug)]
+pub struct AsertExpectation {
+    pub next_bits: u32,
+    pub next_difficulty: f64,
+    /// How far the tip is behind (positive) or ahead (negative) of the ideal
+    /// schedule of one block every 10 minutes since the anchor, in seconds.
+    pub schedule_offset_secs: i64,
+}
+
+/// Computes the target of the block after `tip` like aserti3-2d does, using
+/// integer math so `next_bits` matches what nodes will require.
+pub fn asert_expectation(tip: &BlockInfo) -> AsertExpectation {
+    let (next_bits, schedule_offset_secs) =
+        asert_next_bits(&MAINNET_ANCHOR, tip.height, tip.timestamp);
+    AsertExpectation {
+        next_bits,
+        next_difficulty: calculate_block_difficulty(next_bits),
+        schedule_offset_secs,
+    }
+}
+
+/// nBits of the block after the tip at `tip_height` and `tip_timestamp`,
+/// and the tip's schedule offset.
+fn asert_next_bits(anchor: &AsertAnchor, tip_height: i32, tip_timestamp: i64) -> (u32, i64) {
+    let time_diff = tip_timestamp - anchor.parent_time;
+    let height_diff = (tip_height - anchor.height) as i64;
+    let schedule_offset_secs = time_diff - TARGET_BLOCK_TIME * (height_diff + 1);
+
+    let exponent = schedule_offset_secs * 65536 / ASERT_HALF_LIFE;
+    let shifts = exponent >> 16;
+    let frac = (exponent as u16) as u128;
+    let factor = 65536
+        + ((195_766_423_245_049 * frac
+            + 971_821_376 * frac * frac
+            + 5127 * frac * frac * frac
+            + (1 << 47))
+            >> 48);
+
+    // The anchor target is `mantissa << anchor_shift` and the next target is
+    // `(anchor_target * factor) << shifts >> 16`. Mantissa and factor are
+    // small enough that no bits are lost as long as the result is at least
+    // 2^16 times the mantissa.
+    let anchor_mantissa = (anchor.bits & 0x007f_ffff) as u128;
+    let anchor_shift = 8 * ((anchor.bits >> 24) as i64 - 3);
+    let shift = anchor_shift + shifts - 16;
+    // Checked up front, as the compact size of such targets can overflow
+    if shift >= POW_LIMIT_SHIFT {
+        return (POW_LIMIT_BITS, schedule_offset_secs);
+    }
+    let next_bits = compact_from_shifted(anchor_mantissa * factor, shift);
+    let next_bits = if exceeds_pow_limit(next_bits) {
+        POW_LIMIT_BITS
+    } else {
+        next_bits
+    };
+    (next_bits, schedule_offset_secs)
+}
+
+/// Hashrate that mined `blocks` (oldest first): the work of every block but
+/// the first, over the time since the first.
+pub fn estimate_window_hashrate(blocks: &[BlockInfo]) -> Option<f64> {
+    let (first, last) = (blocks.first()?, blocks.last()?);
+    let timespan = last.timestamp - first.timestamp;
+    if timespan <= 0 {
+        return None;
+    }
+    let work = blocks[1..]
+        .iter()
+        .map(|block| calculate_block_difficulty(block.n_bits) * 2f64.powi(32))
+        .sum::<f64>();
+    Some(work / timespan as f64)
+}
+
+/// Average time between `blocks` (oldest first), in seconds.
+pub fn average_block_time(blocks: &[BlockInfo]) -> Option<f64> {
+    let (first, last) = (blocks.first()?, blocks.last()?);
+    if blocks.len() < 2 {
+        return None;
+    }
+    Some((last.timestamp - first.timestamp) as f64 / (blocks.len() - 1) as f64)
+}
+
+/// Encodes `value << shift` (or `>> -shift`) in compact nBits form.
+fn compact_from_shifted(value: u128, shift: i64) -> u32 {
+    let value = if shift < 0 {
+        value >> (-shift).min(127)
+    } else {
+        value
+    };
+    let shift = shift.max(0);
+    if value == 0 {
+        // Targets are at least 1
+        return 0x0101_0000;
+    }
+    let num_bits = 128 - value.leading_zeros() as i64 + shift;
+    let mut size = (num_bits + 7) / 8;
+    let mantissa_shift = 8 * (size - 3) - shift;
+    let mut mantissa = if mantissa_shift >= 0 {
+        value >> mantissa_shift
+    } else {
+        value << -mantissa_shift
+    } as u32;
+    if mantissa & 0x0080_0000 != 0 {
+        mantissa >>= 8;
+        size += 1;
+    }
+    ((size as u32) << 24) | mantissa
+}
+
+fn exceeds_pow_limit(bits: u32) -> bool {
+    let size = bits >> 24;
+    let mantissa = bits & 0x007f_ffff;
+    let limit_size = POW_LIMIT_BITS >> 24;
+    let limit_mantissa = POW_LIMIT_BITS & 0x007f_ffff;
+    size > limit_size || (size == limit_size && mantissa > limit_mantissa)
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoinsuite_chronik_client::proto::BlockInfo;
+
+    use super::{
+        asert_expectation, asert_next_bits, compact_from_shifted, exceeds_pow_limit, AsertAnchor,
+        MAINNET_ANCHOR, POW_LIMIT_BITS,
+    };
+
+    const HALF_LIFE: i64 = 172_800;
+
+    #[test]
+    fn test_asert_mainnet_anchor() {
+        // (height offset from the anchor, tip time minus the anchor's parent
+        // time, expected nBits), computed with the aserti3-2d reference
+        // algorithm on 256-bit targets
+        let cases = [
+            // On schedule
+            (0, 600, 0x1804_dafe),
+            (50_000, 50_001 * 600 + 1, 0x1804_dafe),
+            (50_000, 50_001 * 600 - 1, 0x1804_dafe),
+            (0, 599, 0x1804_dafe),
+            // One half-life behind and ahead of schedule
+            (0, 600 + HALF_LIFE, 0x1809_b5fc),
+            (0, 600 - HALF_LIFE, 0x1802_6d7f),
+            // Fractional half-lives
+            (0, 600 + HALF_LIFE / 2, 0x1806_ddb4),
+            (1000, 1001 * 600 + 12_345, 0x1805_1a35),
+            (1000, 1001 * 600 - 3600, 0x1804_c938),
+            // Whole half-lives shift the target
+            (10, 11 * 600 + 20 * HALF_LIFE, 0x1a4d_afe0),
+            (10, 11 * 600 - 40 * HALF_LIFE, 0x1304_dafe),
+            // Clamped to the pow limit
+            (10, 11 * 600 + 40 * HALF_LIFE, POW_LIMIT_BITS),
+            (
+                10,
+                4_000_000_000 - MAINNET_ANCHOR.parent_time,
+                POW_LIMIT_BITS,
+            ),
+            // Target rounded down to zero, raised to 1
+            (10, 11 * 600 - 300 * HALF_LIFE, 0x0101_0000),
+        ];
+        for (height_offset, time_offset, expected_bits) in cases {
+            let tip = BlockInfo {
+                height: MAINNET_ANCHOR.height + height_offset,
+                timestamp: MAINNET_ANCHOR.parent_time + time_offset,
+                ..Default::default()
+            };
+            let expectation = asert_expectation(&tip);
+            assert_eq!(
+                expectation.next_bits, expected_bits,
+                "{} {}",
+                height_offset, time_offset
+            );
+            assert_eq!(
+                expectation.schedule_offset_secs,
+                time_offset - 600 * (height_offset as i64 + 1),
+            );
+        }
+    }
+
+    #[test]
+    fn test_asert_pow_limit_anchor() {
+        // Anchored at the pow limit like the reference test runs
+        let anchor = AsertAnchor {
+            height: 1,
+            bits: POW_LIMIT_BITS,
+            parent_time: 0,
+        };
+        let cases = [
+            (2, 1200, POW_LIMIT_BITS),
+            (2, 1200 + HALF_LIFE, POW_LIMIT_BITS),
+            (2, 1200 - HALF_LIFE, 0x1c7f_ff80),
+            (2, 900, 0x1d00_ffb1),
+        ];
+        for (tip_height, tip_timestamp, expected_bits) in cases {
+            let (next_bits, _) = asert_next_bits(&anchor, tip_height, tip_timestamp);
+            assert_eq!(next_bits, expected_bits, "{} {}", tip_height, tip_timestamp);
+        }
+    }
+
+    #[test]
+    fn test_compact_from_shifted() {
+        let cases = [
+            // Zero targets are raised to 1
+            (0, 0, 0x0101_0000),
+            (0, 100, 0x0101_0000),
+            (1, -1, 0x0101_0000),
+            (0xffff, -128, 0x0101_0000),
+            (1, 0, 0x0101_0000),
+            (0x80, 0, 0x0200_8000),
+            (0x7f_ffff, 0, 0x037f_ffff),
+            (0x0123_4567, 0, 0x0401_2345),
+            (0x0123_4567, -8, 0x0301_2345),
+            // The pow limit and just above it
+            (0xffff, 208, POW_LIMIT_BITS),
+            (0x01_0000, 208, 0x1d01_0000),
+            (0x1_0000_0000, 192, 0x1d01_0000),
+        ];
+        for (value, shift, expected_bits) in cases {
+            assert_eq!(
+                compact_from_shifted(value, shift),
+                expected_bits,
+                "{:x} {}",
+                value,
+                shift
+            );
+        }
+    }
+
+    #[test]
+    fn test_exceeds_pow_limit() {
+        assert!(!exceeds_pow_limit(POW_LIMIT_BITS));
+        assert!(!exceeds_pow_limit(0x1c7f_ffff));
+        assert!(!exceeds_pow_limit(0x0101_0000));
+        assert!(exceeds_pow_limit(0x1d01_0000));
+        assert!(exceeds_pow_limit(0x1e00_ffff));
+    }
+}
@@ -0,0 +1,386 @@
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use axum::extract::ws::{Message, WebSocket};
+use bitcoinsuite_core::Sha256d;
+use bitcoinsuite_error::Result;
+use tokio::sync::{broadcast, mpsc};
+
+use crate::{backend::ExplorerBackend, server_primitives::JsonWsMessage};
+
+mod chronik_ws;
+
+pub use chronik_ws::ChronikWsSource;
+
+/// Number of recent block hashes remembered to detect reorgs and to tell the
+/// height of disconnected blocks.
+const MAX_KNOWN_BLOCKS: usize = 100;
+
+/// Chain events the server relays to connected browsers.
+#[derive(Clone, Debug)]
+pub enum ExplorerEvent {
+    BlockConnected(Sha256d),
+    BlockDisconnected { hash: Sha256d, height: i32 },
+    AddedToMempool(Sha256d),
+    RemovedFromMempool(Sha256d),
+}
+
+/// Produces `ExplorerEvent`s, e.g. from the indexer or a test harness.
+#[async_trait]
+pub trait EventSource: Send {
+    /// Waits for the next event; `None` means the source is exhausted.
+    async fn next_event(&mut self, backend: &dyn ExplorerBackend) -> Result<Option<ExplorerEvent>>;
+}
+
+/// Event source that polls the backend's tip and derives block
+/// connected/disconnected events from how it changes, for backends without
+/// a websocket like `ChronikWsSource` listens to.
+///
+/// Mempool txs aren't visible this way, so it never emits mempool events.
+pub struct TipPoller {
+    interval: Duration,
+    known_blocks: BTreeMap<i32, Sha256d>,
+    pending: VecDeque<ExplorerEvent>,
+}
+
+/// Event source fed through a channel, for tests and local setups.
+pub struct ChannelEventSource {
+    receiver: mpsc::UnboundedReceiver<ExplorerEvent>,
+}
+
+/// Event as broadcast to all websocket connections, which each filter it down
+/// to the messages their client asked for.
+#[derive(Clone)]
+pub enum RelayedEvent {
+    Block(JsonWsMessage),
+    Tx {
+        tx_hash: String,
+        /// Output scripts of every input and output of the tx.
+        scripts: Arc<HashSet<Vec<u8>>>,
+        is_confirmed: bool,
+    },
+}
+
+/// Address a websocket client watches, with the output script it stands for.
+pub struct WatchedAddress {
+    pub address: String,
+    pub script: Vec<u8>,
+    /// Keeps `script` subscribed at the event source while the client is
+    /// connected.
+    pub(crate) watch: Option<ScriptWatch>,
+}
+
+/// Change to the scripts whose txs the event source should report, sent
+/// through the channel given to `Server::with_script_subscriptions`.
+///
+/// Scripts can be watched several times, e.g. by several websocket clients;
+/// they stay subscribed until each `Watch` got its `Unwatch`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ScriptSubscription {
+    Watch(Vec<u8>),
+    Unwatch(Vec<u8>),
+}
+
+/// Watches a script until dropped.
+pub(crate) struct ScriptWatch {
+    script: Vec<u8>,
+    subscriptions: mpsc::UnboundedSender<ScriptSubscription>,
+}
+
+impl ScriptWatch {
+    pub(crate) fn new(
+        script: Vec<u8>,
+        subscriptions: mpsc::UnboundedSender<ScriptSubscription>,
+    ) -> Self {
+        // Only fails if the event source stopped
+        let _ = subscriptions.send(ScriptSubscription::Watch(script.clone()));
+        ScriptWatch {
+            script,
+            subscriptions,
+        }
+    }
+}
+
+impl Drop for ScriptWatch {
+    fn drop(&mut self) {
+        let script = std::mem::take(&mut self.script);
+        let _ = self.subscriptions.send(ScriptSubscription::Unwatch(script));
+    }
+}
+
+impl TipPoller {
+    pub fn new(interval: Duration) -> Self {
+        TipPoller {
+            interval,
+            known_blocks: BTreeMap::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    async fn poll(&mut self, backend: &dyn ExplorerBackend) -> Result<()> {
+        let blockchain_info = backend.blockchain_info().await?;
+        let tip_height = blockchain_info.tip_height;
+        let tip_hash = Sha256d::from_slice(&blockchain_info.tip_hash)?;
+
+        let lowest_known_height = match self.known_blocks.keys().next() {
+            Some(&height) => height,
+            None => {
+                // First poll, only remember where the chain is at.
+                self.known_blocks.insert(tip_height, tip_hash);
+                return Ok(());
+            }
+        };
+        if self.known_blocks.get(&tip_height) == Some(&tip_hash)
+            && self.known_blocks.keys().next_back() == Some(&tip_height)
+        {
+            return Ok(());
+        }
+
+        let start_height = lowest_known_height
+            .min(tip_height)
+            .max(tip_height - MAX_KNOWN_BLOCKS as i32 + 1);
+        let active_hashes = backend
+            .blocks(start_height, tip_height)
+            .await?
+            .into_iter()
+            .map(|block_info| Ok((block_info.height, Sha256d::from_slice(&block_info.hash)?)))
+            .collect::<Result<BTreeMap<_, _>>>()?;
+
+        // Disconnect known blocks that left the active chain, newest first.
+        while let Some((&height, hash)) = self.known_blocks.iter().next_back() {
+            if height < start_height {
+                // Too far behind the tip to tell whether the known blocks
+                // are still active, so resync from the fetched ones instead
+                // of reporting them all disconnected.
+                self.known_blocks.clear();
+                break;
+            }
+            if active_hashes.get(&height) == Some(hash) {
+                break;
+            }
+            let hash = hash.clone();
+            self.known_blocks.remove(&height);
+            self.pending
+                .push_back(ExplorerEvent::BlockDisconnected { hash, height });
+        }
+
+        let fork_height = self.known_blocks.keys().next_back().copied();
+        for (&height, hash) in active_hashes.iter() {
+            if fork_height
+                .map(|fork_height| height > fork_height)
+                .unwrap_or(true)
+            {
+                self.known_blocks.insert(height, hash.clone());
+                self.pending
+                    .push_back(ExplorerEvent::BlockConnected(hash.clone()));
+            }
+        }
+
+        while self.known_blocks.len() > MAX_KNOWN_BLOCKS {
+            let lowest_height = *self.known_blocks.keys().next().unwrap();
+            self.known_blocks.remove(&lowest_height);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl EventSource for TipPoller {
+    async fn next_event(&mut self, backend: &dyn ExplorerBackend) -> Result<Option<ExplorerEvent>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(Some(event));
+            }
+            if !self.known_blocks.is_empty() {
+                tokio::time::sleep(self.interval).await;
+            }
+            self.poll(backend).await?;
+        }
+    }
+}
+
+impl ChannelEventSource {
+    pub fn new() -> (mpsc::UnboundedSender<ExplorerEvent>, Self) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (sender, ChannelEventSource { receiver })
+    }
+}
+
+#[async_trait]
+impl EventSource for ChannelEventSource {
+    async fn next_event(
+        &mut self,
+        _backend: &dyn ExplorerBackend,
+    ) -> Result<Option<ExplorerEvent>> {
+        Ok(self.receiver.recv().await)
+    }
+}
+
+/// Forwards relayed events to a websocket client until either side hangs up.
+pub async fn serve_ws(
+    mut socket: WebSocket,
+    mut events: broadcast::Receiver<RelayedEvent>,
+    watched_addresses: Vec<WatchedAddress>,
+) {
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    // Slow client, skip what it missed
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return,
+                };
+                for message in ws_messages(event, &watched_addresses) {
+                    let text = serde_json::to_string(&message).unwrap();
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        return;
+                    }
+                }
+            }
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                _ => {}
+            },
+        }
+    }
+}
+
+fn ws_messages(event: RelayedEvent, watched_addresses: &[WatchedAddress]) -> Vec<JsonWsMessage> {
+    match event {
+        RelayedEvent::Block(message) => vec![message],
+        RelayedEvent::Tx {
+            tx_hash,
+            scripts,
+            is_confirmed,
+        } => watched_addresses
+            .iter()
+            .filter(|watched| scripts.contains(&watched.script))
+            .map(|watched| JsonWsMessage::AddressTx {
+                address: watched.address.clone(),
+                tx_hash: tx_hash.clone(),
+                is_confirmed,
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoinsuite_chronik_client::proto::{Block, BlockInfo};
+    use bitcoinsuite_core::Sha256d;
+    use bitcoinsuite_error::Result;
+
+    use super::{ExplorerEvent, TipPoller};
+    use crate::backend::FixtureBackend;
+
+    fn block(height: i32, fork: u8) -> Block {
+        let mut hash = [fork; 32];
+        hash[..4].copy_from_slice(&height.to_le_bytes());
+        Block {
+            block_info: Some(BlockInfo {
+                hash: hash.to_vec(),
+                height,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn hash(block: &Block) -> Sha256d {
+        Sha256d::from_slice(&block.block_info.as_ref().unwrap().hash).unwrap()
+    }
+
+    fn backend_with_chain(tip_height: i32) -> FixtureBackend {
+        let mut backend = FixtureBackend::new();
+        for height in 0..=tip_height {
+            backend.insert_block(block(height, 0));
+        }
+        backend
+    }
+
+    fn connected_heights(poller: &mut TipPoller) -> Vec<i32> {
+        poller
+            .pending
+            .drain(..)
+            .map(|event| match event {
+                ExplorerEvent::BlockConnected(hash) => {
+                    i32::from_le_bytes(hash.as_slice()[..4].try_into().unwrap())
+                }
+                event => panic!("Unexpected event {:?}", event),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_tip_poller_connects_new_blocks() -> Result<()> {
+        let mut poller = TipPoller::new(Default::default());
+        poller.poll(&backend_with_chain(10)).await?;
+        assert!(poller.pending.is_empty());
+
+        poller.poll(&backend_with_chain(12)).await?;
+        assert_eq!(connected_heights(&mut poller), vec![11, 12]);
+
+        poller.poll(&backend_with_chain(12)).await?;
+        assert!(poller.pending.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tip_poller_reorg() -> Result<()> {
+        let mut poller = TipPoller::new(Default::default());
+        let mut backend = backend_with_chain(10);
+        poller.poll(&backend).await?;
+        poller.poll(&backend_with_chain(12)).await?;
+        poller.pending.clear();
+
+        backend.insert_block(block(11, 1));
+        backend.insert_block(block(12, 1));
+        backend.insert_block(block(13, 1));
+        poller.poll(&backend).await?;
+        let events = poller.pending.drain(..).collect::<Vec<_>>();
+        assert_eq!(events.len(), 5);
+        for (event, height) in events[..2].iter().zip([12, 11]) {
+            match event {
+                ExplorerEvent::BlockDisconnected {
+                    hash: disconnected_hash,
+                    height: disconnected_height,
+                } => {
+                    assert_eq!(*disconnected_height, height);
+                    assert_eq!(*disconnected_hash, hash(&block(height, 0)));
+                }
+                event => panic!("Unexpected event {:?}", event),
+            }
+        }
+        for (event, height) in events[2..].iter().zip([11, 12, 13]) {
+            match event {
+                ExplorerEvent::BlockConnected(connected_hash) => {
+                    assert_eq!(*connected_hash, hash(&block(height, 1)));
+                }
+                event => panic!("Unexpected event {:?}", event),
+            }
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tip_poller_resyncs_after_gap() -> Result<()> {
+        let mut poller = TipPoller::new(Default::default());
+        poller.poll(&backend_with_chain(10)).await?;
+
+        // Far more new blocks than the poller remembers: no disconnects
+        poller.poll(&backend_with_chain(500)).await?;
+        assert_eq!(
+            connected_heights(&mut poller),
+            (401..=500).collect::<Vec<_>>(),
+        );
+
+        poller.poll(&backend_with_chain(501)).await?;
+        assert_eq!(connected_heights(&mut poller), vec![501]);
+        Ok(())
+    }
+}
@@ -0,0 +1,316 @@
+use std::collections::{hash_map::Entry, BTreeMap, HashMap};
+
+use async_trait::async_trait;
+use bitcoinsuite_core::Sha256d;
+use bitcoinsuite_error::Result;
+use eyre::{bail, eyre};
+use futures::{SinkExt, StreamExt};
+use prost::Message as _;
+use tokio::{net::TcpStream, sync::mpsc};
+use tokio_tungstenite::{tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+use super::{EventSource, ExplorerEvent, ScriptSubscription, MAX_KNOWN_BLOCKS};
+use crate::{
+    backend::ExplorerBackend,
+    blockchain::{script_type_name, script_type_payload},
+};
+
+/// Event source listening to Chronik's `/ws` endpoint.
+///
+/// Chronik sends every client block events, but tx events only for the
+/// scripts it subscribed to, which are watched through the sender returned by
+/// `new`. The connection is re-established, with the same subscriptions, on
+/// the call after one failed.
+pub struct ChronikWsSource {
+    url: String,
+    socket: Option<WebSocketStream<MaybeTlsStream<TcpStream>>>,
+    subscriptions: mpsc::UnboundedReceiver<ScriptSubscription>,
+    is_subscriptions_closed: bool,
+    /// Number of watches of each subscribed script.
+    watched: HashMap<Vec<u8>, usize>,
+    /// Heights of recently connected blocks, to tell the height of a
+    /// disconnected one.
+    known_blocks: BTreeMap<i32, Sha256d>,
+}
+
+/// `Subscription` message of Chronik's websocket protocol.
+#[derive(Clone, PartialEq, prost::Message)]
+struct WsSubscription {
+    #[prost(string, tag = "1")]
+    script_type: String,
+    #[prost(bytes = "vec", tag = "2")]
+    payload: Vec<u8>,
+    #[prost(bool, tag = "3")]
+    is_subscribe: bool,
+}
+
+/// `SubscribeMsg` message of Chronik's websocket protocol.
+#[derive(Clone, PartialEq, prost::Message)]
+struct WsMessage {
+    #[prost(oneof = "WsMessageType", tags = "1, 2, 3, 4, 5, 6, 7")]
+    msg_type: Option<WsMessageType>,
+}
+
+#[derive(Clone, PartialEq, prost::Oneof)]
+enum WsMessageType {
+    #[prost(message, tag = "1")]
+    Error(WsError),
+    #[prost(message, tag = "2")]
+    AddedToMempool(WsTxid),
+    #[prost(message, tag = "3")]
+    RemovedFromMempool(WsTxid),
+    #[prost(message, tag = "4")]
+    Confirmed(WsTxid),
+    #[prost(message, tag = "5")]
+    Reorg(WsTxid),
+    #[prost(message, tag = "6")]
+    BlockConnected(WsBlockHash),
+    #[prost(message, tag = "7")]
+    BlockDisconnected(WsBlockHash),
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct WsError {
+    #[prost(string, tag = "1")]
+    error_code: String,
+    #[prost(string, tag = "2")]
+    msg: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct WsTxid {
+    #[prost(bytes = "vec", tag = "1")]
+    txid: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct WsBlockHash {
+    #[prost(bytes = "vec", tag = "1")]
+    block_hash: Vec<u8>,
+}
+
+impl ChronikWsSource {
+    /// Listens to the websocket of the Chronik instance at `chronik_api_url`.
+    /// Scripts watched through the returned sender get subscribed to.
+    pub fn new(chronik_api_url: &str) -> Result<(mpsc::UnboundedSender<ScriptSubscription>, Self)> {
+        let url = match chronik_api_url.split_once("://") {
+            Some(("http", rest)) => format!("ws://{}/ws", rest.trim_end_matches('/')),
+            Some(("https", rest)) => format!("wss://{}/ws", rest.trim_end_matches('/')),
+            _ => bail!("Chronik URL must be http or https: {}", chronik_api_url),
+        };
+        let (sender, subscriptions) = mpsc::unbounded_channel();
+        let source = ChronikWsSource {
+            url,
+            socket: None,
+            subscriptions,
+            is_subscriptions_closed: false,
+            watched: HashMap::new(),
+            known_blocks: BTreeMap::new(),
+        };
+        Ok((sender, source))
+    }
+
+    async fn connect(&mut self) -> Result<()> {
+        let (mut socket, _) = tokio_tungstenite::connect_async(self.url.as_str()).await?;
+        for script in self.watched.keys() {
+            socket.send(subscription_message(script, true)).await?;
+        }
+        self.socket = Some(socket);
+        Ok(())
+    }
+
+    async fn subscribe(&mut self, subscription: ScriptSubscription) -> Result<()> {
+        let message = self.update_watched(subscription);
+        if let (Some(socket), Some(message)) = (&mut self.socket, message) {
+            socket.send(message).await?;
+        }
+        Ok(())
+    }
+
+    /// Counts the watches of scripts, returning the message to send if
+    /// `subscription` first watches a script or drops its last watch.
+    fn update_watched(&mut self, subscription: ScriptSubscription) -> Option<Message> {
+        match subscription {
+            ScriptSubscription::Watch(script) => match self.watched.entry(script) {
+                Entry::Occupied(mut entry) => {
+                    *entry.get_mut() += 1;
+                    None
+                }
+                Entry::Vacant(entry) => {
+                    let message = subscription_message(entry.key(), true);
+                    entry.insert(1);
+                    Some(message)
+                }
+            },
+            ScriptSubscription::Unwatch(script) => match self.watched.entry(script) {
+                Entry::Occupied(mut entry) => {
+                    *entry.get_mut() -= 1;
+                    if *entry.get() > 0 {
+                        return None;
+                    }
+                    let (script, _) = entry.remove_entry();
+                    Some(subscription_message(&script, false))
+                }
+                Entry::Vacant(_) => None,
+            },
+        }
+    }
+
+    async fn message_event(
+        &mut self,
+        message: WsMessage,
+        backend: &dyn ExplorerBackend,
+    ) -> Result<Option<ExplorerEvent>> {
+        let msg_type = match message.msg_type {
+            Some(msg_type) => msg_type,
+            None => return Ok(None),
+        };
+        let event = match msg_type {
+            WsMessageType::Error(error) => {
+                bail!(
+                    "Chronik websocket error {}: {}",
+                    error.error_code,
+                    error.msg
+                )
+            }
+            WsMessageType::AddedToMempool(tx) => {
+                ExplorerEvent::AddedToMempool(Sha256d::from_slice(&tx.txid)?)
+            }
+            // Txs returning to the mempool after their block got disconnected
+            WsMessageType::Reorg(tx) => {
+                ExplorerEvent::AddedToMempool(Sha256d::from_slice(&tx.txid)?)
+            }
+            WsMessageType::RemovedFromMempool(tx) => {
+                ExplorerEvent::RemovedFromMempool(Sha256d::from_slice(&tx.txid)?)
+            }
+            // Covered by the BlockConnected of its block
+            WsMessageType::Confirmed(_) => return Ok(None),
+            WsMessageType::BlockConnected(block) => {
+                let hash = Sha256d::from_slice(&block.block_hash)?;
+                let blockchain_info = backend.blockchain_info().await?;
+                let height = if blockchain_info.tip_hash == block.block_hash {
+                    blockchain_info.tip_height
+                } else {
+                    // Another block arrived since, look this one up
+                    backend
+                        .block_by_hash(&hash)
+                        .await?
+                        .block_info
+                        .ok_or_else(|| eyre!("Block has no info"))?
+                        .height
+                };
+                self.known_blocks.insert(height, hash.clone());
+                while self.known_blocks.len() > MAX_KNOWN_BLOCKS {
+                    let lowest_height = *self.known_blocks.keys().next().unwrap();
+                    self.known_blocks.remove(&lowest_height);
+                }
+                ExplorerEvent::BlockConnected(hash)
+            }
+            WsMessageType::BlockDisconnected(block) => {
+                let hash = Sha256d::from_slice(&block.block_hash)?;
+                let known_height = self
+                    .known_blocks
+                    .iter()
+                    .find(|(_, known_hash)| **known_hash == hash)
+                    .map(|(&height, _)| height);
+                let height = match known_height {
+                    Some(height) => {
+                        self.known_blocks.remove(&height);
+                        height
+                    }
+                    // Chronik no longer serves the block, but it was the tip
+                    None => backend.blockchain_info().await?.tip_height + 1,
+                };
+                ExplorerEvent::BlockDisconnected { hash, height }
+            }
+        };
+        Ok(Some(event))
+    }
+}
+
+#[async_trait]
+impl EventSource for ChronikWsSource {
+    async fn next_event(&mut self, backend: &dyn ExplorerBackend) -> Result<Option<ExplorerEvent>> {
+        loop {
+            if self.socket.is_none() {
+                self.connect().await?;
+            }
+            let socket = self.socket.as_mut().expect("Impossible");
+            let is_subscriptions_closed = self.is_subscriptions_closed;
+            tokio::select! {
+                subscription = self.subscriptions.recv(), if !is_subscriptions_closed => {
+                    match subscription {
+                        Some(subscription) => {
+                            if let Err(err) = self.subscribe(subscription).await {
+                                self.socket = None;
+                                return Err(err);
+                            }
+                        }
+                        None => self.is_subscriptions_closed = true,
+                    }
+                }
+                message = socket.next() => {
+                    let bytes = match message {
+                        Some(Ok(Message::Binary(bytes))) => bytes,
+                        Some(Ok(Message::Close(_))) | None => {
+                            self.socket = None;
+                            bail!("Chronik closed the websocket");
+                        }
+                        Some(Ok(_)) => continue,
+                        Some(Err(err)) => {
+                            self.socket = None;
+                            return Err(err.into());
+                        }
+                    };
+                    let message = WsMessage::decode(bytes.as_slice())?;
+                    if let Some(event) = self.message_event(message, backend).await? {
+                        return Ok(Some(event));
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn subscription_message(script: &[u8], is_subscribe: bool) -> Message {
+    let (script_type, payload) = script_type_payload(script);
+    let subscription = WsSubscription {
+        script_type: script_type_name(script_type).to_string(),
+        payload,
+        is_subscribe,
+    };
+    Message::Binary(subscription.encode_to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_tungstenite::tungstenite::Message;
+
+    use super::{subscription_message, ChronikWsSource, ScriptSubscription};
+
+    #[test]
+    fn test_update_watched() {
+        let (_, mut source) = ChronikWsSource::new("https://chronik.example").unwrap();
+        let script_a = vec![0x76, 0xa9, 0x14];
+        let script_b = vec![0xa9, 0x14];
+        let mut update = |subscription| -> Option<Message> { source.update_watched(subscription) };
+
+        // Subscribed on the first watch, unsubscribed after the last unwatch
+        assert_eq!(
+            update(ScriptSubscription::Watch(script_a.clone())),
+            Some(subscription_message(&script_a, true)),
+        );
+        assert_eq!(update(ScriptSubscription::Watch(script_a.clone())), None);
+        assert_eq!(
+            update(ScriptSubscription::Watch(script_b.clone())),
+            Some(subscription_message(&script_b, true)),
+        );
+        assert_eq!(update(ScriptSubscription::Unwatch(script_a.clone())), None);
+        assert_eq!(
+            update(ScriptSubscription::Unwatch(script_a.clone())),
+            Some(subscription_message(&script_a, false)),
+        );
+        assert_eq!(update(ScriptSubscription::Unwatch(script_a)), None);
+        assert_eq!(source.watched.len(), 1);
+    }
+}
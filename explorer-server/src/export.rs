@@ -0,0 +1,244 @@
+use std::{collections::HashMap, str::FromStr};
+
+use bitcoinsuite_chronik_client::proto::Tx;
+use bitcoinsuite_error::Result;
+use chrono::{TimeZone, Utc};
+use eyre::{eyre, Report};
+
+use crate::{
+    api::{calc_tx_stats, format_token_amount},
+    blockchain::{destination_from_script, to_be_hex, Destination},
+    server_primitives::{JsonExportRow, JsonToken},
+};
+
+const CSV_COLUMNS: [&str; 10] = [
+    "date",
+    "timestamp",
+    "txid",
+    "block_height",
+    "delta_xec",
+    "token_id",
+    "token_ticker",
+    "delta_tokens",
+    "fee_xec",
+    "counterparties",
+];
+
+/// Format of an address history export.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    /// A single JSON array of `JsonExportRow`s.
+    Json,
+}
+
+impl FromStr for ExportFormat {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "csv" => Ok(ExportFormat::Csv),
+            "json" => Ok(ExportFormat::Json),
+            _ => Err(eyre!("Unknown export format: {}", s)),
+        }
+    }
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Json => "application/json",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+
+    pub fn header(&self) -> String {
+        match self {
+            ExportFormat::Csv => format!("{}\n", CSV_COLUMNS.join(",")),
+            ExportFormat::Json => "[".to_string(),
+        }
+    }
+
+    pub fn footer(&self) -> String {
+        match self {
+            ExportFormat::Csv => String::new(),
+            ExportFormat::Json => "]".to_string(),
+        }
+    }
+
+    /// Renders the `row_idx`th row of the export, including any separator
+    /// from the previous row.
+    pub fn row(&self, row: &JsonExportRow, row_idx: usize) -> String {
+        match self {
+            ExportFormat::Csv => {
+                // Whether each field is text, rather than a number
+                let fields = [
+                    (row.date.clone(), true),
+                    (row.timestamp.to_string(), false),
+                    (row.tx_hash.clone(), true),
+                    (
+                        row.block_height
+                            .map(|height| height.to_string())
+                            .unwrap_or_default(),
+                        false,
+                    ),
+                    (row.delta_xec.clone(), false),
+                    (row.token_id.clone().unwrap_or_default(), true),
+                    (row.token_ticker.clone().unwrap_or_default(), true),
+                    (row.delta_tokens.clone().unwrap_or_default(), false),
+                    (row.fee_xec.clone(), false),
+                    (row.counterparties.join(" "), true),
+                ];
+                let fields = fields
+                    .iter()
+                    .map(|(field, is_text)| csv_field(field, *is_text))
+                    .collect::<Vec<_>>();
+                format!("{}\n", fields.join(","))
+            }
+            ExportFormat::Json => {
+                let separator = if row_idx == 0 { "" } else { "," };
+                format!("{}{}", separator, serde_json::to_string(row).unwrap())
+            }
+        }
+    }
+
+    /// Renders the row marking an export that ended early, after `num_rows`
+    /// rows, because the rest of the history couldn't be fetched.
+    pub fn truncated_row(&self, num_rows: usize) -> String {
+        let message = format!("Export truncated after {} txs", num_rows);
+        match self {
+            ExportFormat::Csv => format!("{}\n", csv_field(&message, true)),
+            ExportFormat::Json => {
+                let separator = if num_rows == 0 { "" } else { "," };
+                let marker = serde_json::json!({
+                    "error": "export-truncated",
+                    "message": message,
+                });
+                format!("{}{}", separator, marker)
+            }
+        }
+    }
+}
+
+/// Summarizes `tx` from the point of view of the address with
+/// `address_script`, with amounts in XEC and token decimals applied.
+///
+/// Counterparties are the addresses paid if the address funded the tx, and
+/// the addresses funding it otherwise.
+pub fn export_row(
+    tx: &Tx,
+    address_script: &[u8],
+    address_prefix: &str,
+    json_tokens: &HashMap<String, JsonToken>,
+) -> JsonExportRow {
+    let stats = calc_tx_stats(tx, Some(address_script));
+    let timestamp = match &tx.block {
+        Some(block) => block.timestamp,
+        None => tx.time_first_seen,
+    };
+    let is_sender = tx
+        .inputs
+        .iter()
+        .any(|input| input.output_script == address_script);
+
+    let token_id = tx
+        .slp_tx_data
+        .as_ref()
+        .and_then(|slp_tx_data| slp_tx_data.slp_meta.as_ref())
+        .map(|slp_meta| hex::encode(&slp_meta.token_id));
+    let token = token_id
+        .as_ref()
+        .and_then(|token_id| json_tokens.get(token_id));
+    let delta_tokens = token_id.as_ref().map(|_| {
+        let decimals = token.map(|token| token.decimals).unwrap_or(0);
+        format_token_amount(stats.delta_tokens as i128, decimals)
+    });
+
+    let counterparty_scripts = if is_sender {
+        tx.outputs
+            .iter()
+            .map(|output| &output.output_script)
+            .collect::<Vec<_>>()
+    } else {
+        tx.inputs
+            .iter()
+            .map(|input| &input.output_script)
+            .collect::<Vec<_>>()
+    };
+    let mut counterparties = Vec::<String>::new();
+    for script in counterparty_scripts {
+        if script.as_slice() == address_script {
+            continue;
+        }
+        if let Destination::Address(address) = destination_from_script(address_prefix, script) {
+            let address = address.as_str().to_string();
+            if !counterparties.contains(&address) {
+                counterparties.push(address);
+            }
+        }
+    }
+
+    let fee = if is_sender { stats.fee } else { 0 };
+
+    JsonExportRow {
+        date: Utc.timestamp(timestamp, 0).to_rfc3339(),
+        timestamp,
+        tx_hash: to_be_hex(&tx.txid),
+        block_height: tx.block.as_ref().map(|block| block.height),
+        delta_xec: format_token_amount(stats.delta_sats as i128, 2),
+        token_id,
+        token_ticker: token.map(|token| token.token_ticker.clone()),
+        delta_tokens,
+        fee_xec: format_token_amount(fee as i128, 2),
+        counterparties,
+    }
+}
+
+/// Quotes `field` if needed. Text fields that spreadsheets would evaluate as
+/// a formula get prefixed with `'`; numbers are left as is, as negative
+/// amounts start with `-`.
+fn csv_field(field: &str, is_text: bool) -> String {
+    let field = if is_text && field.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", field)
+    } else {
+        field.to_string()
+    };
+    if field.contains(|c| matches!(c, ',' | '"' | '\n' | '\r')) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::csv_field;
+
+    #[test]
+    fn test_csv_field() {
+        let cases = [
+            ("abc", true, "abc"),
+            ("a,b", true, "\"a,b\""),
+            ("say \"hi\"", true, "\"say \"\"hi\"\"\"\""),
+            ("=1+2", true, "'=1+2"),
+            ("+1", true, "'+1"),
+            ("-1", true, "'-1"),
+            ("@SUM(A1)", true, "'@SUM(A1)"),
+            ("\tx", true, "'\tx"),
+            ("\rx", true, "\"'\rx\""),
+            ("=HYPERLINK(\"a\",1)", true, "\"'=HYPERLINK(\"\"a\"\",1)\""),
+            ("-12.34", false, "-12.34"),
+            ("1,000", false, "\"1,000\""),
+        ];
+        for (field, is_text, expected) in cases {
+            assert_eq!(csv_field(field, is_text), expected, "{:?}", field);
+        }
+    }
+}
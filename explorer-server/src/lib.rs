@@ -1,8 +1,21 @@
 mod api;
+pub mod backend;
 mod blockchain;
+pub mod cache;
+mod charts;
+mod coinbase;
 pub mod config;
+mod difficulty;
+pub mod events;
+mod export;
+mod mempool;
+pub mod op_return;
+pub mod pools;
+pub mod reward;
+mod script;
 pub mod server;
 pub mod server_error;
 pub mod server_http;
 pub mod server_primitives;
 mod templating;
+mod xpub;
@@ -0,0 +1,74 @@
+use std::collections::{HashMap, HashSet};
+
+use bitcoinsuite_chronik_client::proto::{Block, OutPoint, Tx};
+use bitcoinsuite_core::Sha256d;
+
+/// Upper bounds (exclusive, in sats per byte) of the fee rate histogram
+/// buckets; the last bucket is unbounded.
+pub const FEE_RATE_BUCKETS: [f64; 7] = [1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0];
+
+/// Txs currently in the mempool, kept up to date from relayed events.
+///
+/// Only holds the txs the event source reports, which for Chronik are those
+/// of the scripts websocket clients watch, see `Server::ws_subscription`.
+#[derive(Default)]
+pub struct Mempool {
+    txs: HashMap<Sha256d, Tx>,
+}
+
+impl Mempool {
+    pub fn insert(&mut self, txid: Sha256d, tx: Tx) {
+        self.txs.insert(txid, tx);
+    }
+
+    pub fn remove(&mut self, txid: &Sha256d) {
+        self.txs.remove(txid);
+    }
+
+    /// Drops the txs mined in `block`, along with any txs conflicting with
+    /// them.
+    pub fn remove_mined(&mut self, block: &Block) {
+        let mined_txids = block
+            .txs
+            .iter()
+            .map(|tx| tx.txid.as_slice())
+            .collect::<HashSet<_>>();
+        let spent_outpoints = block
+            .txs
+            .iter()
+            .flat_map(|tx| tx.inputs.iter())
+            .filter_map(|input| input.prev_out.as_ref())
+            .map(outpoint_key)
+            .collect::<HashSet<_>>();
+        self.txs.retain(|_, tx| {
+            !mined_txids.contains(tx.txid.as_slice())
+                && !tx
+                    .inputs
+                    .iter()
+                    .filter_map(|input| input.prev_out.as_ref())
+                    .any(|prev_out| spent_outpoints.contains(&outpoint_key(prev_out)))
+        });
+    }
+
+    /// All mempool txs, most recently seen first.
+    pub fn txs(&self) -> Vec<Tx> {
+        self.latest_txs(self.txs.len())
+    }
+
+    /// The `num_txs` most recently seen mempool txs, most recent first.
+    pub fn latest_txs(&self, num_txs: usize) -> Vec<Tx> {
+        let mut txs = self.txs.values().collect::<Vec<_>>();
+        txs.sort_by_key(|tx| std::cmp::Reverse(tx.time_first_seen));
+        txs.into_iter().take(num_txs).cloned().collect()
+    }
+
+    /// Number of mempool txs and their total size in bytes.
+    pub fn size(&self) -> (u64, u64) {
+        let total_bytes = self.txs.values().map(|tx| tx.size as u64).sum();
+        (self.txs.len() as u64, total_bytes)
+    }
+}
+
+fn outpoint_key(outpoint: &OutPoint) -> (Vec<u8>, u32) {
+    (outpoint.txid.clone(), outpoint.out_idx)
+}
@@ -0,0 +1,694 @@
+use std::collections::HashMap;
+
+use bitcoinsuite_chronik_client::proto::Tx;
+use bitcoinsuite_core::{AddressType, CashAddress, ShaRmd160};
+use bitcoinsuite_error::Result;
+use eyre::{bail, eyre};
+
+use crate::{
+    blockchain::to_be_hex,
+    script::{opcode_name, parse_script, ScriptOp, OP_0, OP_RETURN},
+};
+
+/// A named field decoded from an OP_RETURN payload, e.g. a token ticker.
+#[derive(Clone, Debug)]
+pub struct OpReturnField {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct DecodedOpReturn {
+    /// Name of the matching protocol, `None` if no registered protocol matched.
+    pub protocol: Option<&'static str>,
+    pub fields: Vec<OpReturnField>,
+    /// Hex of the script following OP_RETURN.
+    pub hex: String,
+    /// Pushed data as UTF-8, with undecodable and control characters replaced.
+    pub utf8_preview: String,
+}
+
+/// A protocol identified by the bytes following OP_RETURN.
+pub trait OpReturnProtocol: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Script bytes following OP_RETURN that identify the protocol, usually
+    /// the push of its LOKAD ID.
+    fn prefix(&self) -> &[u8];
+
+    /// Decodes `payload`, the script bytes following the prefix.
+    fn decode(&self, payload: &[u8]) -> Result<Vec<OpReturnField>>;
+}
+
+/// Set of known OP_RETURN protocols; the one with the longest matching
+/// prefix decodes a script.
+#[derive(Default)]
+pub struct OpReturnRegistry {
+    protocols: Vec<Box<dyn OpReturnProtocol>>,
+}
+
+impl OpReturnRegistry {
+    /// Registry without any protocols, see `with_builtin_protocols` for the
+    /// built-in ones.
+    pub fn new() -> Self {
+        OpReturnRegistry {
+            protocols: Vec::new(),
+        }
+    }
+
+    /// Registry of the built-in protocols; addresses in payloads are shown
+    /// with `addr_prefix`.
+    pub fn with_builtin_protocols(addr_prefix: &'static str) -> Self {
+        let mut registry = OpReturnRegistry::new();
+        registry.register(Slp);
+        registry.register(Memo);
+        registry.register(CashtabMsg);
+        registry.register(Alias::new(addr_prefix));
+        registry.register(Empp);
+        registry
+    }
+
+    pub fn register(&mut self, protocol: impl OpReturnProtocol + 'static) {
+        self.protocols.push(Box::new(protocol));
+    }
+
+    /// Decodes `script`, or returns `None` if it isn't an OP_RETURN script.
+    pub fn decode(&self, script: &[u8]) -> Option<DecodedOpReturn> {
+        let data = match script {
+            [OP_RETURN, data @ ..] => data,
+            _ => return None,
+        };
+        let protocol = self
+            .protocols
+            .iter()
+            .filter(|protocol| data.starts_with(protocol.prefix()))
+            .max_by_key(|protocol| protocol.prefix().len());
+        let (protocol, fields) = match protocol {
+            Some(protocol) => {
+                let payload = &data[protocol.prefix().len()..];
+                let fields = protocol
+                    .decode(payload)
+                    .unwrap_or_else(|err| vec![field("Error", err)]);
+                (Some(protocol.name()), fields)
+            }
+            None => (None, Vec::new()),
+        };
+        Some(DecodedOpReturn {
+            protocol,
+            fields,
+            hex: hex::encode(data),
+            utf8_preview: utf8_preview(data),
+        })
+    }
+
+    /// Decodes the OP_RETURN outputs of `tx`, by output index.
+    pub fn decode_outputs(&self, tx: &Tx) -> HashMap<usize, DecodedOpReturn> {
+        tx.outputs
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, output)| Some((idx, self.decode(&output.output_script)?)))
+            .collect()
+    }
+}
+
+/// Simple Ledger Protocol tokens, LOKAD ID `SLP\0`.
+pub struct Slp;
+
+/// memo.cash social posts, prefixed with `6d` and an action byte.
+pub struct Memo;
+
+/// Cashtab messages, LOKAD ID `\0tab`.
+pub struct CashtabMsg;
+
+/// eCash alias registrations, LOKAD ID `.xec`.
+pub struct Alias {
+    addr_prefix: &'static str,
+}
+
+/// eCash Multi Pushdata Protocol, `OP_RESERVED` followed by pushes, each
+/// holding e.g. an ALP token section.
+pub struct Empp;
+
+impl OpReturnProtocol for Slp {
+    fn name(&self) -> &'static str {
+        "SLP"
+    }
+
+    fn prefix(&self) -> &[u8] {
+        b"\x04SLP\0"
+    }
+
+    fn decode(&self, payload: &[u8]) -> Result<Vec<OpReturnField>> {
+        let pushes = pushes(payload)?;
+        let (token_type, tx_type, rest) = match pushes.as_slice() {
+            [token_type, tx_type, rest @ ..] => (token_type, tx_type, rest),
+            _ => bail!("Missing token type or tx type"),
+        };
+        let mut fields = vec![
+            field("Token type", be_uint(token_type)?),
+            field("Tx type", String::from_utf8_lossy(tx_type)),
+        ];
+        match (tx_type.as_slice(), rest) {
+            (b"GENESIS", [ticker, name, url, hash, decimals, mint_baton_out_idx, quantity]) => {
+                fields.extend([
+                    field("Ticker", String::from_utf8_lossy(ticker)),
+                    field("Name", String::from_utf8_lossy(name)),
+                    field("Document URL", String::from_utf8_lossy(url)),
+                    field("Document hash", hex::encode(hash)),
+                    field("Decimals", be_uint(decimals)?),
+                    field("Mint baton output", optional_out_idx(mint_baton_out_idx)?),
+                    field("Initial quantity", slp_amount(quantity)?),
+                ]);
+            }
+            (b"MINT", [token_id, mint_baton_out_idx, quantity]) => {
+                fields.extend([
+                    field("Token ID", hex::encode(token_id)),
+                    field("Mint baton output", optional_out_idx(mint_baton_out_idx)?),
+                    field("Additional quantity", slp_amount(quantity)?),
+                ]);
+            }
+            (b"SEND", [token_id, amounts @ ..]) if !amounts.is_empty() => {
+                fields.push(field("Token ID", hex::encode(token_id)));
+                for (idx, amount) in amounts.iter().enumerate() {
+                    fields.push(field(&format!("Output {}", idx + 1), slp_amount(amount)?));
+                }
+            }
+            _ => bail!("Invalid {} section", String::from_utf8_lossy(tx_type)),
+        }
+        Ok(fields)
+    }
+}
+
+impl OpReturnProtocol for Memo {
+    fn name(&self) -> &'static str {
+        "memo"
+    }
+
+    fn prefix(&self) -> &[u8] {
+        b"\x02\x6d"
+    }
+
+    fn decode(&self, payload: &[u8]) -> Result<Vec<OpReturnField>> {
+        let (action, rest) = payload
+            .split_first()
+            .ok_or_else(|| eyre!("Missing memo action"))?;
+        let pushes = pushes(rest)?;
+        let text = |data: &[u8]| String::from_utf8_lossy(data).into_owned();
+        let fields = match (*action, pushes.as_slice()) {
+            (0x01, [name]) => vec![field("Action", "Set name"), field("Name", text(name))],
+            (0x02, [message]) => vec![field("Action", "Post"), field("Message", text(message))],
+            (0x03, [tx_hash, message]) => vec![
+                field("Action", "Reply"),
+                field("Reply to", to_be_hex(tx_hash)),
+                field("Message", text(message)),
+            ],
+            (0x04, [tx_hash]) => vec![field("Action", "Like"), field("Post", to_be_hex(tx_hash))],
+            (0x05, [profile]) => vec![
+                field("Action", "Set profile text"),
+                field("Profile", text(profile)),
+            ],
+            (0x06, [hash]) => vec![field("Action", "Follow"), field("Hash", hex::encode(hash))],
+            (0x07, [hash]) => vec![
+                field("Action", "Unfollow"),
+                field("Hash", hex::encode(hash)),
+            ],
+            (0x0a, [url]) => vec![
+                field("Action", "Set profile picture"),
+                field("URL", text(url)),
+            ],
+            (0x0c, [topic, message]) => vec![
+                field("Action", "Topic post"),
+                field("Topic", text(topic)),
+                field("Message", text(message)),
+            ],
+            (0x0d, [topic]) => vec![field("Action", "Follow topic"), field("Topic", text(topic))],
+            (0x0e, [topic]) => vec![
+                field("Action", "Unfollow topic"),
+                field("Topic", text(topic)),
+            ],
+            _ => bail!("Unknown memo action 0x{:02x}", action),
+        };
+        Ok(fields)
+    }
+}
+
+impl OpReturnProtocol for CashtabMsg {
+    fn name(&self) -> &'static str {
+        "Cashtab message"
+    }
+
+    fn prefix(&self) -> &[u8] {
+        b"\x04\0tab"
+    }
+
+    fn decode(&self, payload: &[u8]) -> Result<Vec<OpReturnField>> {
+        match pushes(payload)?.as_slice() {
+            [message] => Ok(vec![field("Message", String::from_utf8_lossy(message))]),
+            _ => bail!("Expected a single message push"),
+        }
+    }
+}
+
+impl Alias {
+    /// Alias decoder showing the registered addresses with `addr_prefix`.
+    pub fn new(addr_prefix: &'static str) -> Self {
+        Alias { addr_prefix }
+    }
+}
+
+impl OpReturnProtocol for Alias {
+    fn name(&self) -> &'static str {
+        "Alias registration"
+    }
+
+    fn prefix(&self) -> &[u8] {
+        b"\x04.xec"
+    }
+
+    fn decode(&self, payload: &[u8]) -> Result<Vec<OpReturnField>> {
+        let pushes = pushes(payload)?;
+        let (version, alias, address) = match pushes.as_slice() {
+            [version, alias, address] if address.len() == 21 => (version, alias, address),
+            _ => bail!("Expected version, alias and address pushes"),
+        };
+        let addr_type = match address[0] {
+            0 => AddressType::P2PKH,
+            8 => AddressType::P2SH,
+            addr_type => bail!("Unknown address type {}", addr_type),
+        };
+        let hash = ShaRmd160::from_slice(&address[1..]).map_err(|_| eyre!("Invalid hash"))?;
+        let address = CashAddress::from_hash(self.addr_prefix, addr_type, hash);
+        Ok(vec![
+            field("Version", be_uint(version)?),
+            field("Alias", String::from_utf8_lossy(alias)),
+            field("Address", address.as_str()),
+        ])
+    }
+}
+
+impl OpReturnProtocol for Empp {
+    fn name(&self) -> &'static str {
+        "eMPP"
+    }
+
+    fn prefix(&self) -> &[u8] {
+        // OP_RESERVED
+        &[0x50]
+    }
+
+    fn decode(&self, payload: &[u8]) -> Result<Vec<OpReturnField>> {
+        let mut fields = Vec::new();
+        for (idx, section) in pushes(payload)?.iter().enumerate() {
+            let name = format!("Section {}", idx + 1);
+            match section.strip_prefix(b"SLP2") {
+                Some(alp) => {
+                    fields.push(field(&name, "ALP"));
+                    match decode_alp(alp) {
+                        Ok(alp_fields) => fields.extend(alp_fields),
+                        Err(err) => fields.push(field("Error", err)),
+                    }
+                }
+                None => fields.push(field(&name, hex::encode(section))),
+            }
+        }
+        Ok(fields)
+    }
+}
+
+fn decode_alp(section: &[u8]) -> Result<Vec<OpReturnField>> {
+    let mut reader = Reader { data: section };
+    let token_type = reader.u8()?;
+    let tx_type = reader.var_bytes()?;
+    let mut fields = vec![
+        field("Token type", token_type),
+        field("Tx type", String::from_utf8_lossy(tx_type)),
+    ];
+    match tx_type {
+        b"GENESIS" => {
+            fields.extend([
+                field("Ticker", String::from_utf8_lossy(reader.var_bytes()?)),
+                field("Name", String::from_utf8_lossy(reader.var_bytes()?)),
+                field("URL", String::from_utf8_lossy(reader.var_bytes()?)),
+                field("Data", hex::encode(reader.var_bytes()?)),
+                field("Auth pubkey", hex::encode(reader.var_bytes()?)),
+                field("Decimals", reader.u8()?),
+            ]);
+            read_alp_mint_data(&mut reader, &mut fields)?;
+        }
+        b"MINT" => {
+            fields.push(field("Token ID", to_be_hex(reader.bytes(32)?)));
+            read_alp_mint_data(&mut reader, &mut fields)?;
+        }
+        b"SEND" => {
+            fields.push(field("Token ID", to_be_hex(reader.bytes(32)?)));
+            for idx in 0..reader.u8()? {
+                fields.push(field(&format!("Output {}", idx + 1), reader.amount()?));
+            }
+        }
+        b"BURN" => {
+            fields.push(field("Token ID", to_be_hex(reader.bytes(32)?)));
+            fields.push(field("Burn amount", reader.amount()?));
+        }
+        _ => bail!("Unknown ALP tx type"),
+    }
+    Ok(fields)
+}
+
+fn read_alp_mint_data(reader: &mut Reader, fields: &mut Vec<OpReturnField>) -> Result<()> {
+    for idx in 0..reader.u8()? {
+        fields.push(field(
+            &format!("Mint to output {}", idx + 1),
+            reader.amount()?,
+        ));
+    }
+    fields.push(field("Mint batons", reader.u8()?));
+    Ok(())
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.data.len() {
+            bail!("Unexpected end of data");
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    /// Bytes prefixed with their compact size length.
+    fn var_bytes(&mut self) -> Result<&'a [u8]> {
+        let len = match self.u8()? {
+            0xfd => u16::from_le_bytes(self.bytes(2)?.try_into()?) as u64,
+            0xfe => u32::from_le_bytes(self.bytes(4)?.try_into()?) as u64,
+            0xff => u64::from_le_bytes(self.bytes(8)?.try_into()?),
+            len => len as u64,
+        };
+        self.bytes(usize::try_from(len)?)
+    }
+
+    /// 48-bit little-endian ALP amount.
+    fn amount(&mut self) -> Result<u64> {
+        let mut amount = [0; 8];
+        amount[..6].copy_from_slice(self.bytes(6)?);
+        Ok(u64::from_le_bytes(amount))
+    }
+}
+
+fn field(name: &str, value: impl ToString) -> OpReturnField {
+    OpReturnField {
+        name: name.to_string(),
+        value: value.to_string(),
+    }
+}
+
+/// Data of the pushes in `script`, failing on any non-push opcode.
+fn pushes(script: &[u8]) -> Result<Vec<Vec<u8>>> {
+    parse_script(script)
+        .into_iter()
+        .map(|op| match op {
+            ScriptOp::Push { data, .. } => Ok(data),
+            ScriptOp::Code(OP_0) => Ok(vec![]),
+            ScriptOp::Code(opcode) => Err(eyre!("Unexpected {}", opcode_name(opcode))),
+            ScriptOp::Truncated(_) => Err(eyre!("Truncated push")),
+        })
+        .collect()
+}
+
+fn be_uint(bytes: &[u8]) -> Result<u64> {
+    if bytes.len() > 8 {
+        bail!("Integer too large");
+    }
+    Ok(bytes.iter().fold(0, |num, &byte| (num << 8) | byte as u64))
+}
+
+fn slp_amount(bytes: &[u8]) -> Result<u64> {
+    if bytes.len() != 8 {
+        bail!("Invalid amount length {}", bytes.len());
+    }
+    be_uint(bytes)
+}
+
+fn optional_out_idx(bytes: &[u8]) -> Result<String> {
+    match bytes {
+        [] => Ok("None".to_string()),
+        [out_idx] => Ok(out_idx.to_string()),
+        _ => bail!("Invalid output index"),
+    }
+}
+
+fn utf8_preview(data: &[u8]) -> String {
+    let pushed_data = parse_script(data)
+        .into_iter()
+        .filter_map(|op| match op {
+            ScriptOp::Push { data, .. } => Some(String::from_utf8_lossy(&data).into_owned()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    pushed_data
+        .chars()
+        .map(|c| if c.is_control() { '.' } else { c })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DecodedOpReturn, OpReturnRegistry};
+
+    fn decode(script_hex: &str) -> DecodedOpReturn {
+        let script = hex::decode(script_hex.replace(' ', "")).unwrap();
+        OpReturnRegistry::with_builtin_protocols("ecash")
+            .decode(&script)
+            .expect("Not an OP_RETURN script")
+    }
+
+    fn fields(decoded: &DecodedOpReturn) -> Vec<(&str, &str)> {
+        decoded
+            .fields
+            .iter()
+            .map(|field| (field.name.as_str(), field.value.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_decode_slp() {
+        let genesis = decode(
+            "6a 04 534c5000 01 01 07 47454e45534953 03 544b4e 04 546f6b6e 00 4c00 01 02 01 02 \
+             08 0000000000000064",
+        );
+        assert_eq!(genesis.protocol, Some("SLP"));
+        assert_eq!(
+            fields(&genesis),
+            vec![
+                ("Token type", "1"),
+                ("Tx type", "GENESIS"),
+                ("Ticker", "TKN"),
+                ("Name", "Tokn"),
+                ("Document URL", ""),
+                ("Document hash", ""),
+                ("Decimals", "2"),
+                ("Mint baton output", "2"),
+                ("Initial quantity", "100"),
+            ],
+        );
+
+        let token_id = "aa".repeat(32);
+        let send = decode(&format!(
+            "6a 04 534c5000 01 01 04 53454e44 20 {} 08 0000000000000001 08 0000000000000102",
+            token_id,
+        ));
+        assert_eq!(
+            fields(&send),
+            vec![
+                ("Token type", "1"),
+                ("Tx type", "SEND"),
+                ("Token ID", token_id.as_str()),
+                ("Output 1", "1"),
+                ("Output 2", "258"),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_decode_memo() {
+        let post = decode("6a 02 6d02 05 68656c6c6f");
+        assert_eq!(post.protocol, Some("memo"));
+        assert_eq!(
+            fields(&post),
+            vec![("Action", "Post"), ("Message", "hello")]
+        );
+
+        let reply = decode(&format!(
+            "6a 02 6d03 20 {}{} 02 6869",
+            "00".repeat(31),
+            "01"
+        ));
+        assert_eq!(
+            fields(&reply),
+            vec![
+                ("Action", "Reply"),
+                ("Reply to", format!("01{}", "00".repeat(31)).as_str()),
+                ("Message", "hi"),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_decode_cashtab_msg() {
+        let message = decode("6a 04 00746162 05 68656c6c6f");
+        assert_eq!(message.protocol, Some("Cashtab message"));
+        assert_eq!(fields(&message), vec![("Message", "hello")]);
+        assert_eq!(message.hex, "04007461620568656c6c6f");
+        assert_eq!(message.utf8_preview, ".tab hello");
+    }
+
+    #[test]
+    fn test_decode_alias() {
+        let script_hex = format!("6a 04 2e786563 00 03 616263 15 00 {}", "11".repeat(20));
+        let alias = decode(&script_hex);
+        assert_eq!(alias.protocol, Some("Alias registration"));
+        assert_eq!(
+            fields(&alias),
+            vec![
+                ("Version", "0"),
+                ("Alias", "abc"),
+                (
+                    "Address",
+                    "ecash:qqg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyquz9y96w"
+                ),
+            ],
+        );
+
+        let script = hex::decode(script_hex.replace(' ', "")).unwrap();
+        let alias = OpReturnRegistry::with_builtin_protocols("ectest")
+            .decode(&script)
+            .unwrap();
+        assert!(alias.fields[2].value.starts_with("ectest:qqg3zyg3"));
+    }
+
+    #[test]
+    fn test_decode_empp() {
+        let token_id = "bb".repeat(32);
+        let empp = decode(&format!(
+            "6a 50 37 534c5032 00 04 53454e44 {} 02 010000000000 000001000000 02 aabb",
+            token_id,
+        ));
+        assert_eq!(empp.protocol, Some("eMPP"));
+        assert_eq!(
+            fields(&empp),
+            vec![
+                ("Section 1", "ALP"),
+                ("Token type", "0"),
+                ("Tx type", "SEND"),
+                ("Token ID", token_id.as_str()),
+                ("Output 1", "1"),
+                ("Output 2", "65536"),
+                ("Section 2", "aabb"),
+            ],
+        );
+
+        let genesis = decode(
+            "6a 50 1e 534c5032 00 07 47454e45534953 03 544b4e 00 00 00 00 04 \
+             01 640000000000 01",
+        );
+        assert_eq!(
+            fields(&genesis),
+            vec![
+                ("Section 1", "ALP"),
+                ("Token type", "0"),
+                ("Tx type", "GENESIS"),
+                ("Ticker", "TKN"),
+                ("Name", ""),
+                ("URL", ""),
+                ("Data", ""),
+                ("Auth pubkey", ""),
+                ("Decimals", "4"),
+                ("Mint to output 1", "100"),
+                ("Mint batons", "1"),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_decode_malformed() {
+        // Each falls back to an error field, keeping the raw hex
+        let cases = vec![
+            ("6a 04 534c5000 01 01", "SLP"),
+            ("6a 04 534c5000 01 01 04 53454e44", "SLP"),
+            (
+                "6a 04 534c5000 01 01 04 53454e44 20 {id} 07 00000000000001",
+                "SLP",
+            ),
+            (
+                "6a 04 534c5000 01 01 04 4d494e54 20 {id} 02 0102 08 {amount}",
+                "SLP",
+            ),
+            ("6a 04 534c5000 09 010203040506070809 04 53454e44", "SLP"),
+            ("6a 04 534c5000 01 01 07 47454e45 53", "SLP"),
+            ("6a 02 6d", "memo"),
+            ("6a 02 6dff 05 68656c6c6f", "memo"),
+            ("6a 02 6d02 76", "memo"),
+            ("6a 04 00746162 05 6865", "Cashtab message"),
+            ("6a 04 00746162 02 6869 02 6869", "Cashtab message"),
+            (
+                "6a 04 2e786563 00 03 616263 14 {hash}",
+                "Alias registration",
+            ),
+            (
+                "6a 04 2e786563 00 03 616263 15 05{hash}",
+                "Alias registration",
+            ),
+            ("6a 50 03 aabb", "eMPP"),
+        ];
+        for (script_hex, protocol) in cases {
+            let script_hex = script_hex
+                .replace("{id}", &"aa".repeat(32))
+                .replace("{amount}", &"00".repeat(8))
+                .replace("{hash}", &"11".repeat(20));
+            let decoded = decode(&script_hex);
+            assert_eq!(decoded.protocol, Some(protocol), "{}", script_hex);
+            assert_eq!(decoded.fields.len(), 1, "{}", script_hex);
+            assert_eq!(decoded.fields[0].name, "Error", "{}", script_hex);
+            assert_eq!(
+                decoded.hex,
+                script_hex.replace(' ', "")[2..],
+                "{}",
+                script_hex
+            );
+        }
+
+        // Malformed ALP sections are reported within their eMPP section
+        let truncated_alp = decode("6a 50 0b 534c5032 00 04 53454e44 bb");
+        assert_eq!(
+            fields(&truncated_alp)[..2],
+            [("Section 1", "ALP"), ("Error", "Unexpected end of data")],
+        );
+        let unknown_alp = decode("6a 50 0b 534c5032 00 05 4f54484552");
+        assert_eq!(fields(&unknown_alp)[1], ("Error", "Unknown ALP tx type"));
+    }
+
+    #[test]
+    fn test_decode_unknown() {
+        let unknown = decode("6a 04 ffffffff 02 6869");
+        assert_eq!(unknown.protocol, None);
+        assert!(unknown.fields.is_empty());
+        assert_eq!(unknown.hex, "04ffffffff026869");
+        assert_eq!(unknown.utf8_preview, "\u{fffd}\u{fffd}\u{fffd}\u{fffd} hi");
+
+        let empty = decode("6a");
+        assert_eq!(empty.protocol, None);
+        assert_eq!(empty.hex, "");
+
+        let registry = OpReturnRegistry::with_builtin_protocols("ecash");
+        assert!(registry.decode(&[]).is_none());
+        assert!(registry.decode(&hex::decode("51").unwrap()).is_none());
+    }
+}
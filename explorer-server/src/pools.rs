@@ -0,0 +1,119 @@
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use bitcoinsuite_chronik_client::proto::Tx;
+use bitcoinsuite_core::CashAddress;
+use bitcoinsuite_error::Result;
+use eyre::{eyre, Report};
+use serde::Deserialize;
+
+use crate::{
+    blockchain::{cash_addr_to_script_type_payload, script_from_type_payload},
+    coinbase::parse_coinbase,
+};
+
+/// Known mining pools, loaded from the `pools_file` TOML file.
+#[derive(Deserialize, Default)]
+pub struct PoolRegistry {
+    #[serde(default)]
+    pools: Vec<Pool>,
+    /// Output script of every pool payout address, to the pool's index.
+    #[serde(skip)]
+    payout_scripts: HashMap<Vec<u8>, usize>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Pool {
+    pub name: String,
+    pub url: Option<String>,
+    /// Strings the pool puts into its coinbase scripts, matched ignoring case.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Addresses the pool pays block rewards to.
+    #[serde(default)]
+    pub addresses: Vec<String>,
+}
+
+pub fn load_pool_registry(registry_string: &str) -> Result<PoolRegistry> {
+    let mut registry: PoolRegistry = toml::from_str(registry_string)?;
+    for (idx, pool) in registry.pools.iter().enumerate() {
+        for address in &pool.addresses {
+            let address = CashAddress::parse_cow(address.into())
+                .map_err(|_| eyre!("Invalid address {} of pool {}", address, pool.name))?;
+            let (script_type, payload) = cash_addr_to_script_type_payload(&address);
+            let script = script_from_type_payload(script_type, &payload);
+            registry.payout_scripts.insert(script, idx);
+        }
+    }
+    Ok(registry)
+}
+
+impl PoolRegistry {
+    pub fn pools(&self) -> &[Pool] {
+        &self.pools
+    }
+
+    /// Finds the pool that mined the block of `coinbase_tx`, by its payout
+    /// scripts first and its coinbase tags second.
+    pub fn identify(&self, coinbase_tx: &Tx) -> Option<&Pool> {
+        let by_payout = coinbase_tx
+            .outputs
+            .iter()
+            .find_map(|output| self.payout_scripts.get(&output.output_script));
+        if let Some(&idx) = by_payout {
+            return Some(&self.pools[idx]);
+        }
+        let coinbase_script = &coinbase_tx.inputs.first()?.input_script;
+        let coinbase_text = parse_coinbase(coinbase_script).tags.join(" ");
+        let coinbase_text = coinbase_text.to_lowercase();
+        self.pools.iter().find(|pool| {
+            pool.tags
+                .iter()
+                .any(|tag| coinbase_text.contains(&tag.to_lowercase()))
+        })
+    }
+}
+
+/// Range of recent blocks miner stats are computed over.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StatsWindow {
+    Blocks(i32),
+    Days(i64),
+}
+
+pub const DEFAULT_STATS_WINDOWS: [StatsWindow; 3] = [
+    StatsWindow::Blocks(144),
+    StatsWindow::Blocks(1008),
+    StatsWindow::Days(30),
+];
+
+/// Largest window that can be requested, in blocks.
+pub const MAX_STATS_WINDOW_BLOCKS: i32 = 10_000;
+
+/// Largest window that can be requested, in days.
+pub const MAX_STATS_WINDOW_DAYS: i64 = 60;
+
+impl FromStr for StatsWindow {
+    type Err = Report;
+
+    /// Parses a number of blocks like `144` or a number of days like `30d`.
+    fn from_str(s: &str) -> Result<Self> {
+        let window = match s.strip_suffix('d') {
+            Some(days) => StatsWindow::Days(days.parse()?),
+            None => StatsWindow::Blocks(s.parse()?),
+        };
+        match window {
+            StatsWindow::Blocks(1..=MAX_STATS_WINDOW_BLOCKS)
+            | StatsWindow::Days(1..=MAX_STATS_WINDOW_DAYS) => Ok(window),
+            _ => Err(eyre!("Window out of range: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for StatsWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StatsWindow::Blocks(num_blocks) => write!(f, "Last {} blocks", num_blocks),
+            StatsWindow::Days(num_days) => write!(f, "Last {} days", num_days),
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use bitcoinsuite_chronik_client::proto::Tx;
+use bitcoinsuite_core::CashAddress;
+use bitcoinsuite_error::Result;
+use eyre::eyre;
+use serde::{Deserialize, Serialize};
+
+use crate::blockchain::{cash_addr_to_script_type_payload, script_from_type_payload};
+
+const INITIAL_SUBSIDY: i64 = 50 * 100_000_000;
+const HALVING_INTERVAL: i32 = 210_000;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CoinbaseOutputKind {
+    Miner,
+    MinerFund,
+    StakingReward,
+}
+
+/// Share of the block subsidy that has to be paid to `kind` between
+/// `start_height` and `end_height` (inclusive).
+#[derive(Deserialize, Clone)]
+pub struct CoinbaseRule {
+    pub kind: CoinbaseOutputKind,
+    pub start_height: i32,
+    pub end_height: Option<i32>,
+    /// Addresses the share is paid to. If empty, the smallest output paying at
+    /// least the share is attributed to `kind`, as for staking rewards, whose
+    /// payout address changes every block.
+    #[serde(default)]
+    pub addresses: Vec<String>,
+    pub share: f64,
+}
+
+/// Configured `CoinbaseRule`s, with their addresses converted to scripts.
+#[derive(Default)]
+pub struct CoinbaseRules {
+    rules: Vec<(CoinbaseRule, Vec<Vec<u8>>)>,
+}
+
+pub struct CoinbaseOutput {
+    pub out_idx: usize,
+    pub kind: CoinbaseOutputKind,
+    pub value: i64,
+}
+
+/// Coinbase outputs labeled by who they pay, plus any deviations from the
+/// rules in effect at the block's height.
+pub struct CoinbaseBreakdown {
+    pub subsidy: i64,
+    pub fees: i64,
+    pub outputs: Vec<CoinbaseOutput>,
+    pub warnings: Vec<String>,
+}
+
+impl CoinbaseOutputKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            CoinbaseOutputKind::Miner => "Miner",
+            CoinbaseOutputKind::MinerFund => "Miner fund",
+            CoinbaseOutputKind::StakingReward => "Staking reward",
+        }
+    }
+}
+
+pub fn block_subsidy(height: i32) -> i64 {
+    match height / HALVING_INTERVAL {
+        halvings @ 0..=63 => INITIAL_SUBSIDY >> halvings,
+        _ => 0,
+    }
+}
+
+impl CoinbaseRules {
+    pub fn new(rules: Vec<CoinbaseRule>) -> Result<Self> {
+        let rules = rules
+            .into_iter()
+            .map(|rule| {
+                let scripts = rule
+                    .addresses
+                    .iter()
+                    .map(|address| {
+                        let address = CashAddress::parse_cow(address.into())
+                            .map_err(|_| eyre!("Invalid coinbase rule address {}", address))?;
+                        let (script_type, payload) = cash_addr_to_script_type_payload(&address);
+                        Ok(script_from_type_payload(script_type, &payload))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok((rule, scripts))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(CoinbaseRules { rules })
+    }
+
+    /// Labels the outputs of the coinbase tx at `height` of a block
+    /// collecting `fees`, and checks them against the rules in effect.
+    pub fn breakdown(&self, height: i32, coinbase_tx: &Tx, fees: i64) -> CoinbaseBreakdown {
+        let subsidy = block_subsidy(height);
+        let active_rules = self
+            .rules
+            .iter()
+            .filter(|(rule, _)| {
+                rule.start_height <= height && rule.end_height.map_or(true, |end| height <= end)
+            })
+            .collect::<Vec<_>>();
+        let expected_amount = |rule: &CoinbaseRule| (subsidy as f64 * rule.share) as i64;
+
+        let outputs = &coinbase_tx.outputs;
+        let mut kinds = vec![CoinbaseOutputKind::Miner; outputs.len()];
+        for (rule, scripts) in &active_rules {
+            for (idx, output) in outputs.iter().enumerate() {
+                if scripts.contains(&output.output_script) {
+                    kinds[idx] = rule.kind;
+                }
+            }
+        }
+        for (rule, _) in active_rules
+            .iter()
+            .filter(|(_, scripts)| scripts.is_empty())
+        {
+            let expected = expected_amount(rule);
+            let smallest_match = outputs
+                .iter()
+                .enumerate()
+                .filter(|(idx, output)| {
+                    kinds[*idx] == CoinbaseOutputKind::Miner && output.value >= expected
+                })
+                .min_by_key(|(_, output)| output.value);
+            if let Some((idx, _)) = smallest_match {
+                kinds[idx] = rule.kind;
+            }
+        }
+
+        let mut warnings = Vec::new();
+        let total_output = outputs.iter().map(|output| output.value).sum::<i64>();
+        if total_output > subsidy + fees {
+            warnings.push(format!(
+                "Coinbase pays {} XEC more than subsidy plus fees",
+                format_xec(total_output - subsidy - fees),
+            ));
+        }
+        for (rule, _) in &active_rules {
+            let paid = outputs
+                .iter()
+                .zip(&kinds)
+                .filter(|(_, kind)| **kind == rule.kind)
+                .map(|(output, _)| output.value)
+                .sum::<i64>();
+            let expected = expected_amount(rule);
+            if paid < expected {
+                warnings.push(format!(
+                    "{} receives {} XEC, expected at least {} XEC",
+                    rule.kind.label(),
+                    format_xec(paid),
+                    format_xec(expected),
+                ));
+            }
+        }
+
+        CoinbaseBreakdown {
+            subsidy,
+            fees,
+            outputs: outputs
+                .iter()
+                .zip(kinds)
+                .enumerate()
+                .map(|(out_idx, (output, kind))| CoinbaseOutput {
+                    out_idx,
+                    kind,
+                    value: output.value,
+                })
+                .collect(),
+            warnings,
+        }
+    }
+}
+
+fn format_xec(sats: i64) -> String {
+    format!("{:.2}", sats as f64 / 100.0)
+}
@@ -0,0 +1,548 @@
+pub const OP_0: u8 = 0x00;
+pub const OP_PUSHDATA1: u8 = 0x4c;
+pub const OP_PUSHDATA2: u8 = 0x4d;
+pub const OP_PUSHDATA4: u8 = 0x4e;
+pub const OP_1: u8 = 0x51;
+pub const OP_16: u8 = 0x60;
+pub const OP_RETURN: u8 = 0x6a;
+pub const OP_DROP: u8 = 0x75;
+pub const OP_DUP: u8 = 0x76;
+pub const OP_EQUAL: u8 = 0x87;
+pub const OP_EQUALVERIFY: u8 = 0x88;
+pub const OP_RIPEMD160: u8 = 0xa6;
+pub const OP_SHA1: u8 = 0xa7;
+pub const OP_SHA256: u8 = 0xa8;
+pub const OP_HASH160: u8 = 0xa9;
+pub const OP_HASH256: u8 = 0xaa;
+pub const OP_CHECKSIG: u8 = 0xac;
+pub const OP_CHECKMULTISIG: u8 = 0xae;
+pub const OP_CHECKLOCKTIMEVERIFY: u8 = 0xb1;
+
+/// A single parsed script operation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ScriptOp {
+    /// Push of `data` using `opcode`, either a direct push or OP_PUSHDATAn.
+    Push { opcode: u8, data: Vec<u8> },
+    /// Any non-push opcode, including OP_0 and OP_1..OP_16.
+    Code(u8),
+    /// A push whose length or data runs past the end of the script; holds
+    /// the remaining bytes starting at the push opcode.
+    Truncated(Vec<u8>),
+}
+
+/// Classification of an input script, based on the output it spends.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ScriptSig {
+    P2pkh {
+        signature: Vec<u8>,
+        pubkey: Vec<u8>,
+    },
+    P2pk {
+        signature: Vec<u8>,
+    },
+    /// Spend of a bare multisig output.
+    Multisig {
+        num_required: u8,
+        num_keys: u8,
+        signatures: Vec<Vec<u8>>,
+    },
+    P2sh {
+        redeem_script: Vec<u8>,
+        /// Set if the redeem script is an m-of-n multisig script.
+        multisig: Option<(u8, u8)>,
+    },
+    Unknown,
+}
+
+/// Splits `script` into its operations; a malformed push ends the script
+/// with `ScriptOp::Truncated`.
+pub fn parse_script(script: &[u8]) -> Vec<ScriptOp> {
+    let mut ops = Vec::new();
+    let mut idx = 0;
+    while idx < script.len() {
+        let opcode = script[idx];
+        let (len_size, data_len) = match opcode {
+            0x01..=0x4b => (0, Some(opcode as usize)),
+            OP_PUSHDATA1 => (1, read_len(script, idx + 1, 1)),
+            OP_PUSHDATA2 => (2, read_len(script, idx + 1, 2)),
+            OP_PUSHDATA4 => (4, read_len(script, idx + 1, 4)),
+            _ => {
+                ops.push(ScriptOp::Code(opcode));
+                idx += 1;
+                continue;
+            }
+        };
+        let data_start = idx + 1 + len_size;
+        match data_len.and_then(|data_len| data_start.checked_add(data_len)) {
+            Some(data_end) if data_end <= script.len() => {
+                ops.push(ScriptOp::Push {
+                    opcode,
+                    data: script[data_start..data_end].to_vec(),
+                });
+                idx = data_end;
+            }
+            _ => {
+                ops.push(ScriptOp::Truncated(script[idx..].to_vec()));
+                break;
+            }
+        }
+    }
+    ops
+}
+
+fn read_len(script: &[u8], start: usize, size: usize) -> Option<usize> {
+    let bytes = script.get(start..start + size)?;
+    Some(match size {
+        1 => bytes[0] as usize,
+        2 => u16::from_le_bytes(bytes.try_into().ok()?) as usize,
+        _ => u32::from_le_bytes(bytes.try_into().ok()?) as usize,
+    })
+}
+
+/// Renders `script` in ASM notation, e.g. `OP_DUP OP_HASH160 <hex> ...`.
+pub fn disassemble(script: &[u8]) -> String {
+    parse_script(script)
+        .iter()
+        .map(|op| match op {
+            ScriptOp::Push { opcode, data } if *opcode < OP_PUSHDATA1 => hex::encode(data),
+            ScriptOp::Push { opcode, data } => {
+                format!("{} {}", opcode_name(*opcode), hex::encode(data))
+            }
+            ScriptOp::Code(opcode) => opcode_name(*opcode),
+            ScriptOp::Truncated(bytes) => format!("[error: truncated push {}]", hex::encode(bytes)),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn opcode_name(opcode: u8) -> String {
+    let name = match opcode {
+        OP_0 => "OP_0",
+        OP_PUSHDATA1 => "OP_PUSHDATA1",
+        OP_PUSHDATA2 => "OP_PUSHDATA2",
+        OP_PUSHDATA4 => "OP_PUSHDATA4",
+        0x4f => "OP_1NEGATE",
+        0x50 => "OP_RESERVED",
+        OP_1..=OP_16 => return format!("OP_{}", opcode - OP_1 + 1),
+        0x61 => "OP_NOP",
+        0x62 => "OP_VER",
+        0x63 => "OP_IF",
+        0x64 => "OP_NOTIF",
+        0x65 => "OP_VERIF",
+        0x66 => "OP_VERNOTIF",
+        0x67 => "OP_ELSE",
+        0x68 => "OP_ENDIF",
+        0x69 => "OP_VERIFY",
+        OP_RETURN => "OP_RETURN",
+        0x6b => "OP_TOALTSTACK",
+        0x6c => "OP_FROMALTSTACK",
+        0x6d => "OP_2DROP",
+        0x6e => "OP_2DUP",
+        0x6f => "OP_3DUP",
+        0x70 => "OP_2OVER",
+        0x71 => "OP_2ROT",
+        0x72 => "OP_2SWAP",
+        0x73 => "OP_IFDUP",
+        0x74 => "OP_DEPTH",
+        OP_DROP => "OP_DROP",
+        OP_DUP => "OP_DUP",
+        0x77 => "OP_NIP",
+        0x78 => "OP_OVER",
+        0x79 => "OP_PICK",
+        0x7a => "OP_ROLL",
+        0x7b => "OP_ROT",
+        0x7c => "OP_SWAP",
+        0x7d => "OP_TUCK",
+        0x7e => "OP_CAT",
+        0x7f => "OP_SPLIT",
+        0x80 => "OP_NUM2BIN",
+        0x81 => "OP_BIN2NUM",
+        0x82 => "OP_SIZE",
+        0x83 => "OP_INVERT",
+        0x84 => "OP_AND",
+        0x85 => "OP_OR",
+        0x86 => "OP_XOR",
+        OP_EQUAL => "OP_EQUAL",
+        OP_EQUALVERIFY => "OP_EQUALVERIFY",
+        0x89 => "OP_RESERVED1",
+        0x8a => "OP_RESERVED2",
+        0x8b => "OP_1ADD",
+        0x8c => "OP_1SUB",
+        0x8d => "OP_2MUL",
+        0x8e => "OP_2DIV",
+        0x8f => "OP_NEGATE",
+        0x90 => "OP_ABS",
+        0x91 => "OP_NOT",
+        0x92 => "OP_0NOTEQUAL",
+        0x93 => "OP_ADD",
+        0x94 => "OP_SUB",
+        0x95 => "OP_MUL",
+        0x96 => "OP_DIV",
+        0x97 => "OP_MOD",
+        0x98 => "OP_LSHIFT",
+        0x99 => "OP_RSHIFT",
+        0x9a => "OP_BOOLAND",
+        0x9b => "OP_BOOLOR",
+        0x9c => "OP_NUMEQUAL",
+        0x9d => "OP_NUMEQUALVERIFY",
+        0x9e => "OP_NUMNOTEQUAL",
+        0x9f => "OP_LESSTHAN",
+        0xa0 => "OP_GREATERTHAN",
+        0xa1 => "OP_LESSTHANOREQUAL",
+        0xa2 => "OP_GREATERTHANOREQUAL",
+        0xa3 => "OP_MIN",
+        0xa4 => "OP_MAX",
+        0xa5 => "OP_WITHIN",
+        OP_RIPEMD160 => "OP_RIPEMD160",
+        OP_SHA1 => "OP_SHA1",
+        OP_SHA256 => "OP_SHA256",
+        OP_HASH160 => "OP_HASH160",
+        OP_HASH256 => "OP_HASH256",
+        0xab => "OP_CODESEPARATOR",
+        OP_CHECKSIG => "OP_CHECKSIG",
+        0xad => "OP_CHECKSIGVERIFY",
+        OP_CHECKMULTISIG => "OP_CHECKMULTISIG",
+        0xaf => "OP_CHECKMULTISIGVERIFY",
+        0xb0 => "OP_NOP1",
+        OP_CHECKLOCKTIMEVERIFY => "OP_CHECKLOCKTIMEVERIFY",
+        0xb2 => "OP_CHECKSEQUENCEVERIFY",
+        0xb3..=0xb9 => return format!("OP_NOP{}", opcode - 0xb3 + 4),
+        0xba => "OP_CHECKDATASIG",
+        0xbb => "OP_CHECKDATASIGVERIFY",
+        0xbc => "OP_REVERSEBYTES",
+        // Native introspection
+        0xc0 => "OP_INPUTINDEX",
+        0xc1 => "OP_ACTIVEBYTECODE",
+        0xc2 => "OP_TXVERSION",
+        0xc3 => "OP_TXINPUTCOUNT",
+        0xc4 => "OP_TXOUTPUTCOUNT",
+        0xc5 => "OP_TXLOCKTIME",
+        0xc6 => "OP_UTXOVALUE",
+        0xc7 => "OP_UTXOBYTECODE",
+        0xc8 => "OP_OUTPOINTTXHASH",
+        0xc9 => "OP_OUTPOINTINDEX",
+        0xca => "OP_INPUTBYTECODE",
+        0xcb => "OP_INPUTSEQUENCENUMBER",
+        0xcc => "OP_OUTPUTVALUE",
+        0xcd => "OP_OUTPUTBYTECODE",
+        _ => return format!("OP_UNKNOWN<0x{:02x}>", opcode),
+    };
+    name.to_string()
+}
+
+/// Parses an m-of-n `OP_m <pubkey>... OP_n OP_CHECKMULTISIG` script into
+/// `(m, n, pubkeys)`.
+pub fn parse_multisig(script: &[u8]) -> Option<(u8, u8, Vec<Vec<u8>>)> {
+    let ops = parse_script(script);
+    let (first, rest) = ops.split_first()?;
+    let (last, rest) = rest.split_last()?;
+    let (n_op, pubkey_ops) = rest.split_last()?;
+    let num_required = small_int(first)?;
+    let num_keys = small_int(n_op)?;
+    if *last != ScriptOp::Code(OP_CHECKMULTISIG)
+        || num_required > num_keys
+        || pubkey_ops.len() != num_keys as usize
+    {
+        return None;
+    }
+    let pubkeys = pubkey_ops
+        .iter()
+        .map(|op| match op {
+            ScriptOp::Push { data, .. } if data.len() == 33 || data.len() == 65 => {
+                Some(data.clone())
+            }
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some((num_required, num_keys, pubkeys))
+}
+
+/// Decodes a minimally encoded script number of up to 8 bytes, e.g. a
+/// locktime pushed for OP_CHECKLOCKTIMEVERIFY.
+pub fn decode_script_num(data: &[u8]) -> Option<i64> {
+    let (last, rest) = match data.split_last() {
+        Some(split) => split,
+        None => return Some(0),
+    };
+    if data.len() > 8 {
+        return None;
+    }
+    let mut num = (*last & 0x7f) as i64;
+    for byte in rest.iter().rev() {
+        num = (num << 8) | *byte as i64;
+    }
+    if *last & 0x80 != 0 {
+        num = -num;
+    }
+    Some(num)
+}
+
+fn small_int(op: &ScriptOp) -> Option<u8> {
+    match op {
+        ScriptOp::Code(opcode @ OP_1..=OP_16) => Some(opcode - OP_1 + 1),
+        _ => None,
+    }
+}
+
+/// Classifies `input_script` by the shape of `output_script`, the script of
+/// the output it spends.
+pub fn classify_script_sig(input_script: &[u8], output_script: &[u8]) -> ScriptSig {
+    let pushes = match parse_script(input_script)
+        .into_iter()
+        .map(|op| match op {
+            ScriptOp::Push { data, .. } => Some(data),
+            ScriptOp::Code(OP_0) => Some(vec![]),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()
+    {
+        Some(pushes) => pushes,
+        None => return ScriptSig::Unknown,
+    };
+
+    match output_script {
+        [OP_DUP, OP_HASH160, 20, .., OP_EQUALVERIFY, OP_CHECKSIG] if output_script.len() == 25 => {
+            match pushes.as_slice() {
+                [signature, pubkey] => ScriptSig::P2pkh {
+                    signature: signature.clone(),
+                    pubkey: pubkey.clone(),
+                },
+                _ => ScriptSig::Unknown,
+            }
+        }
+        [OP_HASH160, 20, .., OP_EQUAL] if output_script.len() == 23 => match pushes.last() {
+            Some(redeem_script) => ScriptSig::P2sh {
+                multisig: parse_multisig(redeem_script).map(|(m, n, _)| (m, n)),
+                redeem_script: redeem_script.clone(),
+            },
+            None => ScriptSig::Unknown,
+        },
+        [33, .., OP_CHECKSIG] | [65, .., OP_CHECKSIG]
+            if output_script.len() == output_script[0] as usize + 2 =>
+        {
+            match pushes.as_slice() {
+                [signature] => ScriptSig::P2pk {
+                    signature: signature.clone(),
+                },
+                _ => ScriptSig::Unknown,
+            }
+        }
+        _ => match parse_multisig(output_script) {
+            // The leading OP_0 is a dummy element consumed by OP_CHECKMULTISIG
+            Some((num_required, num_keys, _)) if !pushes.is_empty() => ScriptSig::Multisig {
+                num_required,
+                num_keys,
+                signatures: pushes[1..].to_vec(),
+            },
+            _ => ScriptSig::Unknown,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        classify_script_sig, decode_script_num, disassemble, parse_script, ScriptOp, ScriptSig,
+    };
+
+    fn script(script_hex: &str) -> Vec<u8> {
+        hex::decode(script_hex.replace(' ', "")).unwrap()
+    }
+
+    fn push(opcode: u8, data_hex: &str) -> ScriptOp {
+        ScriptOp::Push {
+            opcode,
+            data: script(data_hex),
+        }
+    }
+
+    #[test]
+    fn test_parse_script() {
+        let truncated = |script_hex| ScriptOp::Truncated(script(script_hex));
+        let cases = vec![
+            ("", vec![]),
+            (
+                "00 51 ac",
+                vec![
+                    ScriptOp::Code(0x00),
+                    ScriptOp::Code(0x51),
+                    ScriptOp::Code(0xac),
+                ],
+            ),
+            ("02 aabb 01 cc", vec![push(0x02, "aabb"), push(0x01, "cc")]),
+            ("03 aabb", vec![truncated("03aabb")]),
+            ("51 02 aa", vec![ScriptOp::Code(0x51), truncated("02aa")]),
+            ("4c 02 aabb", vec![push(0x4c, "aabb")]),
+            ("4c 00", vec![push(0x4c, "")]),
+            ("4c", vec![truncated("4c")]),
+            ("4c 03 aabb", vec![truncated("4c03aabb")]),
+            (
+                "4d 0200 aabb 75",
+                vec![push(0x4d, "aabb"), ScriptOp::Code(0x75)],
+            ),
+            ("4d 02", vec![truncated("4d02")]),
+            ("4d 0300 aabb", vec![truncated("4d0300aabb")]),
+            ("4e 02000000 aabb", vec![push(0x4e, "aabb")]),
+            ("4e 020000", vec![truncated("4e020000")]),
+            ("4e ffffffff aabb", vec![truncated("4effffffffaabb")]),
+        ];
+        for (script_hex, expected) in cases {
+            assert_eq!(
+                parse_script(&script(script_hex)),
+                expected,
+                "{}",
+                script_hex
+            );
+        }
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let hash = "11".repeat(20);
+        let p2pkh = format!("76 a9 14 {} 88 ac", hash);
+        let p2pkh_asm = format!("OP_DUP OP_HASH160 {} OP_EQUALVERIFY OP_CHECKSIG", hash);
+        let cases = vec![
+            (p2pkh.as_str(), p2pkh_asm.as_str()),
+            ("", ""),
+            ("00 4f 51 60 50", "OP_0 OP_1NEGATE OP_1 OP_16 OP_RESERVED"),
+            ("4c 02 aabb", "OP_PUSHDATA1 aabb"),
+            ("4d 0100 aa", "OP_PUSHDATA2 aa"),
+            ("4e 01000000 aa", "OP_PUSHDATA4 aa"),
+            (
+                "6a 02 aabb 03 aa",
+                "OP_RETURN aabb [error: truncated push 03aa]",
+            ),
+            ("4d 01", "[error: truncated push 4d01]"),
+            ("b1 75", "OP_CHECKLOCKTIMEVERIFY OP_DROP"),
+            ("b2 b3 b9", "OP_CHECKSEQUENCEVERIFY OP_NOP4 OP_NOP10"),
+            (
+                "ba bb bc",
+                "OP_CHECKDATASIG OP_CHECKDATASIGVERIFY OP_REVERSEBYTES",
+            ),
+            ("7e 7f 80 81", "OP_CAT OP_SPLIT OP_NUM2BIN OP_BIN2NUM"),
+            (
+                "c0 c1 c2 c3 c4 c5 c6",
+                "OP_INPUTINDEX OP_ACTIVEBYTECODE OP_TXVERSION OP_TXINPUTCOUNT \
+                 OP_TXOUTPUTCOUNT OP_TXLOCKTIME OP_UTXOVALUE",
+            ),
+            (
+                "c7 c8 c9 ca cb cc cd",
+                "OP_UTXOBYTECODE OP_OUTPOINTTXHASH OP_OUTPOINTINDEX OP_INPUTBYTECODE \
+                 OP_INPUTSEQUENCENUMBER OP_OUTPUTVALUE OP_OUTPUTBYTECODE",
+            ),
+            ("ce ff", "OP_UNKNOWN<0xce> OP_UNKNOWN<0xff>"),
+        ];
+        for (script_hex, expected) in cases {
+            assert_eq!(disassemble(&script(script_hex)), expected, "{}", script_hex);
+        }
+    }
+
+    #[test]
+    fn test_decode_script_num() {
+        let cases = vec![
+            ("", Some(0)),
+            ("00", Some(0)),
+            // Negative zero
+            ("80", Some(0)),
+            ("01", Some(1)),
+            ("81", Some(-1)),
+            ("7f", Some(127)),
+            ("ff", Some(-127)),
+            ("8000", Some(128)),
+            ("8080", Some(-128)),
+            ("ff00", Some(255)),
+            ("ff80", Some(-255)),
+            ("00e1f505", Some(100_000_000)),
+            ("00e1f585", Some(-100_000_000)),
+            // 5-byte CLTV operand
+            ("ffffffff00", Some(0xffff_ffff)),
+            ("ffffffffffffff7f", Some(i64::MAX)),
+            ("ffffffffffffffff", Some(-i64::MAX)),
+            ("000000000000000000", None),
+        ];
+        for (data_hex, expected) in cases {
+            assert_eq!(
+                decode_script_num(&script(data_hex)),
+                expected,
+                "{}",
+                data_hex
+            );
+        }
+    }
+
+    #[test]
+    fn test_classify_script_sig() {
+        let pubkey = format!("21 {}", "02".repeat(33));
+        let multisig = format!("51 {} {} 52 ae", pubkey, pubkey);
+        let p2pkh = format!("76 a9 14 {} 88 ac", "11".repeat(20));
+        let p2sh = format!("a9 14 {} 87", "22".repeat(20));
+        let p2pk = format!("{} ac", pubkey);
+        let p2pk_uncompressed = format!("41 {} ac", "04".repeat(65));
+        let multisig_spend = format!("00 02 aabb 47 {}", multisig);
+        let cases = vec![
+            (
+                "02 aabb 03 ccddee",
+                p2pkh.as_str(),
+                ScriptSig::P2pkh {
+                    signature: script("aabb"),
+                    pubkey: script("ccddee"),
+                },
+            ),
+            ("02 aabb", p2pkh.as_str(), ScriptSig::Unknown),
+            ("02 aabb 76", p2pkh.as_str(), ScriptSig::Unknown),
+            ("03 aabb", p2pkh.as_str(), ScriptSig::Unknown),
+            (
+                "02 aabb",
+                p2pk.as_str(),
+                ScriptSig::P2pk {
+                    signature: script("aabb"),
+                },
+            ),
+            (
+                "4c 02 aabb",
+                p2pk_uncompressed.as_str(),
+                ScriptSig::P2pk {
+                    signature: script("aabb"),
+                },
+            ),
+            ("02 aabb 02 ccdd", p2pk.as_str(), ScriptSig::Unknown),
+            (
+                "00 02 aabb",
+                multisig.as_str(),
+                ScriptSig::Multisig {
+                    num_required: 1,
+                    num_keys: 2,
+                    signatures: vec![script("aabb")],
+                },
+            ),
+            ("", multisig.as_str(), ScriptSig::Unknown),
+            (
+                multisig_spend.as_str(),
+                p2sh.as_str(),
+                ScriptSig::P2sh {
+                    redeem_script: script(&multisig),
+                    multisig: Some((1, 2)),
+                },
+            ),
+            (
+                "01 51 01 75",
+                p2sh.as_str(),
+                ScriptSig::P2sh {
+                    redeem_script: script("75"),
+                    multisig: None,
+                },
+            ),
+            ("", p2sh.as_str(), ScriptSig::Unknown),
+            ("02 aabb", "6a 02 aabb", ScriptSig::Unknown),
+        ];
+        for (input_hex, output_hex, expected) in cases {
+            assert_eq!(
+                classify_script_sig(&script(input_hex), &script(output_hex)),
+                expected,
+                "{} spending {}",
+                input_hex,
+                output_hex,
+            );
+        }
+    }
+}
@@ -1,84 +1,414 @@
 use askama::Template;
 use axum::{response::Redirect, routing::get, Router};
-use bitcoinsuite_chronik_client::proto::{SlpTokenType, SlpTxType, Token, Utxo};
-use bitcoinsuite_chronik_client::{proto::OutPoint, ChronikClient};
-use bitcoinsuite_core::{CashAddress, Hashed, Sha256d};
+use bitcoinsuite_chronik_client::proto::{
+    Block, BlockInfo, BlockchainInfo, OutPoint, SlpTokenType, SlpTxType, Token, Tx, Utxo,
+};
+use bitcoinsuite_chronik_client::ScriptType;
+use bitcoinsuite_core::{AddressType, CashAddress, Hashed, Sha256d};
 use bitcoinsuite_error::Result;
 use chrono::{TimeZone, Utc};
-use eyre::{bail, eyre};
-use futures::future;
+use eyre::{bail, eyre, Report};
+use futures::{future, stream, Stream, StreamExt, TryStreamExt};
+use lru::LruCache;
 use std::path::PathBuf;
 use std::{
     borrow::Cow,
     collections::{hash_map::Entry, HashMap, HashSet},
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
 };
+use tokio::sync::{broadcast, mpsc, Semaphore};
 
 use crate::{
-    api::{block_txs_to_json, calc_tx_stats, tokens_to_json, tx_history_to_json},
+    api::{
+        balance_history_to_json, block_info_to_json, block_stats_to_json, block_txs_to_json,
+        calc_tx_stats, calc_tx_stats_matching, fee_rate_histogram, format_token_amount,
+        miner_stats_window_to_json, slp_action_str, slp_token_type_str, token_balances_to_json,
+        token_to_json, token_tx_to_json, tokens_to_json, tx_details_to_json, tx_history_to_json,
+        tx_to_json, AddressPrefixes, BalanceDelta,
+    },
+    backend::ExplorerBackend,
     blockchain::{
-        calculate_block_difficulty, cash_addr_to_script_type_payload, from_be_hex, to_be_hex,
-        to_legacy_address,
+        calculate_block_difficulty, cash_addr_to_script_type_payload, destination_from_script,
+        from_be_hex, parse_script_type, redeem_script, script_from_type_payload, script_type_name,
+        to_be_hex, to_legacy_address, Destination,
+    },
+    cache::CachedBackend,
+    charts::{BlockSummary, ChainStats, ChartSeries, ALL_CHART_SERIES},
+    coinbase::parse_coinbase,
+    config::{CacheConfig, ChartsConfig},
+    difficulty::{asert_expectation, average_block_time, estimate_window_hashrate},
+    events::{
+        EventSource, ExplorerEvent, RelayedEvent, ScriptSubscription, ScriptWatch, WatchedAddress,
     },
+    export::{export_row, ExportFormat},
+    mempool::Mempool,
+    op_return::OpReturnRegistry,
+    pools::{PoolRegistry, StatsWindow, DEFAULT_STATS_WINDOWS, MAX_STATS_WINDOW_BLOCKS},
+    reward::CoinbaseRules,
+    script::OP_RETURN,
+    server_error::ServerError,
     server_http::{
-        address, address_qr, block, block_height, blocks, data_address_txs, data_block_txs,
-        data_blocks, homepage, search, serve_files, tx,
+        address, address_qr, block, block_height, blocks, charts, data_address_balance,
+        data_address_balance_history, data_address_export, data_address_txs, data_address_utxos,
+        data_block_stats, data_block_txs, data_blocks, data_cache_stats, data_chart,
+        data_difficulty, data_mempool, data_mempool_summary, data_miners, data_nft_children,
+        data_script, data_script_txs, data_script_utxos, data_token, data_tx, data_wallet,
+        data_wallet_form, data_xpub, homepage, mempool, miners, nft_children, not_found, script,
+        search, serve_files, token, tx, wallet, wallet_form, ws, xpub,
+    },
+    server_primitives::{
+        JsonAddressBalanceResponse, JsonBalance, JsonBalanceHistoryResponse, JsonBlockStats,
+        JsonBlocksResponse, JsonCacheStats, JsonChartSeries, JsonDifficultyResponse,
+        JsonMempoolResponse, JsonMempoolSummary, JsonMinerStatsResponse, JsonMintBaton,
+        JsonNftChild, JsonNftChildrenResponse, JsonScriptResponse, JsonTokenBalance,
+        JsonTokenResponse, JsonTx, JsonTxDetails, JsonTxsResponse, JsonUtxo, JsonUtxosResponse,
+        JsonWalletAddress, JsonWalletResponse, JsonWalletTx, JsonWalletUtxo, JsonWsMessage,
+        JsonXpubAddress, JsonXpubResponse,
     },
-    server_primitives::{JsonBalance, JsonBlock, JsonBlocksResponse, JsonTxsResponse, JsonUtxo},
     templating::{
-        AddressTemplate, BlockTemplate, BlocksTemplate, HomepageTemplate, TransactionTemplate,
+        AddressTemplate, BlockTemplate, BlocksTemplate, ChartsTemplate, HomepageTemplate,
+        MempoolTemplate, MinersTemplate, NftChildrenTemplate, ScriptTemplate, TokenTemplate,
+        TransactionTemplate, WalletTemplate, XpubTemplate,
     },
+    xpub::{DerivedAddress, Xpub, CHANGE_CHAIN, RECEIVE_CHAIN},
 };
 
+struct AddressBalances {
+    token_dust: i64,
+    total_xec: i64,
+    token_utxos: Vec<Utxo>,
+    json_balances: HashMap<String, JsonBalance>,
+    tokens: HashMap<String, Token>,
+}
+
+/// Max. number of addresses a wallet view aggregates.
+const MAX_WALLET_ADDRESSES: usize = 100;
+
+/// Number of latest txs of each address merged into a wallet's history.
+const WALLET_HISTORY_PAGE_SIZE: usize = 200;
+
+/// Max. number of txs listed per page of a wallet's history.
+const MAX_WALLET_TXS_TAKE: usize = 200;
+
+/// Number of wallet addresses whose balances and history are fetched in
+/// parallel.
+const WALLET_LOOKUP_CONCURRENCY: usize = 8;
+
+/// Number of unused addresses in a row after which an xpub chain is assumed
+/// to have no more used addresses, as in BIP44.
+const DEFAULT_GAP_LIMIT: u32 = 20;
+
+/// Max. gap limit accepted in the `gap` query param.
+const MAX_GAP_LIMIT: u32 = 100;
+
+/// Max. number of addresses derived per xpub chain.
+const MAX_XPUB_CHAIN_ADDRESSES: u32 = 1_000;
+
+/// Number of derived xpub addresses whose history is checked in parallel.
+const XPUB_LOOKUP_CONCURRENCY: usize = 8;
+
+/// Max. size of an output script, as enforced by consensus.
+const MAX_SCRIPT_SIZE: usize = 10_000;
+
+/// Number of relayed events buffered per websocket connection before it lags.
+const EVENTS_CAPACITY: usize = 256;
+
+/// Max. number of spending txs fetched to unwrap a tx's spent P2SH outputs.
+const MAX_REDEEM_SCRIPT_LOOKUPS: usize = 20;
+
+/// Max. number of txs listed per page of a token's txs.
+const MAX_TOKEN_TXS_TAKE: usize = 200;
+
+/// Max. number of txs listed per page of the mempool.
+const MAX_MEMPOOL_TXS_TAKE: usize = 200;
+
+/// Max. number of txs listed per page of a script's history.
+const MAX_SCRIPT_TXS_TAKE: usize = 200;
+
+/// Max. number of txs walked to list a page of a token's txs, which limits
+/// how deep it can be paged.
+const MAX_TOKEN_TXS_WALKED: usize = 5_000;
+
+/// Max. number of NFTs listed per page of an NFT1 group's children.
+const MAX_NFT_CHILDREN_TAKE: usize = 100;
+
+/// Max. number of txs of an NFT1 group walked to find a page of its children.
+const MAX_NFT_GROUP_TXS_WALKED: usize = 10_000;
+
+/// Number of NFTs whose holder is looked up in parallel.
+const NFT_HOLDER_LOOKUP_CONCURRENCY: usize = 8;
+
+/// Number of txs fetched in parallel while walking a token's tx graph.
+const TOKEN_TXS_CONCURRENCY: usize = 16;
+
+/// Max. number of spending txs followed per request to locate a token's mint
+/// baton or NFT; longer paths are resumed by later requests.
+const MAX_TOKEN_OUTPUT_HOPS: usize = 500;
+
+/// Number of tokens whose mint baton or NFT location is remembered.
+const TOKEN_OUTPUT_CACHE_CAPACITY: usize = 10_000;
+
+/// Number of blocks whose miner is remembered.
+const MINER_CACHE_CAPACITY: usize = 100_000;
+
+/// Number of blocks requested at once when collecting blocks for stats.
+const STATS_BLOCKS_PAGE_SIZE: i32 = 500;
+
+/// Number of blocks fetched in parallel to find their miners.
+const MINER_LOOKUP_CONCURRENCY: usize = 16;
+
+/// Max. number of blocks fetched to find their miners per page of blocks;
+/// the miners of the others are only shown once known.
+const MAX_LISTED_MINER_LOOKUPS: usize = 16;
+
+/// Time between checks whether the default miner stats windows are outdated.
+const MINER_STATS_SYNC_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Number of custom miner stats windows remembered for the current tip.
+const CUSTOM_MINER_STATS_CAPACITY: usize = 16;
+
+/// Number of latest blocks and txs listed on the homepage.
+const HOMEPAGE_NUM_BLOCKS: i32 = 10;
+const HOMEPAGE_NUM_TXS: usize = 10;
+
+/// Number of txs fetched per history page when exporting an address.
+const EXPORT_PAGE_SIZE: usize = 200;
+
+/// Number of txs fetched per history page to reconstruct balance history.
+const BALANCE_HISTORY_PAGE_SIZE: usize = 200;
+
+/// Max. number of txs walked back to reconstruct an address's balances.
+const MAX_BALANCE_HISTORY_TXS: usize = 20_000;
+
+/// Number of blocks hashrate and block time are averaged over by default.
+const DEFAULT_HASHRATE_WINDOW: i32 = 144;
+
+/// Expected number of blocks per day, used to size the charts' history.
+const BLOCKS_PER_DAY: i32 = 144;
+
+/// Number of blocks fetched in parallel to summarize them for the charts.
+const CHART_BLOCKS_CONCURRENCY: usize = 16;
+
 pub struct Server {
-    chronik: ChronikClient,
+    chronik: CachedBackend,
+    events: broadcast::Sender<RelayedEvent>,
+    /// Asks the event source to report txs of the scripts clients watch.
+    script_subscriptions: Option<mpsc::UnboundedSender<ScriptSubscription>>,
+    mempool: Mutex<Mempool>,
+    op_returns: OpReturnRegistry,
+    pools: PoolRegistry,
+    coinbase_rules: CoinbaseRules,
+    /// Name of the pool that mined a block, by block hash.
+    miner_cache: Mutex<LruCache<Sha256d, Option<String>>>,
+    /// Latest tx known to hold a token's mint baton (`true`) or NFT
+    /// (`false`), by token id, so `follow_token_output` needn't start over.
+    token_output_cache: Mutex<LruCache<(Sha256d, bool), Sha256d>>,
+    /// Stats of `DEFAULT_STATS_WINDOWS`, with the tip hash they're for.
+    miner_stats: Mutex<Option<(Vec<u8>, JsonMinerStatsResponse)>>,
+    /// Stats of custom windows, by window, with the tip hash they're for.
+    custom_miner_stats: Mutex<LruCache<String, (Vec<u8>, JsonMinerStatsResponse)>>,
+    /// Lets one custom window be computed at a time.
+    custom_miner_stats_permit: Semaphore,
+    chain_stats: Mutex<ChainStats>,
     base_dir: PathBuf,
     satoshi_addr_prefix: &'static str,
     tokens_addr_prefix: &'static str,
 }
 
 impl Server {
-    pub async fn setup(chronik: ChronikClient, base_dir: PathBuf) -> Result<Self> {
+    pub async fn setup(
+        chronik: impl ExplorerBackend + 'static,
+        base_dir: PathBuf,
+        cache_config: CacheConfig,
+    ) -> Result<Self> {
+        let satoshi_addr_prefix = "ecash";
         Ok(Server {
-            chronik,
+            chronik: CachedBackend::new(chronik, cache_config),
+            events: broadcast::channel(EVENTS_CAPACITY).0,
+            script_subscriptions: None,
+            mempool: Mutex::new(Mempool::default()),
+            op_returns: OpReturnRegistry::with_builtin_protocols(satoshi_addr_prefix),
+            pools: PoolRegistry::default(),
+            coinbase_rules: CoinbaseRules::default(),
+            miner_cache: Mutex::new(LruCache::new(MINER_CACHE_CAPACITY)),
+            token_output_cache: Mutex::new(LruCache::new(TOKEN_OUTPUT_CACHE_CAPACITY)),
+            miner_stats: Mutex::new(None),
+            custom_miner_stats: Mutex::new(LruCache::new(CUSTOM_MINER_STATS_CAPACITY)),
+            custom_miner_stats_permit: Semaphore::new(1),
+            chain_stats: Mutex::new(ChainStats::default()),
             base_dir,
-            satoshi_addr_prefix: "ecash",
+            satoshi_addr_prefix,
             tokens_addr_prefix: "etoken",
         })
     }
 
+    /// Replaces the built-in OP_RETURN protocols with `op_returns`.
+    pub fn with_op_return_registry(mut self, op_returns: OpReturnRegistry) -> Self {
+        self.op_returns = op_returns;
+        self
+    }
+
+    /// Attributes blocks to the mining pools in `pools`.
+    pub fn with_pool_registry(mut self, pools: PoolRegistry) -> Self {
+        self.pools = pools;
+        self
+    }
+
+    /// Subscribes the scripts websocket clients watch through
+    /// `script_subscriptions`, e.g. the sender of a `ChronikWsSource`.
+    pub fn with_script_subscriptions(
+        mut self,
+        script_subscriptions: mpsc::UnboundedSender<ScriptSubscription>,
+    ) -> Self {
+        self.script_subscriptions = Some(script_subscriptions);
+        self
+    }
+
+    /// Labels coinbase outputs and checks them against `coinbase_rules`.
+    pub fn with_coinbase_rules(mut self, coinbase_rules: CoinbaseRules) -> Self {
+        self.coinbase_rules = coinbase_rules;
+        self
+    }
+
     pub fn router(&self) -> Router {
         Router::new()
             .route("/", get(homepage))
             .route("/tx/:hash", get(tx))
             .route("/blocks", get(blocks))
+            .route("/mempool", get(mempool))
+            .route("/miners", get(miners))
+            .route("/charts", get(charts))
             .route("/block/:hash", get(block))
             .route("/block-height/:height", get(block_height))
+            .route("/token/:hash", get(token))
+            .route("/token/:hash/children", get(nft_children))
             .route("/address/:hash", get(address))
             .route("/address-qr/:hash", get(address_qr))
+            .route("/script/:type/:payload", get(script))
+            .route("/wallet", get(wallet).post(wallet_form))
+            .route("/xpub/:xpub", get(xpub))
             .route("/search/:query", get(search))
+            .route("/ws", get(ws))
             .route("/api/blocks/:start_height/:end_height", get(data_blocks))
+            .route("/api/mempool", get(data_mempool))
+            .route("/api/mempool/summary", get(data_mempool_summary))
+            .route("/api/miners", get(data_miners))
+            .route("/api/stats/:series", get(data_chart))
+            .route("/api/difficulty", get(data_difficulty))
             .route("/api/block/:hash/transactions", get(data_block_txs))
+            .route("/api/block/:hash/stats", get(data_block_stats))
             .route("/api/address/:hash/transactions", get(data_address_txs))
+            .route("/api/address/:hash/balance", get(data_address_balance))
+            .route("/api/address/:hash/export", get(data_address_export))
+            .route(
+                "/api/address/:hash/balance-history",
+                get(data_address_balance_history),
+            )
+            .route("/api/address/:hash/utxos", get(data_address_utxos))
+            .route("/api/script/:type/:payload", get(data_script))
+            .route(
+                "/api/script/:type/:payload/transactions",
+                get(data_script_txs),
+            )
+            .route("/api/script/:type/:payload/utxos", get(data_script_utxos))
+            .route("/api/wallet", get(data_wallet).post(data_wallet_form))
+            .route("/api/xpub/:xpub", get(data_xpub))
+            .route("/api/tx/:hash", get(data_tx))
+            .route("/api/token/:hash", get(data_token))
+            .route("/api/token/:hash/children", get(data_nft_children))
+            .route("/api/cache/stats", get(data_cache_stats))
             .nest("/code", serve_files(&self.base_dir.join("code")))
             .nest("/assets", serve_files(&self.base_dir.join("assets")))
             .nest(
                 "/favicon.ico",
                 serve_files(&self.base_dir.join("assets").join("favicon.png")),
             )
+            .fallback(get(not_found))
     }
 }
 
 impl Server {
     pub async fn homepage(&self) -> Result<String> {
-        let homepage = HomepageTemplate {};
+        let blockchain_info = self.chronik.blockchain_info().await?;
+        let tip_height = blockchain_info.tip_height;
+        let tip_hash = to_be_hex(&blockchain_info.tip_hash);
+
+        let blocks = self
+            .data_blocks((tip_height - HOMEPAGE_NUM_BLOCKS + 1).max(0), tip_height)
+            .await?
+            .data;
+        let (mempool_num_txs, mempool_bytes, mempool_txs) = {
+            let mempool = self.mempool.lock().unwrap();
+            let (num_txs, total_bytes) = mempool.size();
+            (num_txs, total_bytes, mempool.latest_txs(HOMEPAGE_NUM_TXS))
+        };
+        let mut txs = Vec::with_capacity(HOMEPAGE_NUM_TXS);
+        if !mempool_txs.is_empty() {
+            let token_ids = mempool_txs.iter().filter_map(known_token_id).collect();
+            let json_tokens = tokens_to_json(&self.batch_get_chronik_tokens(token_ids).await?)?;
+            txs.extend(
+                mempool_txs
+                    .iter()
+                    .map(|tx| tx_to_json(tx, None, &json_tokens)),
+            );
+        }
+        if txs.len() < HOMEPAGE_NUM_TXS {
+            // Only the tip's latest txs are converted, not the whole block
+            let tip_hash = Sha256d::from_slice(&blockchain_info.tip_hash)?;
+            let mut tip_block = self.chronik.block_by_hash(&tip_hash).await?;
+            let num_missing = HOMEPAGE_NUM_TXS - txs.len();
+            let num_older_txs = tip_block.txs.len().saturating_sub(num_missing);
+            tip_block.txs.drain(..num_older_txs);
+            let token_ids = tip_block.txs.iter().filter_map(known_token_id).collect();
+            let tokens_by_hex = self.batch_get_chronik_tokens(token_ids).await?;
+            txs.extend(
+                block_txs_to_json(tip_block, &tokens_by_hex)?
+                    .into_iter()
+                    .rev(),
+            );
+        }
+
+        let homepage = HomepageTemplate {
+            tip_height,
+            tip_hash,
+            blocks,
+            txs,
+            mempool_num_txs,
+            mempool_bytes,
+            difficulty: self.data_difficulty(HashMap::new()).await?,
+        };
         Ok(homepage.render().unwrap())
     }
 
+    pub async fn mempool(&self, query: HashMap<String, String>) -> Result<String> {
+        let mempool_template = MempoolTemplate {
+            mempool: self.data_mempool(query).await?,
+        };
+        Ok(mempool_template.render().unwrap())
+    }
+
+    pub async fn miners(&self, query: HashMap<String, String>) -> Result<String> {
+        let miners_template = MinersTemplate {
+            stats: self.data_miners(query).await?,
+        };
+        Ok(miners_template.render().unwrap())
+    }
+
+    pub async fn charts(&self) -> Result<String> {
+        let charts_template = ChartsTemplate {
+            series: ALL_CHART_SERIES.to_vec(),
+        };
+        Ok(charts_template.render().unwrap())
+    }
+
     pub async fn blocks(&self) -> Result<String> {
         let blockchain_info = self.chronik.blockchain_info().await?;
 
         let blocks_template = BlocksTemplate {
             last_block_height: blockchain_info.tip_height as u32,
+            difficulty: self.data_difficulty(HashMap::new()).await?,
         };
 
         Ok(blocks_template.render().unwrap())
@@ -92,27 +422,305 @@ impl Server {
         end_height: i32,
     ) -> Result<JsonBlocksResponse> {
         let blocks = self.chronik.blocks(start_height, end_height).await?;
+        let miners = self.block_miners(&blocks, MAX_LISTED_MINER_LOOKUPS).await?;
 
-        let mut json_blocks = Vec::with_capacity(blocks.len());
-        for block in blocks.into_iter().rev() {
-            json_blocks.push(JsonBlock {
-                hash: to_be_hex(&block.hash),
-                height: block.height,
-                timestamp: block.timestamp,
-                difficulty: calculate_block_difficulty(block.n_bits),
-                size: block.block_size,
-                num_txs: block.num_txs,
-            });
-        }
+        let json_blocks = blocks
+            .iter()
+            .zip(miners)
+            .rev()
+            .map(|(block, miner)| block_info_to_json(block, miner))
+            .collect();
 
         Ok(JsonBlocksResponse { data: json_blocks })
     }
 
+    /// Share of blocks mined by each pool, over the `window` query param or
+    /// `DEFAULT_STATS_WINDOWS`.
+    pub async fn data_miners(
+        &self,
+        query: HashMap<String, String>,
+    ) -> Result<JsonMinerStatsResponse> {
+        if query.get("window").is_none() {
+            // Kept up to date by `sync_miner_stats`
+            if let Some((_, stats)) = &*self.miner_stats.lock().unwrap() {
+                return Ok(stats.clone());
+            }
+            return self.update_miner_stats().await;
+        }
+        let window: StatsWindow = parse_query(&query, "window", DEFAULT_STATS_WINDOWS[0])?;
+        let key = window.to_string();
+        let blockchain_info = self.chronik.blockchain_info().await?;
+        let cached_stats = || {
+            let mut custom_miner_stats = self.custom_miner_stats.lock().unwrap();
+            match custom_miner_stats.get(&key) {
+                Some((tip_hash, stats)) if *tip_hash == blockchain_info.tip_hash => {
+                    Some(stats.clone())
+                }
+                _ => None,
+            }
+        };
+        if let Some(stats) = cached_stats() {
+            return Ok(stats);
+        }
+        // Computing a window can take many requests to Chronik, so requests
+        // for uncached windows wait their turn
+        let _permit = self.custom_miner_stats_permit.acquire().await?;
+        if let Some(stats) = cached_stats() {
+            return Ok(stats);
+        }
+        let stats = self.miner_stats(&blockchain_info, &[window]).await?;
+        self.custom_miner_stats
+            .lock()
+            .unwrap()
+            .put(key, (blockchain_info.tip_hash.clone(), stats.clone()));
+        Ok(stats)
+    }
+
+    /// Keeps the miner stats of `DEFAULT_STATS_WINDOWS` up to date, so
+    /// requests don't have to compute them.
+    pub async fn sync_miner_stats(self: Arc<Self>) {
+        loop {
+            if let Err(err) = self.update_miner_stats().await {
+                eprintln!("Failed to update miner stats: {}", err);
+            }
+            tokio::time::sleep(MINER_STATS_SYNC_INTERVAL).await;
+        }
+    }
+
+    async fn update_miner_stats(&self) -> Result<JsonMinerStatsResponse> {
+        let blockchain_info = self.chronik.blockchain_info().await?;
+        if let Some((tip_hash, stats)) = &*self.miner_stats.lock().unwrap() {
+            if *tip_hash == blockchain_info.tip_hash {
+                return Ok(stats.clone());
+            }
+        }
+        let stats = self
+            .miner_stats(&blockchain_info, &DEFAULT_STATS_WINDOWS)
+            .await?;
+        *self.miner_stats.lock().unwrap() = Some((blockchain_info.tip_hash, stats.clone()));
+        Ok(stats)
+    }
+
+    async fn miner_stats(
+        &self,
+        blockchain_info: &BlockchainInfo,
+        windows: &[StatsWindow],
+    ) -> Result<JsonMinerStatsResponse> {
+        let blocks = self
+            .stats_window_blocks(blockchain_info.tip_height, windows)
+            .await?;
+        let miners = self.block_miners(&blocks, usize::MAX).await?;
+        let tip_timestamp = blocks.last().map(|block| block.timestamp).unwrap_or(0);
+        let windows = windows
+            .iter()
+            .map(|window| {
+                let num_blocks = match *window {
+                    StatsWindow::Blocks(num_blocks) => num_blocks as usize,
+                    StatsWindow::Days(num_days) => blocks
+                        .iter()
+                        .rev()
+                        .take_while(|block| block.timestamp >= tip_timestamp - num_days * 86_400)
+                        .count(),
+                };
+                let start = blocks.len() - num_blocks.min(blocks.len());
+                miner_stats_window_to_json(
+                    window.to_string(),
+                    &blocks[start..],
+                    &miners[start..],
+                    &self.pools,
+                )
+            })
+            .collect();
+        Ok(JsonMinerStatsResponse {
+            tip_height: blockchain_info.tip_height,
+            windows,
+        })
+    }
+
+    /// Network hashrate and block time over the last `blocks` query param
+    /// blocks, and ASERT's expected difficulty of the next block.
+    pub async fn data_difficulty(
+        &self,
+        query: HashMap<String, String>,
+    ) -> Result<JsonDifficultyResponse> {
+        let num_blocks = parse_query(&query, "blocks", DEFAULT_HASHRATE_WINDOW)?;
+        if !(2..=MAX_STATS_WINDOW_BLOCKS).contains(&num_blocks) {
+            return Err(ServerError::BadRequest(format!(
+                "Number of blocks out of range: {}",
+                num_blocks
+            ))
+            .into());
+        }
+        let blockchain_info = self.chronik.blockchain_info().await?;
+        let blocks = self
+            .stats_window_blocks(
+                blockchain_info.tip_height,
+                &[StatsWindow::Blocks(num_blocks)],
+            )
+            .await?;
+        let blocks = &blocks[blocks.len().saturating_sub(num_blocks as usize)..];
+        let tip = blocks.last().ok_or_else(|| eyre!("Chain has no blocks"))?;
+        let expectation = asert_expectation(tip);
+
+        Ok(JsonDifficultyResponse {
+            tip_height: tip.height,
+            difficulty: calculate_block_difficulty(tip.n_bits),
+            next_bits: expectation.next_bits,
+            next_difficulty: expectation.next_difficulty,
+            schedule_offset_secs: expectation.schedule_offset_secs,
+            num_blocks: blocks.len(),
+            hashrate: estimate_window_hashrate(blocks).unwrap_or(0.0),
+            avg_block_time: average_block_time(blocks).unwrap_or(0.0),
+        })
+    }
+
+    /// Daily values of `series` over the `days` query param, or over all
+    /// summarized blocks.
+    pub fn data_chart(
+        &self,
+        series: &str,
+        query: HashMap<String, String>,
+    ) -> Result<JsonChartSeries> {
+        let series = series
+            .parse::<ChartSeries>()
+            .map_err(|err| ServerError::BadRequest(err.to_string()))?;
+        let num_days = parse_query(&query, "days", usize::MAX)?;
+        Ok(self.chain_stats.lock().unwrap().series(series, num_days))
+    }
+
+    /// Blocks up to `tip_height` covering all of `windows`, oldest first.
+    async fn stats_window_blocks(
+        &self,
+        tip_height: i32,
+        windows: &[StatsWindow],
+    ) -> Result<Vec<BlockInfo>> {
+        let min_blocks = windows
+            .iter()
+            .filter_map(|window| match *window {
+                StatsWindow::Blocks(num_blocks) => Some(num_blocks as usize),
+                StatsWindow::Days(_) => None,
+            })
+            .max()
+            .unwrap_or(0);
+        let num_days = windows
+            .iter()
+            .filter_map(|window| match *window {
+                StatsWindow::Days(num_days) => Some(num_days),
+                StatsWindow::Blocks(_) => None,
+            })
+            .max();
+
+        // Newest first until reversed at the end
+        let mut blocks = Vec::<BlockInfo>::new();
+        let mut end_height = tip_height;
+        loop {
+            let start_height = (end_height - STATS_BLOCKS_PAGE_SIZE + 1).max(0);
+            let page = self.chronik.blocks(start_height, end_height).await?;
+            blocks.extend(page.into_iter().rev());
+            let covers_days = match (num_days, blocks.first(), blocks.last()) {
+                (Some(num_days), Some(tip), Some(oldest)) => {
+                    oldest.timestamp < tip.timestamp - num_days * 86_400
+                }
+                _ => true,
+            };
+            if (blocks.len() >= min_blocks && covers_days)
+                || blocks.len() >= MAX_STATS_WINDOW_BLOCKS as usize
+                || start_height == 0
+            {
+                break;
+            }
+            end_height = start_height - 1;
+        }
+        blocks.reverse();
+        Ok(blocks)
+    }
+
+    pub async fn data_block_stats(&self, block_hex: &str) -> Result<JsonBlockStats> {
+        let block_hash = parse_hash(block_hex)?;
+        let block = self.chronik.block_by_hash(&block_hash).await?;
+        block_stats_to_json(&block, &self.coinbase_rules, self.satoshi_addr_prefix)
+    }
+
     pub async fn data_block_txs(&self, block_hex: &str) -> Result<JsonTxsResponse> {
-        let block_hash = Sha256d::from_hex_be(block_hex)?;
+        let block_hash = parse_hash(block_hex)?;
         let block = self.chronik.block_by_hash(&block_hash).await?;
 
-        let token_ids = block
+        let token_ids = block.txs.iter().filter_map(known_token_id).collect();
+
+        let tokens_by_hex = self.batch_get_chronik_tokens(token_ids).await?;
+        let json_txs = block_txs_to_json(block, &tokens_by_hex)?;
+
+        Ok(JsonTxsResponse { data: json_txs })
+    }
+
+    pub async fn data_mempool(
+        &self,
+        query: HashMap<String, String>,
+    ) -> Result<JsonMempoolResponse> {
+        let page: usize = parse_query(&query, "page", 0)?;
+        let take: usize = parse_query(&query, "take", 100)?;
+        if take > MAX_MEMPOOL_TXS_TAKE {
+            return Err(ServerError::BadRequest(format!(
+                "take must be at most {}",
+                MAX_MEMPOOL_TXS_TAKE
+            ))
+            .into());
+        }
+
+        let txs = self.mempool.lock().unwrap().txs();
+        let page_txs = txs
+            .iter()
+            .skip(page.saturating_mul(take))
+            .take(take)
+            .collect::<Vec<_>>();
+        let token_ids = page_txs
+            .iter()
+            .copied()
+            .filter_map(known_token_id)
+            .collect();
+        let tokens = self.batch_get_chronik_tokens(token_ids).await?;
+        let json_tokens = tokens_to_json(&tokens)?;
+
+        // Aggregates cover the whole mempool, the rest only the page.
+        let stats = txs
+            .iter()
+            .map(|tx| calc_tx_stats(tx, None))
+            .collect::<Vec<_>>();
+        let fee_rate_histogram = fee_rate_histogram(txs.iter().zip(&stats));
+        let num_txs = txs.len();
+        let total_bytes = txs.iter().map(|tx| tx.size as u64).sum();
+        let total_fee = stats.iter().map(|stats| stats.fee).sum();
+
+        Ok(JsonMempoolResponse {
+            num_txs: num_txs as u64,
+            total_bytes,
+            total_fee,
+            fee_rate_histogram,
+            txs: page_txs
+                .into_iter()
+                .map(|tx| tx_to_json(tx, None, &json_tokens))
+                .collect(),
+            page,
+            has_more: num_txs > page.saturating_add(1).saturating_mul(take),
+        })
+    }
+
+    pub async fn data_address_txs(
+        &self,
+        address: &str,
+        query: HashMap<String, String>,
+    ) -> Result<JsonTxsResponse> {
+        let address = parse_address(address)?;
+        let (script_type, script_payload) = cash_addr_to_script_type_payload(&address);
+
+        let page: usize = parse_query(&query, "page", 0)?;
+        let take: usize = parse_query(&query, "take", 200)?;
+        let address_tx_history = self
+            .chronik
+            .script_history(script_type, &script_payload, page, take)
+            .await?;
+
+        let token_ids = address_tx_history
             .txs
             .iter()
             .filter_map(|tx| {
@@ -121,63 +729,693 @@ impl Server {
                 if slp_meta.token_type() == SlpTokenType::UnknownTokenType {
                     return None;
                 }
-                Some(Sha256d::from_slice_be(&slp_meta.token_id).expect("Impossible"))
+                Some(Sha256d::from_slice_be_or_null(&slp_meta.token_id))
             })
-            .collect::<HashSet<_>>();
+            .collect();
 
-        let tokens_by_hex = self.batch_get_chronik_tokens(token_ids).await?;
-        let json_txs = block_txs_to_json(block, &tokens_by_hex)?;
+        let tokens = self.batch_get_chronik_tokens(token_ids).await?;
+        let json_tokens = tokens_to_json(&tokens)?;
+        let json_txs = tx_history_to_json(&address, address_tx_history, &json_tokens)?;
 
         Ok(JsonTxsResponse { data: json_txs })
     }
 
-    pub async fn data_address_txs(
+    /// Streams every tx of `address` as rows of the `format` query param,
+    /// newest first, fetching one page of history at a time.
+    ///
+    /// If a page can't be fetched once the response started, the export ends
+    /// with a row marking it as truncated instead.
+    pub fn address_export(
+        self: Arc<Self>,
+        address: &str,
+        query: HashMap<String, String>,
+    ) -> Result<(ExportFormat, impl Stream<Item = Result<String>>)> {
+        let format = parse_query(&query, "format", ExportFormat::Csv)?;
+        let cash_address = parse_address(address)?;
+        let (script_type, script_payload) = cash_addr_to_script_type_payload(&cash_address);
+        let address_script = cash_address.to_script().bytecode().to_vec();
+
+        // Next page to fetch and number of rows written so far; no page once
+        // the history is exhausted.
+        let rows = stream::unfold((Some(0), 0), move |(page, num_rows)| {
+            let server = self.clone();
+            let address_script = address_script.clone();
+            async move {
+                let page = page?;
+                let page_rows = async {
+                    let history = server
+                        .chronik
+                        .script_history(script_type, &script_payload, page, EXPORT_PAGE_SIZE)
+                        .await?;
+                    let token_ids = history.txs.iter().filter_map(known_token_id).collect();
+                    let tokens = server.batch_get_chronik_tokens(token_ids).await?;
+                    let json_tokens = tokens_to_json(&tokens)?;
+
+                    let mut chunk = String::new();
+                    for (idx, tx) in history.txs.iter().enumerate() {
+                        let row = export_row(
+                            tx,
+                            &address_script,
+                            server.satoshi_addr_prefix,
+                            &json_tokens,
+                        );
+                        chunk.push_str(&format.row(&row, num_rows + idx));
+                    }
+                    let next_page = if page + 1 < history.num_pages as usize {
+                        Some(page + 1)
+                    } else {
+                        None
+                    };
+                    Ok::<_, Report>((chunk, next_page, num_rows + history.txs.len()))
+                };
+                match page_rows.await {
+                    Ok((chunk, next_page, num_rows)) => Some((Ok(chunk), (next_page, num_rows))),
+                    Err(err) => {
+                        eprintln!("Failed to export address history: {}", err);
+                        Some((Ok(format.truncated_row(num_rows)), (None, num_rows)))
+                    }
+                }
+            }
+        });
+
+        let body = stream::once(future::ready(Ok(format.header())))
+            .chain(rows)
+            .chain(stream::once(future::ready(Ok(format.footer()))));
+        Ok((format, body))
+    }
+
+    /// Balances of `address` after each of its latest
+    /// `MAX_BALANCE_HISTORY_TXS` txs.
+    pub async fn data_address_balance_history(
+        &self,
+        address: &str,
+    ) -> Result<JsonBalanceHistoryResponse> {
+        let address = parse_address(address)?;
+        let (script_type, script_payload) = cash_addr_to_script_type_payload(&address);
+        let address_script = address.to_script().bytecode().to_vec();
+        let AddressBalances {
+            token_dust,
+            total_xec,
+            json_balances,
+            ..
+        } = self.address_balances(script_type, &script_payload).await?;
+        let current_tokens = json_balances
+            .into_values()
+            .filter_map(|balance| Some((balance.token_id?, balance.token_amount)))
+            .collect::<HashMap<_, _>>();
+
+        // Newest first
+        let mut deltas = Vec::new();
+        let mut token_ids = HashSet::new();
+        let mut page = 0;
+        let is_complete = loop {
+            let history = self
+                .chronik
+                .script_history(
+                    script_type,
+                    &script_payload,
+                    page,
+                    BALANCE_HISTORY_PAGE_SIZE,
+                )
+                .await?;
+            for tx in &history.txs {
+                let stats = calc_tx_stats(tx, Some(&address_script));
+                token_ids.extend(known_token_id(tx));
+                deltas.push(BalanceDelta {
+                    timestamp: match &tx.block {
+                        Some(block) => block.timestamp,
+                        None => tx.time_first_seen,
+                    },
+                    tx_hash: to_be_hex(&tx.txid),
+                    delta_sats: stats.delta_sats,
+                    token_id: tx
+                        .slp_tx_data
+                        .as_ref()
+                        .and_then(|slp_tx_data| slp_tx_data.slp_meta.as_ref())
+                        .map(|slp_meta| hex::encode(&slp_meta.token_id)),
+                    delta_tokens: stats.delta_tokens,
+                });
+            }
+            page += 1;
+            if page >= history.num_pages as usize {
+                break true;
+            }
+            if deltas.len() >= MAX_BALANCE_HISTORY_TXS {
+                break false;
+            }
+        };
+
+        let tokens = self.batch_get_chronik_tokens(token_ids).await?;
+        let json_tokens = tokens_to_json(&tokens)?;
+
+        Ok(balance_history_to_json(
+            &deltas,
+            total_xec + token_dust,
+            &current_tokens,
+            &json_tokens,
+            is_complete,
+        ))
+    }
+
+    pub async fn data_address_balance(
+        &self,
+        address: &str,
+        query: HashMap<String, String>,
+    ) -> Result<JsonAddressBalanceResponse> {
+        let address = parse_address(address)?;
+        let (script_type, script_payload) = cash_addr_to_script_type_payload(&address);
+        let AddressBalances {
+            token_dust,
+            total_xec,
+            json_balances,
+            tokens,
+            ..
+        } = self.address_balances(script_type, &script_payload).await?;
+        let json_tokens = tokens_to_json(&tokens)?;
+        let token_balances =
+            token_balances_to_json(json_balances, &json_tokens, query.get("token_id"));
+
+        Ok(JsonAddressBalanceResponse {
+            address: address.as_str().to_string(),
+            total_xec,
+            token_dust,
+            tokens: token_balances,
+        })
+    }
+
+    pub async fn data_address_utxos(
+        &self,
+        address: &str,
+        query: HashMap<String, String>,
+    ) -> Result<JsonUtxosResponse> {
+        let address = parse_address(address)?;
+        let (script_type, script_payload) = cash_addr_to_script_type_payload(&address);
+        self.script_utxos(script_type, &script_payload, query).await
+    }
+
+    /// UTXOs of a script, newest first, optionally only those of the
+    /// `token_id` query param.
+    async fn script_utxos(
+        &self,
+        script_type: ScriptType,
+        script_payload: &[u8],
+        query: HashMap<String, String>,
+    ) -> Result<JsonUtxosResponse> {
+        let AddressBalances { json_balances, .. } =
+            self.address_balances(script_type, script_payload).await?;
+        let token_id_filter = query.get("token_id");
+
+        let mut utxos = json_balances
+            .into_iter()
+            .filter(|(_, balance)| match token_id_filter {
+                Some(filter) => balance.token_id.as_ref() == Some(filter),
+                None => true,
+            })
+            .flat_map(|(_, balance)| balance.utxos)
+            .collect::<Vec<_>>();
+        utxos.sort_by_key(|utxo| std::cmp::Reverse(utxo.block_height));
+
+        Ok(JsonUtxosResponse { data: utxos })
+    }
+
+    pub async fn wallet(&self, query: HashMap<String, String>) -> Result<String> {
+        let addresses = query.get("addresses").cloned().unwrap_or_default();
+        let wallet = if addresses.trim().is_empty() {
+            None
+        } else {
+            Some(self.data_wallet(query).await?)
+        };
+        let wallet_template = WalletTemplate { addresses, wallet };
+        Ok(wallet_template.render().unwrap())
+    }
+
+    /// Combined balances, UTXOs and history of the comma or whitespace
+    /// separated `addresses` query param.
+    ///
+    /// History deltas are relative to the whole wallet, so transfers between
+    /// its addresses only show up with their fee.
+    pub async fn data_wallet(&self, query: HashMap<String, String>) -> Result<JsonWalletResponse> {
+        let page: usize = parse_query(&query, "page", 0)?;
+        let take: usize = parse_query(&query, "take", 50)?;
+        let addresses = query.get("addresses").map(String::as_str).unwrap_or("");
+
+        let mut wallet_addresses = Vec::new();
+        let mut wallet_scripts = HashSet::new();
+        for address in addresses
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|address| !address.is_empty())
+        {
+            let address = parse_address(address)?;
+            // The same script can be given with different prefixes
+            if wallet_scripts.insert(address.to_script().bytecode().to_vec()) {
+                wallet_addresses.push(address);
+            }
+        }
+        if wallet_addresses.is_empty() {
+            return Err(ServerError::BadRequest("No addresses given".to_string()).into());
+        }
+        if wallet_addresses.len() > MAX_WALLET_ADDRESSES {
+            return Err(ServerError::BadRequest(format!(
+                "Too many addresses, at most {} are allowed",
+                MAX_WALLET_ADDRESSES
+            ))
+            .into());
+        }
+
+        self.wallet_summary(&wallet_addresses, page, take).await
+    }
+
+    /// Combined balances, UTXOs and `page` of the merged history of
+    /// `wallet_addresses`, which must be distinct.
+    async fn wallet_summary(
+        &self,
+        wallet_addresses: &[CashAddress<'_>],
+        page: usize,
+        take: usize,
+    ) -> Result<JsonWalletResponse> {
+        if take > MAX_WALLET_TXS_TAKE {
+            return Err(ServerError::BadRequest(format!(
+                "take must be at most {}",
+                MAX_WALLET_TXS_TAKE
+            ))
+            .into());
+        }
+        let wallet_scripts = wallet_addresses
+            .iter()
+            .map(|address| address.to_script().bytecode().to_vec())
+            .collect::<HashSet<_>>();
+
+        let address_results = stream::iter(wallet_addresses)
+            .map(|address| async move {
+                let (script_type, script_payload) = cash_addr_to_script_type_payload(address);
+                let balances = self.address_balances(script_type, &script_payload).await?;
+                let history = self
+                    .chronik
+                    .script_history(script_type, &script_payload, 0, WALLET_HISTORY_PAGE_SIZE)
+                    .await?;
+                Ok::<_, Report>((balances, history))
+            })
+            .buffered(WALLET_LOOKUP_CONCURRENCY)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        let mut json_addresses = Vec::new();
+        let mut total_xec = 0;
+        let mut token_dust = 0;
+        let mut token_totals = HashMap::<String, (i128, i64, usize)>::new();
+        let mut tokens = HashMap::new();
+        let mut utxos = Vec::new();
+        let mut txs = HashMap::new();
+        // Txs older than this may be missing from truncated histories
+        let mut history_cutoff = None;
+        for (address, (balances, history)) in wallet_addresses.iter().zip(address_results) {
+            let address = address.as_str().to_string();
+            let mut num_utxos = 0;
+            for (_, balance) in balances.json_balances {
+                num_utxos += balance.utxos.len();
+                if let Some(token_id) = balance.token_id {
+                    let total = token_totals.entry(token_id).or_default();
+                    total.0 += balance.token_amount;
+                    total.1 += balance.sats_amount;
+                    total.2 += balance.utxos.len();
+                }
+                utxos.extend(balance.utxos.into_iter().map(|utxo| JsonWalletUtxo {
+                    address: address.clone(),
+                    utxo,
+                }));
+            }
+            total_xec += balances.total_xec;
+            token_dust += balances.token_dust;
+            tokens.extend(balances.tokens);
+            json_addresses.push(JsonWalletAddress {
+                address,
+                total_xec: balances.total_xec,
+                token_dust: balances.token_dust,
+                num_utxos,
+            });
+
+            if history.num_pages > 1 {
+                let oldest_key = history.txs.last().map(wallet_history_key);
+                history_cutoff = history_cutoff.max(oldest_key);
+            }
+            for tx in history.txs {
+                txs.insert(tx.txid.clone(), tx);
+            }
+        }
+        utxos.sort_by_key(|utxo| std::cmp::Reverse(utxo.utxo.block_height));
+
+        let mut txs = txs
+            .into_values()
+            .filter(|tx| Some(wallet_history_key(tx)) >= history_cutoff)
+            .collect::<Vec<_>>();
+        txs.sort_by_key(|tx| std::cmp::Reverse(wallet_history_key(tx)));
+
+        let token_ids = txs
+            .iter()
+            .filter_map(known_token_id)
+            .collect::<HashSet<_>>();
+        tokens.extend(self.batch_get_chronik_tokens(token_ids).await?);
+        let json_tokens = tokens_to_json(&tokens)?;
+
+        let is_ours = |script: &[u8]| wallet_scripts.contains(script);
+        let num_txs = txs.len();
+        let json_txs = txs
+            .iter()
+            .skip(page.saturating_mul(take))
+            .take(take)
+            .map(|tx| {
+                let mut json_tx = tx_to_json(tx, None, &json_tokens);
+                json_tx.stats = calc_tx_stats_matching(tx, is_ours);
+                let is_internal = !tx.is_coinbase
+                    && tx.inputs.iter().all(|input| is_ours(&input.output_script))
+                    && tx.outputs.iter().all(|output| {
+                        is_ours(&output.output_script)
+                            || output.output_script.first() == Some(&OP_RETURN)
+                    });
+                JsonWalletTx {
+                    tx: json_tx,
+                    is_internal,
+                }
+            })
+            .collect();
+
+        let mut token_balances = token_totals
+            .into_iter()
+            .map(|(token_id, (token_amount, sats_amount, num_utxos))| {
+                let token = json_tokens.get(&token_id).cloned();
+                let decimals = token.as_ref().map(|token| token.decimals).unwrap_or(0);
+                JsonTokenBalance {
+                    token_amount_str: format_token_amount(token_amount, decimals),
+                    token_id,
+                    token,
+                    token_amount,
+                    sats_amount,
+                    num_utxos,
+                }
+            })
+            .collect::<Vec<_>>();
+        token_balances.sort_by(|a, b| a.token_id.cmp(&b.token_id));
+
+        Ok(JsonWalletResponse {
+            addresses: json_addresses,
+            total_xec,
+            token_dust,
+            tokens: token_balances,
+            utxos,
+            txs: json_txs,
+            page,
+            has_more: num_txs > page.saturating_add(1).saturating_mul(take),
+            is_complete: history_cutoff.is_none(),
+        })
+    }
+
+    pub async fn xpub(&self, xpub: &str, query: HashMap<String, String>) -> Result<String> {
+        let xpub = self.data_xpub(xpub, query).await?;
+        let xpub_template = XpubTemplate { xpub };
+        Ok(xpub_template.render().unwrap())
+    }
+
+    /// Scans the receive and change addresses of `xpub_str` until `gap`
+    /// (query param) addresses in a row have no txs, and summarizes the used
+    /// ones as a wallet.
+    pub async fn data_xpub(
+        &self,
+        xpub_str: &str,
+        query: HashMap<String, String>,
+    ) -> Result<JsonXpubResponse> {
+        let page: usize = parse_query(&query, "page", 0)?;
+        let take: usize = parse_query(&query, "take", 50)?;
+        let gap_limit: u32 = parse_query(&query, "gap", DEFAULT_GAP_LIMIT)?;
+        if !(1..=MAX_GAP_LIMIT).contains(&gap_limit) {
+            return Err(ServerError::BadRequest(format!(
+                "gap must be between 1 and {}",
+                MAX_GAP_LIMIT
+            ))
+            .into());
+        }
+        let xpub = parse_xpub(xpub_str)?;
+
+        let (receive, change) = future::try_join(
+            self.scan_xpub_chain(&xpub, RECEIVE_CHAIN, gap_limit),
+            self.scan_xpub_chain(&xpub, CHANGE_CHAIN, gap_limit),
+        )
+        .await?;
+        let is_scan_complete = receive.1 && change.1;
+        let used_addresses = receive.0.into_iter().chain(change.0).collect::<Vec<_>>();
+
+        let next_receive_index = used_addresses
+            .iter()
+            .filter(|(derived, _)| derived.chain == RECEIVE_CHAIN)
+            .map(|(derived, _)| derived.index + 1)
+            .max()
+            .unwrap_or(0);
+        let next_receive_address =
+            xpub.derive(RECEIVE_CHAIN, next_receive_index, self.satoshi_addr_prefix)?;
+
+        let wallet_addresses = used_addresses
+            .iter()
+            .map(|(derived, _)| derived.address.clone())
+            .collect::<Vec<_>>();
+        let wallet = self.wallet_summary(&wallet_addresses, page, take).await?;
+
+        Ok(JsonXpubResponse {
+            xpub: xpub_str.to_string(),
+            account_path: xpub.account_path(),
+            gap_limit,
+            addresses: used_addresses
+                .into_iter()
+                .map(|(derived, num_txs)| JsonXpubAddress {
+                    address: derived.address.as_str().to_string(),
+                    chain: derived.chain,
+                    index: derived.index,
+                    num_txs,
+                })
+                .collect(),
+            next_receive_address: next_receive_address.address.as_str().to_string(),
+            is_scan_complete,
+            wallet,
+        })
+    }
+
+    /// Derives addresses of `chain` until `gap_limit` of them in a row have
+    /// no txs, and returns the used ones with their number of txs.
+    ///
+    /// Also returns false if the scan stopped at `MAX_XPUB_CHAIN_ADDRESSES`.
+    async fn scan_xpub_chain(
+        &self,
+        xpub: &Xpub,
+        chain: u32,
+        gap_limit: u32,
+    ) -> Result<(Vec<(DerivedAddress, u32)>, bool)> {
+        let mut used_addresses = Vec::new();
+        let mut next_index = 0;
+        let mut num_unused = 0;
+        while num_unused < gap_limit {
+            if next_index >= MAX_XPUB_CHAIN_ADDRESSES {
+                return Ok((used_addresses, false));
+            }
+            // Just enough addresses to either reach the gap limit or find a
+            // used one
+            let batch_end = (next_index + gap_limit - num_unused).min(MAX_XPUB_CHAIN_ADDRESSES);
+            let batch = (next_index..batch_end)
+                .map(|index| xpub.derive(chain, index, self.satoshi_addr_prefix))
+                .collect::<Result<Vec<_>>>()?;
+            next_index = batch_end;
+
+            let nums_txs = stream::iter(&batch)
+                .map(|derived| async move {
+                    let (script_type, script_payload) =
+                        cash_addr_to_script_type_payload(&derived.address);
+                    // With one tx per page, the number of pages is the number of txs
+                    let history = self
+                        .chronik
+                        .script_history(script_type, &script_payload, 0, 1)
+                        .await?;
+                    Ok::<_, Report>(history.num_pages)
+                })
+                .buffered(XPUB_LOOKUP_CONCURRENCY)
+                .try_collect::<Vec<_>>()
+                .await?;
+
+            for (derived, num_txs) in batch.into_iter().zip(nums_txs) {
+                if num_txs > 0 {
+                    num_unused = 0;
+                    used_addresses.push((derived, num_txs));
+                } else {
+                    num_unused += 1;
+                }
+            }
+        }
+        Ok((used_addresses, true))
+    }
+
+    pub async fn script(
+        &self,
+        script_type: &str,
+        payload: &str,
+        query: HashMap<String, String>,
+    ) -> Result<String> {
+        let (script_type, script_payload) = parse_script_type_payload(script_type, payload)?;
+        let script = script_from_type_payload(script_type, &script_payload);
+        let page: usize = parse_query(&query, "page", 0)?;
+        let take: usize = parse_query(&query, "take", 50)?;
+        let (summary, txs) = future::try_join(
+            self.script_summary(script_type, &script_payload),
+            self.script_txs(script_type, &script_payload, page, take),
+        )
+        .await?;
+
+        let script_template = ScriptTemplate {
+            has_more: (summary.num_txs as usize) > page.saturating_add(1).saturating_mul(take),
+            destination: destination_from_script(self.satoshi_addr_prefix, &script),
+            script,
+            summary,
+            txs,
+            page,
+        };
+        Ok(script_template.render().unwrap())
+    }
+
+    pub async fn data_script(
+        &self,
+        script_type: &str,
+        payload: &str,
+    ) -> Result<JsonScriptResponse> {
+        let (script_type, script_payload) = parse_script_type_payload(script_type, payload)?;
+        self.script_summary(script_type, &script_payload).await
+    }
+
+    pub async fn data_script_txs(
+        &self,
+        script_type: &str,
+        payload: &str,
+        query: HashMap<String, String>,
+    ) -> Result<JsonTxsResponse> {
+        let (script_type, script_payload) = parse_script_type_payload(script_type, payload)?;
+        let page: usize = parse_query(&query, "page", 0)?;
+        let take: usize = parse_query(&query, "take", 200)?;
+        let txs = self
+            .script_txs(script_type, &script_payload, page, take)
+            .await?;
+        Ok(JsonTxsResponse { data: txs })
+    }
+
+    pub async fn data_script_utxos(
+        &self,
+        script_type: &str,
+        payload: &str,
+        query: HashMap<String, String>,
+    ) -> Result<JsonUtxosResponse> {
+        let (script_type, script_payload) = parse_script_type_payload(script_type, payload)?;
+        self.script_utxos(script_type, &script_payload, query).await
+    }
+
+    async fn script_summary(
+        &self,
+        script_type: ScriptType,
+        script_payload: &[u8],
+    ) -> Result<JsonScriptResponse> {
+        let (balances, history) = future::try_join(
+            self.address_balances(script_type, script_payload),
+            // With one tx per page, the number of pages is the number of txs
+            self.chronik
+                .script_history(script_type, script_payload, 0, 1),
+        )
+        .await?;
+        let json_tokens = tokens_to_json(&balances.tokens)?;
+
+        Ok(JsonScriptResponse {
+            script_type: script_type_name(script_type).to_string(),
+            payload: hex::encode(script_payload),
+            script_hex: hex::encode(script_from_type_payload(script_type, script_payload)),
+            total_xec: balances.total_xec,
+            token_dust: balances.token_dust,
+            tokens: token_balances_to_json(balances.json_balances, &json_tokens, None),
+            num_txs: history.num_pages,
+        })
+    }
+
+    /// Page of a script's history, with deltas relative to the script.
+    async fn script_txs(
         &self,
-        address: &str,
-        query: HashMap<String, String>,
-    ) -> Result<JsonTxsResponse> {
-        let address = CashAddress::parse_cow(address.into())?;
-        let (script_type, script_payload) = cash_addr_to_script_type_payload(&address);
-        let script_endpoint = self.chronik.script(script_type, &script_payload);
-
-        let page: usize = query
-            .get("page")
-            .map(|s| s.as_str())
-            .unwrap_or("0")
-            .parse()?;
-        let take: usize = query
-            .get("take")
-            .map(|s| s.as_str())
-            .unwrap_or("200")
-            .parse()?;
-        let address_tx_history = script_endpoint.history_with_page_size(page, take).await?;
+        script_type: ScriptType,
+        script_payload: &[u8],
+        page: usize,
+        take: usize,
+    ) -> Result<Vec<JsonTx>> {
+        if take > MAX_SCRIPT_TXS_TAKE {
+            return Err(ServerError::BadRequest(format!(
+                "take must be at most {}",
+                MAX_SCRIPT_TXS_TAKE
+            ))
+            .into());
+        }
+        let script = script_from_type_payload(script_type, script_payload);
+        let history = self
+            .chronik
+            .script_history(script_type, script_payload, page, take)
+            .await?;
+        let token_ids = history.txs.iter().filter_map(known_token_id).collect();
+        let tokens = self.batch_get_chronik_tokens(token_ids).await?;
+        let json_tokens = tokens_to_json(&tokens)?;
 
-        let token_ids = address_tx_history
+        Ok(history
             .txs
             .iter()
-            .filter_map(|tx| {
-                let slp_tx_data = tx.slp_tx_data.as_ref()?;
-                let slp_meta = slp_tx_data.slp_meta.as_ref()?;
-                if slp_meta.token_type() == SlpTokenType::UnknownTokenType {
-                    return None;
-                }
-                Some(Sha256d::from_slice_be_or_null(&slp_meta.token_id))
-            })
-            .collect();
+            .map(|tx| tx_to_json(tx, Some(&script), &json_tokens))
+            .collect())
+    }
 
-        let tokens = self.batch_get_chronik_tokens(token_ids).await?;
-        let json_tokens = tokens_to_json(&tokens)?;
-        let json_txs = tx_history_to_json(&address, address_tx_history, &json_tokens)?;
+    pub async fn data_tx(&self, tx_hex: &str) -> Result<JsonTxDetails> {
+        let tx_hash = parse_hash(tx_hex)?;
+        let tx = self.chronik.tx(&tx_hash).await?;
+        let (_, token) = self.tx_token(&tx).await?;
+        let blockchain_info = self.chronik.blockchain_info().await?;
+        let raw_tx = self.chronik.raw_tx(&tx_hash).await?;
+        let redeem_scripts = self.spent_redeem_scripts(&tx).await?;
 
-        Ok(JsonTxsResponse { data: json_txs })
+        tx_details_to_json(
+            &tx,
+            token.as_ref(),
+            raw_tx.hex(),
+            blockchain_info.tip_height,
+            &redeem_scripts,
+            &self.op_returns,
+            AddressPrefixes {
+                satoshi: self.satoshi_addr_prefix,
+                tokens: self.tokens_addr_prefix,
+            },
+        )
+    }
+
+    /// Size of the mempool, cheap enough to poll.
+    pub fn data_mempool_summary(&self) -> JsonMempoolSummary {
+        let (num_txs, total_bytes) = self.mempool.lock().unwrap().size();
+        JsonMempoolSummary {
+            num_txs,
+            total_bytes,
+        }
+    }
+
+    pub fn data_cache_stats(&self) -> JsonCacheStats {
+        let stats = self.chronik.stats();
+        JsonCacheStats {
+            hits: stats.hits,
+            misses: stats.misses,
+            immutable_entries: stats.immutable_entries,
+            immutable_bytes: stats.immutable_bytes,
+            volatile_entries: stats.volatile_entries,
+            volatile_bytes: stats.volatile_bytes,
+        }
     }
 }
 
 impl Server {
     pub async fn block(&self, block_hex: &str) -> Result<String> {
-        let block_hash = Sha256d::from_hex_be(block_hex)?;
+        let block_hash = parse_hash(block_hex)?;
 
         let block = self.chronik.block_by_hash(&block_hash).await?;
+        let stats = block_stats_to_json(&block, &self.coinbase_rules, self.satoshi_addr_prefix)?;
         let block_info = block.block_info.ok_or_else(|| eyre!("Block has no info"))?;
         let block_details = block
             .block_details
@@ -189,6 +1427,8 @@ impl Server {
         let difficulty = calculate_block_difficulty(block_info.n_bits);
         let timestamp = Utc.timestamp(block_info.timestamp, 0);
         let coinbase_data = block.txs[0].inputs[0].input_script.clone();
+        let coinbase = parse_coinbase(&coinbase_data);
+        let miner = self.pools.identify(&block.txs[0]).cloned();
         let confirmations = best_height - block_info.height + 1;
 
         let block_template = BlockTemplate {
@@ -200,26 +1440,18 @@ impl Server {
             timestamp,
             difficulty,
             coinbase_data,
+            coinbase,
+            miner,
+            stats,
         };
 
         Ok(block_template.render().unwrap())
     }
 
     pub async fn tx(&self, tx_hex: &str) -> Result<String> {
-        let tx_hash = Sha256d::from_hex_be(tx_hex)?;
+        let tx_hash = parse_hash(tx_hex)?;
         let tx = self.chronik.tx(&tx_hash).await?;
-        let (token_id, token) = match &tx.slp_tx_data {
-            Some(slp_tx_data) => {
-                let slp_meta = slp_tx_data.slp_meta.as_ref().expect("Impossible");
-                let token_id = Sha256d::from_slice_be(&slp_meta.token_id)?;
-                let mut token = None;
-                if slp_meta.token_type() != SlpTokenType::UnknownTokenType {
-                    token = Some(self.chronik.token(&token_id).await?);
-                }
-                (Some(token_id), token)
-            }
-            None => (None, None),
-        };
+        let (token_id, token) = self.tx_token(&tx).await?;
         let token_ticker = token.as_ref().and_then(|token| {
             Some(String::from_utf8_lossy(
                 &token
@@ -246,25 +1478,7 @@ impl Server {
         let token_section_title: Cow<str> = match &tx.slp_tx_data {
             Some(slp_tx_data) => {
                 let slp_meta = slp_tx_data.slp_meta.as_ref().expect("Impossible");
-                let token_type = SlpTokenType::from_i32(slp_meta.token_type)
-                    .ok_or_else(|| eyre!("Malformed slp_meta"))?;
-                let tx_type = SlpTxType::from_i32(slp_meta.tx_type)
-                    .ok_or_else(|| eyre!("Malformed slp_meta"))?;
-
-                let action_str = match (token_type, tx_type) {
-                    (SlpTokenType::Fungible, SlpTxType::Genesis) => "GENESIS",
-                    (SlpTokenType::Fungible, SlpTxType::Mint) => "MINT",
-                    (SlpTokenType::Fungible, SlpTxType::Send) => "SEND",
-                    (SlpTokenType::Fungible, SlpTxType::Burn) => "BURN",
-                    (SlpTokenType::Nft1Group, SlpTxType::Genesis) => "NFT1 GROUP GENESIS",
-                    (SlpTokenType::Nft1Group, SlpTxType::Mint) => "NFT1 GROUP MINT",
-                    (SlpTokenType::Nft1Group, SlpTxType::Send) => "NFT1 GROUP SEND",
-                    (SlpTokenType::Nft1Group, SlpTxType::Burn) => "NFT1 GROUP BURN",
-                    (SlpTokenType::Nft1Child, SlpTxType::Genesis) => "NFT1 Child GENESIS",
-                    (SlpTokenType::Nft1Child, SlpTxType::Send) => "NFT1 Child SEND",
-                    (SlpTokenType::Nft1Child, SlpTxType::Burn) => "NFT1 Child BURN",
-                    _ => "Unknown",
-                };
+                let action_str = slp_action_str(slp_meta)?;
 
                 format!("Token Details ({} Transaction)", action_str).into()
             }
@@ -291,6 +1505,8 @@ impl Server {
         let raw_tx = raw_tx.hex();
 
         let tx_stats = calc_tx_stats(&tx, None);
+        let redeem_scripts = self.spent_redeem_scripts(&tx).await?;
+        let op_returns = self.op_returns.decode_outputs(&tx);
 
         let transaction_template = TransactionTemplate {
             title: &title,
@@ -308,6 +1524,10 @@ impl Server {
             sats_output: tx_stats.sats_output,
             token_input: tx_stats.token_input,
             token_output: tx_stats.token_output,
+            fee: tx_stats.fee,
+            fee_rate: tx_stats.fee_rate,
+            redeem_scripts,
+            op_returns,
             raw_tx,
             confirmations,
             timestamp,
@@ -315,11 +1535,476 @@ impl Server {
 
         Ok(transaction_template.render().unwrap())
     }
+
+    /// Redeem scripts of `tx`'s spent P2SH outputs, by output index, taken
+    /// from the inputs spending them.
+    async fn spent_redeem_scripts(&self, tx: &Tx) -> Result<HashMap<usize, Vec<u8>>> {
+        let mut redeem_scripts = HashMap::new();
+        let spent_p2sh_outputs = tx
+            .outputs
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, output)| {
+                let spent_by = output.spent_by.as_ref()?;
+                match destination_from_script(self.satoshi_addr_prefix, &output.output_script) {
+                    Destination::Address(address)
+                        if matches!(address.addr_type(), AddressType::P2SH) =>
+                    {
+                        Some((idx, output, spent_by))
+                    }
+                    _ => None,
+                }
+            })
+            .take(MAX_REDEEM_SCRIPT_LOOKUPS);
+        for (idx, output, spent_by) in spent_p2sh_outputs {
+            let spending_tx = self
+                .chronik
+                .tx(&Sha256d::from_slice(&spent_by.txid)?)
+                .await?;
+            let redeem_script = spending_tx
+                .inputs
+                .get(spent_by.out_idx as usize)
+                .and_then(|input| redeem_script(&input.input_script, &output.output_script));
+            if let Some(redeem_script) = redeem_script {
+                redeem_scripts.insert(idx, redeem_script);
+            }
+        }
+        Ok(redeem_scripts)
+    }
+
+    /// Name of the pool that mined each of `blocks`, if known.
+    ///
+    /// At most `max_lookups` blocks whose miner isn't remembered yet are
+    /// fetched, newest first; the miners of the others, and of blocks that
+    /// couldn't be fetched, are `None`.
+    async fn block_miners(
+        &self,
+        blocks: &[BlockInfo],
+        max_lookups: usize,
+    ) -> Result<Vec<Option<String>>> {
+        let mut miners = vec![None; blocks.len()];
+        let mut missing = Vec::new();
+        {
+            let mut miner_cache = self.miner_cache.lock().unwrap();
+            for (idx, block) in blocks.iter().enumerate() {
+                let hash = Sha256d::from_slice(&block.hash)?;
+                match miner_cache.get(&hash) {
+                    Some(miner) => miners[idx] = miner.clone(),
+                    None => missing.push((idx, hash)),
+                }
+            }
+        }
+        missing.sort_by_key(|&(idx, _)| std::cmp::Reverse(blocks[idx].height));
+        missing.truncate(max_lookups);
+        // Blocks within the charts' history were identified while syncing
+        // them, so this only fetches older blocks. Chronik doesn't serve a
+        // block's coinbase on its own, so the whole block is fetched, but
+        // uncached so it doesn't evict the shared cache.
+        let fetched = stream::iter(missing)
+            .map(|(idx, hash)| async move {
+                let miner = async {
+                    let block = self.chronik.uncached().block_by_hash(&hash).await?;
+                    Ok::<_, Report>(self.identify_block_miner(&block)?)
+                };
+                (idx, miner.await)
+            })
+            .buffer_unordered(MINER_LOOKUP_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await;
+        for (idx, miner) in fetched {
+            match miner {
+                Ok(miner) => miners[idx] = miner,
+                Err(err) => eprintln!("Failed to look up the miner of a block: {}", err),
+            }
+        }
+        Ok(miners)
+    }
+
+    /// Pool that mined `block`, remembered for `block_miners`.
+    fn identify_block_miner(&self, block: &Block) -> Result<Option<String>> {
+        let block_info = block
+            .block_info
+            .as_ref()
+            .ok_or_else(|| eyre!("Block has no info"))?;
+        let miner = block
+            .txs
+            .first()
+            .and_then(|coinbase_tx| self.pools.identify(coinbase_tx))
+            .map(|pool| pool.name.clone());
+        let hash = Sha256d::from_slice(&block_info.hash)?;
+        self.miner_cache.lock().unwrap().put(hash, miner.clone());
+        Ok(miner)
+    }
+
+    /// Looks up the token `tx` belongs to, if it is a token tx of a known type.
+    async fn tx_token(&self, tx: &Tx) -> Result<(Option<Sha256d>, Option<Token>)> {
+        match &tx.slp_tx_data {
+            Some(slp_tx_data) => {
+                let slp_meta = slp_tx_data.slp_meta.as_ref().expect("Impossible");
+                let token_id = Sha256d::from_slice_be(&slp_meta.token_id)?;
+                let mut token = None;
+                if slp_meta.token_type() != SlpTokenType::UnknownTokenType {
+                    token = Some(self.chronik.token(&token_id).await?);
+                }
+                Ok((Some(token_id), token))
+            }
+            None => Ok((None, None)),
+        }
+    }
+}
+
+fn is_tx_of_token(tx: &Tx, token_id_be: &str) -> bool {
+    tx.slp_tx_data
+        .as_ref()
+        .and_then(|slp_tx_data| slp_tx_data.slp_meta.as_ref())
+        .map(|slp_meta| hex::encode(&slp_meta.token_id) == token_id_be)
+        .unwrap_or(false)
 }
 
 impl Server {
+    pub async fn token(&self, token_hex: &str, query: HashMap<String, String>) -> Result<String> {
+        let token = self.data_token(token_hex, query).await?;
+        let timestamp = Utc.timestamp(token.time_first_seen, 0);
+
+        let token_template = TokenTemplate {
+            token_hex,
+            token,
+            timestamp,
+        };
+
+        Ok(token_template.render().unwrap())
+    }
+
+    pub async fn data_token(
+        &self,
+        token_hex: &str,
+        query: HashMap<String, String>,
+    ) -> Result<JsonTokenResponse> {
+        let token_id = parse_hash(token_hex)?;
+        let page: usize = parse_query(&query, "page", 0)?;
+        let take: usize = parse_query(&query, "take", 50)?;
+        if take > MAX_TOKEN_TXS_TAKE {
+            return Err(ServerError::BadRequest(format!(
+                "take must be at most {}",
+                MAX_TOKEN_TXS_TAKE
+            ))
+            .into());
+        }
+        let limit = page.saturating_add(1).saturating_mul(take);
+        if limit > MAX_TOKEN_TXS_WALKED {
+            return Err(ServerError::BadRequest(format!(
+                "Only the first {} txs of a token can be listed",
+                MAX_TOKEN_TXS_WALKED
+            ))
+            .into());
+        }
+
+        // Stats change with every mint and burn, so skip the cache
+        let token = self.chronik.uncached().token(&token_id).await?;
+        let slp_tx_data = token
+            .slp_tx_data
+            .as_ref()
+            .ok_or_else(|| eyre!("Token has no slp_tx_data"))?;
+        let slp_meta = slp_tx_data
+            .slp_meta
+            .as_ref()
+            .ok_or_else(|| eyre!("Token has no slp_meta"))?;
+        let genesis_info = slp_tx_data
+            .genesis_info
+            .as_ref()
+            .ok_or_else(|| eyre!("Token has no genesis_info"))?;
+        let json_token =
+            token_to_json(token_hex, &token).ok_or_else(|| eyre!("Token has no genesis_info"))?;
+        let token_stats = token.token_stats.clone().unwrap_or_default();
+
+        let mint_baton = self.follow_token_output(&token_id, true).await?;
+        let group = match slp_meta.token_type() {
+            SlpTokenType::Nft1Child => {
+                let group_id = Sha256d::from_slice_be(&slp_meta.group_token_id)?;
+                let group = self.chronik.token(&group_id).await?;
+                token_to_json(&group_id.to_hex_be(), &group)
+            }
+            _ => None,
+        };
+        let (txs, has_more) = self.token_txs(&token_id, limit).await?;
+        // Don't offer a next page that would be rejected
+        let has_more = has_more && limit + take <= MAX_TOKEN_TXS_WALKED;
+        let txs = txs
+            .iter()
+            .skip(page * take)
+            .map(|tx| token_tx_to_json(tx, &slp_meta.token_id))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(JsonTokenResponse {
+            token: json_token,
+            token_type_name: slp_token_type_str(slp_meta.token_type()).to_string(),
+            token_document_url: String::from_utf8_lossy(&genesis_info.token_document_url)
+                .to_string(),
+            token_document_hash: hex::encode(&genesis_info.token_document_hash),
+            genesis_block_height: token.block.as_ref().map(|block| block.height),
+            time_first_seen: token.time_first_seen,
+            initial_token_quantity: token.initial_token_quantity,
+            total_minted: token_stats.total_minted,
+            total_burned: token_stats.total_burned,
+            mint_baton,
+            group,
+            txs,
+            page,
+            has_more,
+        })
+    }
+
+    pub async fn nft_children(
+        &self,
+        group_hex: &str,
+        query: HashMap<String, String>,
+    ) -> Result<String> {
+        let children = self.data_nft_children(group_hex, query).await?;
+
+        let nft_children_template = NftChildrenTemplate {
+            group_hex,
+            children,
+        };
+
+        Ok(nft_children_template.render().unwrap())
+    }
+
+    pub async fn data_nft_children(
+        &self,
+        group_hex: &str,
+        query: HashMap<String, String>,
+    ) -> Result<JsonNftChildrenResponse> {
+        let group_id = parse_hash(group_hex)?;
+        let page: usize = parse_query(&query, "page", 0)?;
+        let take: usize = parse_query(&query, "take", 20)?;
+        if take > MAX_NFT_CHILDREN_TAKE {
+            return Err(ServerError::BadRequest(format!(
+                "take must be at most {}",
+                MAX_NFT_CHILDREN_TAKE
+            ))
+            .into());
+        }
+
+        let group = self.chronik.token(&group_id).await?;
+        let group_meta = group
+            .slp_tx_data
+            .as_ref()
+            .and_then(|slp_tx_data| slp_tx_data.slp_meta.as_ref())
+            .ok_or_else(|| eyre!("Token has no slp_meta"))?;
+        if group_meta.token_type() != SlpTokenType::Nft1Group {
+            return Err(ServerError::BadRequest(format!(
+                "Token {} is not an NFT1 group",
+                group_hex
+            ))
+            .into());
+        }
+        let json_group =
+            token_to_json(group_hex, &group).ok_or_else(|| eyre!("Token has no genesis_info"))?;
+
+        let limit = page.saturating_add(1).saturating_mul(take);
+        let mut child_geneses = Vec::new();
+        let mut num_walked = 0;
+        let has_more = self
+            .walk_token_txs(&group_id, |tx| {
+                num_walked += 1;
+                let is_child_genesis = tx
+                    .slp_tx_data
+                    .as_ref()
+                    .and_then(|slp_tx_data| slp_tx_data.slp_meta.as_ref())
+                    .map(|slp_meta| {
+                        slp_meta.token_type() == SlpTokenType::Nft1Child
+                            && slp_meta.tx_type() == SlpTxType::Genesis
+                            && slp_meta.group_token_id == group_meta.token_id
+                    })
+                    .unwrap_or(false);
+                if is_child_genesis {
+                    child_geneses.push(tx);
+                }
+                child_geneses.len() < limit && num_walked < MAX_NFT_GROUP_TXS_WALKED
+            })
+            .await?;
+        // Stopping at the walk limit isn't the end of the children
+        let is_complete = !has_more || child_geneses.len() >= limit;
+
+        let children = stream::iter(child_geneses.into_iter().skip(page * take))
+            .map(|genesis_tx| async move {
+                let child_id = Sha256d::from_slice(&genesis_tx.txid)?;
+                let genesis_info = genesis_tx
+                    .slp_tx_data
+                    .as_ref()
+                    .and_then(|slp_tx_data| slp_tx_data.genesis_info.clone())
+                    .unwrap_or_default();
+                let holder = self.follow_token_output(&child_id, false).await?;
+                Ok::<_, Report>(JsonNftChild {
+                    token_id: child_id.to_hex_be(),
+                    token_ticker: String::from_utf8_lossy(&genesis_info.token_ticker).to_string(),
+                    token_name: String::from_utf8_lossy(&genesis_info.token_name).to_string(),
+                    token_document_url: String::from_utf8_lossy(&genesis_info.token_document_url)
+                        .to_string(),
+                    genesis_block_height: genesis_tx.block.as_ref().map(|block| block.height),
+                    holder_address: holder.and_then(|holder| holder.address),
+                })
+            })
+            .buffered(NFT_HOLDER_LOOKUP_CONCURRENCY)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        Ok(JsonNftChildrenResponse {
+            group: json_group,
+            children,
+            page,
+            has_more: has_more && is_complete,
+            is_complete,
+        })
+    }
+
+    /// Returns up to `limit` txs of the token, in the order of
+    /// `walk_token_txs`, and whether any remain.
+    async fn token_txs(&self, token_id: &Sha256d, limit: usize) -> Result<(Vec<Tx>, bool)> {
+        let mut txs = Vec::new();
+        let has_more = self
+            .walk_token_txs(token_id, |tx| {
+                txs.push(tx);
+                txs.len() < limit
+            })
+            .await?;
+        Ok((txs, has_more))
+    }
+
+    /// Walks the token's tx graph breadth-first from its genesis by following
+    /// the `spent_by` links of its token outputs, passing each tx to `visit`
+    /// until it returns false. Returns whether the walk stopped early.
+    ///
+    /// The txs of each level of the graph are fetched concurrently, but
+    /// visited in order.
+    ///
+    /// For an NFT1 group, this includes the geneses of its children, but not
+    /// the children's own txs.
+    async fn walk_token_txs(
+        &self,
+        token_id: &Sha256d,
+        mut visit: impl FnMut(Tx) -> bool + Send,
+    ) -> Result<bool> {
+        let token_id_be = token_id.to_hex_be();
+        let mut visited = HashSet::new();
+        let mut level = vec![token_id.clone()];
+        visited.insert(token_id.clone());
+
+        while !level.is_empty() {
+            let mut next_level = Vec::new();
+            let mut level_txs = stream::iter(level.iter())
+                .map(|txid| self.chronik.tx(txid))
+                .buffered(TOKEN_TXS_CONCURRENCY);
+            let mut num_visited = 0;
+            while let Some(tx) = level_txs.try_next().await? {
+                num_visited += 1;
+                if is_tx_of_token(&tx, &token_id_be) {
+                    for output in tx.outputs.iter() {
+                        let is_token = output
+                            .slp_token
+                            .as_ref()
+                            .map(|slp| slp.amount > 0 || slp.is_mint_baton)
+                            .unwrap_or(false);
+                        if let (true, Some(spent_by)) = (is_token, &output.spent_by) {
+                            let spent_by_txid = Sha256d::from_slice(&spent_by.txid)?;
+                            if visited.insert(spent_by_txid.clone()) {
+                                next_level.push(spent_by_txid);
+                            }
+                        }
+                    }
+                }
+                if !visit(tx) {
+                    return Ok(num_visited < level.len() || !next_level.is_empty());
+                }
+            }
+            drop(level_txs);
+            level = next_level;
+        }
+
+        Ok(false)
+    }
+
+    /// Follows a token output from the genesis through every tx spending it
+    /// until it reaches an unspent output: the mint baton if `is_mint_baton`,
+    /// otherwise the token amount itself (only meaningful for NFTs). Returns
+    /// `None` if the output was destroyed.
+    ///
+    /// The walk resumes from the last tx a previous call reached, and fails
+    /// after `MAX_TOKEN_OUTPUT_HOPS`, so a retry gets further.
+    async fn follow_token_output(
+        &self,
+        token_id: &Sha256d,
+        is_mint_baton: bool,
+    ) -> Result<Option<JsonMintBaton>> {
+        let token_id_be = token_id.to_hex_be();
+        let cache_key = (token_id.clone(), is_mint_baton);
+        let cached_txid = self
+            .token_output_cache
+            .lock()
+            .unwrap()
+            .get(&cache_key)
+            .cloned();
+        let mut tx = match cached_txid {
+            // The cached tx may have been reorged out, then start over
+            Some(txid) => match self.chronik.tx(&txid).await {
+                Ok(tx) => tx,
+                Err(_) => self.chronik.tx(token_id).await?,
+            },
+            None => self.chronik.tx(token_id).await?,
+        };
+
+        for _ in 0..MAX_TOKEN_OUTPUT_HOPS {
+            self.token_output_cache
+                .lock()
+                .unwrap()
+                .put(cache_key.clone(), Sha256d::from_slice(&tx.txid)?);
+            if !is_tx_of_token(&tx, &token_id_be) {
+                return Ok(None);
+            }
+            let token_output = tx.outputs.iter().enumerate().find(|(_, output)| {
+                output
+                    .slp_token
+                    .as_ref()
+                    .map(|slp| match is_mint_baton {
+                        true => slp.is_mint_baton,
+                        false => slp.amount > 0,
+                    })
+                    .unwrap_or(false)
+            });
+            let (out_idx, output) = match token_output {
+                Some(token_output) => token_output,
+                None => return Ok(None),
+            };
+            match &output.spent_by {
+                Some(spent_by) => {
+                    let spent_by_txid = Sha256d::from_slice(&spent_by.txid)?;
+                    tx = self.chronik.tx(&spent_by_txid).await?;
+                }
+                None => {
+                    let address = match destination_from_script(
+                        self.tokens_addr_prefix,
+                        &output.output_script,
+                    ) {
+                        Destination::Address(address) => Some(address.as_str().to_string()),
+                        _ => None,
+                    };
+                    return Ok(Some(JsonMintBaton {
+                        tx_hash: to_be_hex(&tx.txid),
+                        out_idx: out_idx as u32,
+                        address,
+                    }));
+                }
+            }
+        }
+
+        bail!(
+            "Output of token {} moved too often to locate, try again",
+            token_id_be
+        )
+    }
+
     pub async fn address<'a>(&'a self, address: &str) -> Result<String> {
-        let address = CashAddress::parse_cow(address.into())?;
+        let address = parse_address(address)?;
         let sats_address = address.with_prefix(self.satoshi_addr_prefix);
         let token_address = address.with_prefix(self.tokens_addr_prefix);
 
@@ -328,12 +2013,54 @@ impl Server {
         let token_address = token_address.as_str();
 
         let (script_type, script_payload) = cash_addr_to_script_type_payload(&address);
-        let script_endpoint = self.chronik.script(script_type, &script_payload);
         let page_size = 1; // Set to minimum so that num_pages == total existing tx's
-        let address_tx_history = script_endpoint.history_with_page_size(0, page_size).await?;
+        let address_tx_history = self
+            .chronik
+            .script_history(script_type, &script_payload, 0, page_size)
+            .await?;
         let address_num_txs = address_tx_history.num_pages;
 
-        let utxos = script_endpoint.utxos().await?;
+        let AddressBalances {
+            token_dust,
+            total_xec,
+            token_utxos,
+            json_balances,
+            tokens,
+        } = self.address_balances(script_type, &script_payload).await?;
+        let json_tokens = tokens_to_json(&tokens)?;
+
+        let encoded_tokens = serde_json::to_string(&json_tokens)?.replace('\'', r"\'");
+        let encoded_balances = serde_json::to_string(&json_balances)?.replace('\'', r"\'");
+
+        let address_template = AddressTemplate {
+            tokens,
+            token_utxos,
+            token_dust,
+            total_xec,
+            address_num_txs,
+            address: address.as_str(),
+            sats_address,
+            token_address,
+            legacy_address,
+            json_balances,
+            encoded_tokens,
+            encoded_balances,
+        };
+
+        Ok(address_template.render().unwrap())
+    }
+
+    /// Groups the UTXOs of a script into its XEC balance (under the key
+    /// `"main"`) and one balance per token id.
+    async fn address_balances(
+        &self,
+        script_type: ScriptType,
+        script_payload: &[u8],
+    ) -> Result<AddressBalances> {
+        let utxos = self
+            .chronik
+            .script_utxos(script_type, script_payload)
+            .await?;
 
         let mut token_dust: i64 = 0;
         let mut total_xec: i64 = 0;
@@ -355,6 +2082,7 @@ impl Server {
                     tx_hash: to_be_hex(txid),
                     out_idx: *out_idx,
                     sats_amount: utxo.value,
+                    token_id: None,
                     token_amount: 0,
                     is_coinbase: utxo.is_coinbase,
                     block_height: utxo.block_height,
@@ -365,6 +2093,7 @@ impl Server {
                         let token_id_hex = hex::encode(&slp_meta.token_id);
                         let token_id_hash = Sha256d::from_slice_be_or_null(&slp_meta.token_id);
 
+                        json_utxo.token_id = Some(token_id_hex.clone());
                         json_utxo.token_amount = slp_token.amount;
 
                         match json_balances.entry(token_id_hex) {
@@ -390,6 +2119,7 @@ impl Server {
                     }
                     _ => {
                         total_xec += utxo.value;
+                        main_json_balance.sats_amount += utxo.value;
                         main_json_balance.utxos.push(json_utxo);
                     }
                 };
@@ -398,27 +2128,14 @@ impl Server {
         json_balances.insert(String::from("main"), main_json_balance);
 
         let tokens = self.batch_get_chronik_tokens(token_ids).await?;
-        let json_tokens = tokens_to_json(&tokens)?;
-
-        let encoded_tokens = serde_json::to_string(&json_tokens)?.replace('\'', r"\'");
-        let encoded_balances = serde_json::to_string(&json_balances)?.replace('\'', r"\'");
 
-        let address_template = AddressTemplate {
-            tokens,
-            token_utxos,
+        Ok(AddressBalances {
             token_dust,
             total_xec,
-            address_num_txs,
-            address: address.as_str(),
-            sats_address,
-            token_address,
-            legacy_address,
+            token_utxos,
             json_balances,
-            encoded_tokens,
-            encoded_balances,
-        };
-
-        Ok(address_template.render().unwrap())
+            tokens,
+        })
     }
 
     pub async fn batch_get_chronik_tokens(
@@ -447,7 +2164,7 @@ impl Server {
     pub async fn address_qr(&self, address: &str) -> Result<Vec<u8>> {
         use qrcode_generator::QrCodeEcc;
         if address.len() > 60 {
-            bail!("Invalid address length");
+            return Err(ServerError::BadRequest("Invalid address length".to_string()).into());
         }
         let png = qrcode_generator::to_png_to_vec(address, QrCodeEcc::Quartile, 160)?;
         Ok(png)
@@ -469,8 +2186,15 @@ impl Server {
         if let Ok(address) = CashAddress::parse_cow(query.into()) {
             return Ok(self.redirect(format!("/address/{}", address.as_str())));
         }
-        let bytes = from_be_hex(query)?;
-        let unknown_hash = Sha256d::from_slice(&bytes)?;
+        if query.parse::<Xpub>().is_ok() {
+            return Ok(self.redirect(format!("/xpub/{}", query)));
+        }
+        let unknown_hash = from_be_hex(query)
+            .ok()
+            .and_then(|bytes| Sha256d::from_slice(&bytes).ok())
+            .ok_or_else(|| {
+                ServerError::BadRequest(format!("Not an address, xpub or hash: {}", query))
+            })?;
 
         if self.chronik.tx(&unknown_hash).await.is_ok() {
             return Ok(self.redirect(format!("/tx/{}", query)));
@@ -486,3 +2210,214 @@ impl Server {
         Redirect::permanent(&url)
     }
 }
+
+fn parse_hash(hash_hex: &str) -> Result<Sha256d> {
+    Sha256d::from_hex_be(hash_hex)
+        .map_err(|_| ServerError::BadRequest(format!("Invalid hash: {}", hash_hex)).into())
+}
+
+fn parse_xpub(xpub: &str) -> Result<Xpub> {
+    xpub.parse()
+        .map_err(|_| ServerError::BadRequest(format!("Invalid xpub: {}", xpub)).into())
+}
+
+/// Parses a `/script/:type/:payload` pair, checking the payload fits the type.
+fn parse_script_type_payload(script_type: &str, payload: &str) -> Result<(ScriptType, Vec<u8>)> {
+    let script_type = parse_script_type(script_type)
+        .ok_or_else(|| ServerError::BadRequest(format!("Unknown script type: {}", script_type)))?;
+    let payload = hex::decode(payload)
+        .map_err(|_| ServerError::BadRequest(format!("Invalid payload hex: {}", payload)))?;
+    let is_valid_len = match script_type {
+        ScriptType::P2pkh | ScriptType::P2sh => payload.len() == 20,
+        ScriptType::P2pk => payload.len() == 33 || payload.len() == 65,
+        ScriptType::Other => payload.len() <= MAX_SCRIPT_SIZE,
+    };
+    if !is_valid_len {
+        return Err(ServerError::BadRequest(format!(
+            "Invalid payload length {} for script type {}",
+            payload.len(),
+            script_type_name(script_type)
+        ))
+        .into());
+    }
+    Ok((script_type, payload))
+}
+
+fn parse_address(address: &str) -> Result<CashAddress<'_>> {
+    CashAddress::parse_cow(address.into())
+        .map_err(|_| ServerError::BadRequest(format!("Invalid address: {}", address)).into())
+}
+
+/// Parses the query param `key`, falling back to `default` if it's missing.
+fn parse_query<T: FromStr>(query: &HashMap<String, String>, key: &str, default: T) -> Result<T> {
+    match query.get(key) {
+        Some(value) => value.parse().map_err(|_| {
+            ServerError::BadRequest(format!("Invalid query param {}: {}", key, value)).into()
+        }),
+        None => Ok(default),
+    }
+}
+
+/// Orders txs newest first when sorted in reverse, with mempool txs on top.
+fn wallet_history_key(tx: &Tx) -> (i32, i64) {
+    match &tx.block {
+        Some(block) => (block.height, block.timestamp),
+        None => (i32::MAX, tx.time_first_seen),
+    }
+}
+
+/// Token ID of `tx`, if it's a token tx of a known token type.
+fn known_token_id(tx: &Tx) -> Option<Sha256d> {
+    let slp_tx_data = tx.slp_tx_data.as_ref()?;
+    let slp_meta = slp_tx_data.slp_meta.as_ref()?;
+    if slp_meta.token_type() == SlpTokenType::UnknownTokenType {
+        return None;
+    }
+    Some(Sha256d::from_slice_be(&slp_meta.token_id).expect("Impossible"))
+}
+
+impl Server {
+    /// Relays events from `source` to websocket clients until it's exhausted.
+    pub async fn relay_events(self: Arc<Self>, mut source: impl EventSource + 'static) {
+        loop {
+            let event = match source.next_event(self.chronik.uncached()).await {
+                Ok(Some(event)) => event,
+                Ok(None) => return,
+                Err(err) => {
+                    eprintln!("Event source failed: {}", err);
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
+            };
+            if let Err(err) = self.handle_event(event).await {
+                eprintln!("Failed to relay event: {}", err);
+            }
+        }
+    }
+
+    async fn handle_event(&self, event: ExplorerEvent) -> Result<()> {
+        match event {
+            ExplorerEvent::BlockConnected(hash) => {
+                let block = self.chronik.uncached().block_by_hash(&hash).await?;
+                let block_info = block
+                    .block_info
+                    .as_ref()
+                    .ok_or_else(|| eyre!("Block has no info"))?;
+                // Only fails if no client is connected
+                let _ = self
+                    .events
+                    .send(RelayedEvent::Block(JsonWsMessage::BlockConnected {
+                        block: block_info_to_json(block_info, self.identify_block_miner(&block)?),
+                    }));
+                for tx in block.txs.iter() {
+                    self.relay_tx(tx, true);
+                }
+                let (height, summary) = BlockSummary::new(&block)?;
+                self.chain_stats.lock().unwrap().insert(height, summary);
+                self.mempool.lock().unwrap().remove_mined(&block);
+            }
+            ExplorerEvent::BlockDisconnected { hash, height } => {
+                self.chronik.invalidate_from_height(height);
+                self.chain_stats.lock().unwrap().remove_from_height(height);
+                let _ = self
+                    .events
+                    .send(RelayedEvent::Block(JsonWsMessage::BlockDisconnected {
+                        hash: hash.to_hex_be(),
+                        height,
+                    }));
+            }
+            ExplorerEvent::AddedToMempool(txid) => {
+                let tx = self.chronik.uncached().tx(&txid).await?;
+                self.relay_tx(&tx, false);
+                self.mempool.lock().unwrap().insert(txid, tx);
+            }
+            ExplorerEvent::RemovedFromMempool(txid) => {
+                self.mempool.lock().unwrap().remove(&txid);
+            }
+        }
+        Ok(())
+    }
+
+    /// Keeps the charts' block summaries complete, checking every
+    /// `sync_interval_secs` for blocks the relayed events didn't cover.
+    pub async fn sync_chain_stats(self: Arc<Self>, config: ChartsConfig) {
+        loop {
+            if let Err(err) = self.update_chain_stats(&config).await {
+                eprintln!("Failed to update chain stats: {}", err);
+            }
+            tokio::time::sleep(Duration::from_secs(config.sync_interval_secs)).await;
+        }
+    }
+
+    async fn update_chain_stats(&self, config: &ChartsConfig) -> Result<()> {
+        let blockchain_info = self.chronik.blockchain_info().await?;
+        let tip_height = blockchain_info.tip_height;
+        let start_height = (tip_height - config.history_days * BLOCKS_PER_DAY + 1).max(0);
+        let missing_heights = {
+            let mut chain_stats = self.chain_stats.lock().unwrap();
+            chain_stats.remove_below_height(start_height);
+            chain_stats.missing_heights(start_height, tip_height)
+        };
+        // Newest first, so recent days are charted while older ones still load
+        for heights in missing_heights.chunks(STATS_BLOCKS_PAGE_SIZE as usize) {
+            let summaries = stream::iter(heights)
+                .map(|&height| async move {
+                    let block = self.chronik.uncached().block_by_height(height).await?;
+                    self.identify_block_miner(&block)?;
+                    BlockSummary::new(&block)
+                })
+                .buffer_unordered(CHART_BLOCKS_CONCURRENCY)
+                .try_collect::<Vec<_>>()
+                .await?;
+            let mut chain_stats = self.chain_stats.lock().unwrap();
+            for (height, summary) in summaries {
+                chain_stats.insert(height, summary);
+            }
+        }
+        Ok(())
+    }
+
+    fn relay_tx(&self, tx: &Tx, is_confirmed: bool) {
+        let scripts = tx
+            .inputs
+            .iter()
+            .map(|input| input.output_script.clone())
+            .chain(tx.outputs.iter().map(|output| output.output_script.clone()))
+            .collect::<HashSet<_>>();
+        let _ = self.events.send(RelayedEvent::Tx {
+            tx_hash: to_be_hex(&tx.txid),
+            scripts: Arc::new(scripts),
+            is_confirmed,
+        });
+    }
+
+    /// Subscribes a websocket client to block events and to txs of the
+    /// comma-separated `addresses` in `query`.
+    pub fn ws_subscription(
+        &self,
+        query: &HashMap<String, String>,
+    ) -> Result<(broadcast::Receiver<RelayedEvent>, Vec<WatchedAddress>)> {
+        let mut watched_addresses = Vec::new();
+        if let Some(addresses) = query.get("addresses") {
+            for address in addresses.split(',').filter(|address| !address.is_empty()) {
+                let cash_address = parse_address(address)?;
+                let (script_type, script_payload) = cash_addr_to_script_type_payload(&cash_address);
+                watched_addresses.push(WatchedAddress {
+                    address: address.to_string(),
+                    script: script_from_type_payload(script_type, &script_payload),
+                    watch: None,
+                });
+            }
+        }
+        // Watched until the client's `WatchedAddress`es are dropped
+        if let Some(script_subscriptions) = &self.script_subscriptions {
+            for watched_address in watched_addresses.iter_mut() {
+                watched_address.watch = Some(ScriptWatch::new(
+                    watched_address.script.clone(),
+                    script_subscriptions.clone(),
+                ));
+            }
+        }
+        Ok((self.events.subscribe(), watched_addresses))
+    }
+}
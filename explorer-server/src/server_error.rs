@@ -1,28 +1,154 @@
+use std::fmt;
+
 use askama::Template;
+use async_trait::async_trait;
 use axum::{
+    extract::{FromRequest, RequestParts},
     http::StatusCode,
     response::{Html, IntoResponse, Response},
+    Json,
 };
+use eyre::Report;
 
-use crate::templating::ErrorTemplate;
+use crate::{server_primitives::JsonError, templating::ErrorTemplate};
 
-pub struct ServerError {
-    pub message: String,
+/// Error returned by the server's handlers.
+///
+/// Server methods raise these inside an `eyre::Report`; `to_server_error`
+/// recovers them, treating every other error as internal.
+#[derive(Debug, Clone)]
+pub enum ServerError {
+    /// Malformed user input, e.g. an invalid hash or query param.
+    BadRequest(String),
+    NotFound(String),
+    /// Chronik couldn't be reached or failed to answer.
+    UpstreamUnavailable(String),
+    Internal(String),
 }
 
+/// `ServerError` rendered as a JSON body, for `/api/*` routes.
+pub struct ApiError(pub ServerError);
+
+/// Extracts `E`, like a `Path` or `Query`, rejecting requests it can't be
+/// extracted from with a `ServerError` page.
+pub struct PageExtract<E>(pub E);
+
+/// Extracts `E`, like a `Path` or `Query`, rejecting requests it can't be
+/// extracted from with an `ApiError`.
+pub struct ApiExtract<E>(pub E);
+
+impl ServerError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            ServerError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ServerError::NotFound(_) => StatusCode::NOT_FOUND,
+            ServerError::UpstreamUnavailable(_) => StatusCode::BAD_GATEWAY,
+            ServerError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            ServerError::BadRequest(_) => "bad-request",
+            ServerError::NotFound(_) => "not-found",
+            ServerError::UpstreamUnavailable(_) => "upstream-unavailable",
+            ServerError::Internal(_) => "internal",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            ServerError::BadRequest(message)
+            | ServerError::NotFound(message)
+            | ServerError::UpstreamUnavailable(message)
+            | ServerError::Internal(message) => message,
+        }
+    }
+}
+
+impl fmt::Display for ServerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for ServerError {}
+
 impl IntoResponse for ServerError {
     fn into_response(self) -> Response {
+        let status_code = self.status_code();
         let error_template = ErrorTemplate {
-            message: self.message,
+            message: self.message().to_string(),
         };
         let error_page = error_template.render().unwrap();
 
-        (StatusCode::INTERNAL_SERVER_ERROR, Html(error_page)).into_response()
+        (status_code, Html(error_page)).into_response()
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let json_error = JsonError {
+            error: self.0.error_code(),
+            message: self.0.message().to_string(),
+        };
+
+        (self.0.status_code(), Json(json_error)).into_response()
     }
 }
 
-pub fn to_server_error<T: ToString>(err: T) -> ServerError {
-    ServerError {
-        message: err.to_string(),
+#[async_trait]
+impl<E, B> FromRequest<B> for PageExtract<E>
+where
+    E: FromRequest<B>,
+    E::Rejection: IntoResponse + fmt::Display,
+    B: Send,
+{
+    type Rejection = ServerError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        E::from_request(req)
+            .await
+            .map(PageExtract)
+            .map_err(rejection_to_server_error)
+    }
+}
+
+#[async_trait]
+impl<E, B> FromRequest<B> for ApiExtract<E>
+where
+    E: FromRequest<B>,
+    E::Rejection: IntoResponse + fmt::Display,
+    B: Send,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        E::from_request(req)
+            .await
+            .map(ApiExtract)
+            .map_err(|rejection| ApiError(rejection_to_server_error(rejection)))
     }
 }
+
+/// Malformed requests are bad requests, anything else axum rejects is
+/// internal, e.g. a handler asking for a path param its route lacks.
+fn rejection_to_server_error(rejection: impl IntoResponse + fmt::Display) -> ServerError {
+    let message = rejection.to_string();
+    if rejection.into_response().status().is_client_error() {
+        ServerError::BadRequest(message)
+    } else {
+        ServerError::Internal(message)
+    }
+}
+
+pub fn to_server_error(err: Report) -> ServerError {
+    match err.downcast_ref::<ServerError>() {
+        Some(server_error) => server_error.clone(),
+        None => ServerError::Internal(err.to_string()),
+    }
+}
+
+pub fn to_api_error(err: Report) -> ApiError {
+    ApiError(to_server_error(err))
+}
@@ -1,16 +1,24 @@
 use crate::{
+    events::serve_ws,
     server::Server,
-    server_error::{to_server_error, ServerError},
-    server_primitives::{JsonBlocksResponse, JsonTxsResponse},
+    server_error::{to_api_error, to_server_error, ApiError, ApiExtract, PageExtract, ServerError},
+    server_primitives::{
+        JsonAddressBalanceResponse, JsonBalanceHistoryResponse, JsonBlockStats, JsonBlocksResponse,
+        JsonCacheStats, JsonChartSeries, JsonDifficultyResponse, JsonMempoolResponse,
+        JsonMempoolSummary, JsonMinerStatsResponse, JsonNftChildrenResponse, JsonScriptResponse,
+        JsonTokenResponse, JsonTxDetails, JsonTxsResponse, JsonUtxosResponse, JsonWalletResponse,
+        JsonXpubResponse,
+    },
 };
 use axum::{
-    extract::{Path, Query},
-    http::StatusCode,
-    response::{Html, IntoResponse, Redirect},
+    body::StreamBody,
+    extract::{ws::WebSocketUpgrade, Path, Query},
+    http::{StatusCode, Uri},
+    response::{Html, IntoResponse, Redirect, Response},
     routing::{get_service, MethodRouter},
-    Extension, Json,
+    Extension, Form, Json,
 };
-use futures::future::ready;
+use futures::{future::ready, StreamExt};
 use std::{collections::HashMap, sync::Arc};
 use tower_http::services::ServeDir;
 
@@ -22,29 +30,143 @@ pub async fn blocks(server: Extension<Arc<Server>>) -> Result<Html<String>, Serv
     Ok(Html(server.blocks().await.map_err(to_server_error)?))
 }
 
+pub async fn mempool(
+    PageExtract(Query(query)): PageExtract<Query<HashMap<String, String>>>,
+    server: Extension<Arc<Server>>,
+) -> Result<Html<String>, ServerError> {
+    Ok(Html(server.mempool(query).await.map_err(to_server_error)?))
+}
+
+pub async fn miners(
+    PageExtract(Query(query)): PageExtract<Query<HashMap<String, String>>>,
+    server: Extension<Arc<Server>>,
+) -> Result<Html<String>, ServerError> {
+    Ok(Html(server.miners(query).await.map_err(to_server_error)?))
+}
+
+pub async fn charts(server: Extension<Arc<Server>>) -> Result<Html<String>, ServerError> {
+    Ok(Html(server.charts().await.map_err(to_server_error)?))
+}
+
 pub async fn tx(
-    Path(hash): Path<String>,
+    PageExtract(Path(hash)): PageExtract<Path<String>>,
     server: Extension<Arc<Server>>,
 ) -> Result<Html<String>, ServerError> {
     Ok(Html(server.tx(&hash).await.map_err(to_server_error)?))
 }
 
 pub async fn block(
-    Path(hash): Path<String>,
+    PageExtract(Path(hash)): PageExtract<Path<String>>,
     server: Extension<Arc<Server>>,
 ) -> Result<Html<String>, ServerError> {
     Ok(Html(server.block(&hash).await.map_err(to_server_error)?))
 }
 
+pub async fn token(
+    PageExtract(Path(hash)): PageExtract<Path<String>>,
+    PageExtract(Query(query)): PageExtract<Query<HashMap<String, String>>>,
+    server: Extension<Arc<Server>>,
+) -> Result<Html<String>, ServerError> {
+    Ok(Html(
+        server.token(&hash, query).await.map_err(to_server_error)?,
+    ))
+}
+
+pub async fn nft_children(
+    PageExtract(Path(hash)): PageExtract<Path<String>>,
+    PageExtract(Query(query)): PageExtract<Query<HashMap<String, String>>>,
+    server: Extension<Arc<Server>>,
+) -> Result<Html<String>, ServerError> {
+    Ok(Html(
+        server
+            .nft_children(&hash, query)
+            .await
+            .map_err(to_server_error)?,
+    ))
+}
+
 pub async fn address(
-    Path(hash): Path<String>,
+    PageExtract(Path(hash)): PageExtract<Path<String>>,
     server: Extension<Arc<Server>>,
 ) -> Result<Html<String>, ServerError> {
     Ok(Html(server.address(&hash).await.map_err(to_server_error)?))
 }
 
+pub async fn script(
+    PageExtract(Path((script_type, payload))): PageExtract<Path<(String, String)>>,
+    PageExtract(Query(query)): PageExtract<Query<HashMap<String, String>>>,
+    server: Extension<Arc<Server>>,
+) -> Result<Html<String>, ServerError> {
+    Ok(Html(
+        server
+            .script(&script_type, &payload, query)
+            .await
+            .map_err(to_server_error)?,
+    ))
+}
+
+pub async fn wallet(
+    PageExtract(Query(query)): PageExtract<Query<HashMap<String, String>>>,
+    server: Extension<Arc<Server>>,
+) -> Result<Html<String>, ServerError> {
+    Ok(Html(server.wallet(query).await.map_err(to_server_error)?))
+}
+
+pub async fn wallet_form(
+    PageExtract(Query(mut query)): PageExtract<Query<HashMap<String, String>>>,
+    PageExtract(Form(form)): PageExtract<Form<HashMap<String, String>>>,
+    server: Extension<Arc<Server>>,
+) -> Result<Html<String>, ServerError> {
+    query.extend(form);
+    Ok(Html(server.wallet(query).await.map_err(to_server_error)?))
+}
+
+pub async fn xpub(
+    PageExtract(Path(xpub)): PageExtract<Path<String>>,
+    PageExtract(Query(query)): PageExtract<Query<HashMap<String, String>>>,
+    server: Extension<Arc<Server>>,
+) -> Result<Html<String>, ServerError> {
+    Ok(Html(
+        server.xpub(&xpub, query).await.map_err(to_server_error)?,
+    ))
+}
+
+pub async fn ws(
+    ws: WebSocketUpgrade,
+    ApiExtract(Query(query)): ApiExtract<Query<HashMap<String, String>>>,
+    server: Extension<Arc<Server>>,
+) -> Result<Response, ApiError> {
+    let (events, watched_addresses) = server.ws_subscription(&query).map_err(to_api_error)?;
+    Ok(ws.on_upgrade(move |socket| serve_ws(socket, events, watched_addresses)))
+}
+
+pub async fn data_address_export(
+    ApiExtract(Path(hash)): ApiExtract<Path<String>>,
+    ApiExtract(Query(query)): ApiExtract<Query<HashMap<String, String>>>,
+    server: Extension<Arc<Server>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let (format, rows) = server
+        .0
+        .clone()
+        .address_export(&hash, query)
+        .map_err(to_api_error)?;
+    let content_disposition = format!(
+        "attachment; filename=\"{}.{}\"",
+        hash.replace(':', "_"),
+        format.extension(),
+    );
+    Ok((
+        StatusCode::OK,
+        [
+            ("content-type", format.content_type().to_string()),
+            ("content-disposition", content_disposition),
+        ],
+        StreamBody::new(rows.map(|chunk| chunk.map_err(|err| err.to_string()))),
+    ))
+}
+
 pub async fn address_qr(
-    Path(hash): Path<String>,
+    PageExtract(Path(hash)): PageExtract<Path<String>>,
     server: Extension<Arc<Server>>,
 ) -> Result<impl IntoResponse, ServerError> {
     let qr_code = server.address_qr(&hash).await.map_err(to_server_error)?;
@@ -52,56 +174,250 @@ pub async fn address_qr(
 }
 
 pub async fn block_height(
-    Path(height): Path<u32>,
+    PageExtract(Path(height)): PageExtract<Path<u32>>,
     server: Extension<Arc<Server>>,
 ) -> Result<Redirect, ServerError> {
     Ok(server.block_height(height).await.map_err(to_server_error)?)
 }
 
 pub async fn search(
-    Path(query): Path<String>,
+    PageExtract(Path(query)): PageExtract<Path<String>>,
     server: Extension<Arc<Server>>,
 ) -> Result<Redirect, ServerError> {
     server.search(&query).await.map_err(to_server_error)
 }
 
+pub async fn not_found(uri: Uri) -> Response {
+    let error = ServerError::NotFound(format!("Page not found: {}", uri.path()));
+    if uri.path().starts_with("/api/") {
+        ApiError(error).into_response()
+    } else {
+        error.into_response()
+    }
+}
+
 pub async fn data_blocks(
-    Path((start_height, end_height)): Path<(i32, i32)>,
+    ApiExtract(Path((start_height, end_height))): ApiExtract<Path<(i32, i32)>>,
     server: Extension<Arc<Server>>,
-) -> Result<Json<JsonBlocksResponse>, ServerError> {
+) -> Result<Json<JsonBlocksResponse>, ApiError> {
     Ok(Json(
         server
             .data_blocks(start_height, end_height)
             .await
-            .map_err(to_server_error)?,
+            .map_err(to_api_error)?,
+    ))
+}
+
+pub async fn data_mempool(
+    ApiExtract(Query(query)): ApiExtract<Query<HashMap<String, String>>>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonMempoolResponse>, ApiError> {
+    Ok(Json(
+        server.data_mempool(query).await.map_err(to_api_error)?,
+    ))
+}
+
+pub async fn data_mempool_summary(server: Extension<Arc<Server>>) -> Json<JsonMempoolSummary> {
+    Json(server.data_mempool_summary())
+}
+
+pub async fn data_miners(
+    ApiExtract(Query(query)): ApiExtract<Query<HashMap<String, String>>>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonMinerStatsResponse>, ApiError> {
+    Ok(Json(server.data_miners(query).await.map_err(to_api_error)?))
+}
+
+pub async fn data_chart(
+    ApiExtract(Path(series)): ApiExtract<Path<String>>,
+    ApiExtract(Query(query)): ApiExtract<Query<HashMap<String, String>>>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonChartSeries>, ApiError> {
+    Ok(Json(
+        server.data_chart(&series, query).map_err(to_api_error)?,
+    ))
+}
+
+pub async fn data_difficulty(
+    ApiExtract(Query(query)): ApiExtract<Query<HashMap<String, String>>>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonDifficultyResponse>, ApiError> {
+    Ok(Json(
+        server.data_difficulty(query).await.map_err(to_api_error)?,
+    ))
+}
+
+pub async fn data_block_stats(
+    ApiExtract(Path(hash)): ApiExtract<Path<String>>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonBlockStats>, ApiError> {
+    Ok(Json(
+        server.data_block_stats(&hash).await.map_err(to_api_error)?,
     ))
 }
 
 pub async fn data_block_txs(
-    Path(hash): Path<String>,
+    ApiExtract(Path(hash)): ApiExtract<Path<String>>,
     server: Extension<Arc<Server>>,
-) -> Result<Json<JsonTxsResponse>, ServerError> {
+) -> Result<Json<JsonTxsResponse>, ApiError> {
     Ok(Json(
-        server
-            .data_block_txs(&hash)
-            .await
-            .map_err(to_server_error)?,
+        server.data_block_txs(&hash).await.map_err(to_api_error)?,
     ))
 }
 
 pub async fn data_address_txs(
-    Path(hash): Path<String>,
-    Query(query): Query<HashMap<String, String>>,
+    ApiExtract(Path(hash)): ApiExtract<Path<String>>,
+    ApiExtract(Query(query)): ApiExtract<Query<HashMap<String, String>>>,
     server: Extension<Arc<Server>>,
-) -> Result<Json<JsonTxsResponse>, ServerError> {
+) -> Result<Json<JsonTxsResponse>, ApiError> {
     Ok(Json(
         server
             .data_address_txs(&hash, query)
             .await
-            .map_err(to_server_error)?,
+            .map_err(to_api_error)?,
+    ))
+}
+
+pub async fn data_script(
+    ApiExtract(Path((script_type, payload))): ApiExtract<Path<(String, String)>>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonScriptResponse>, ApiError> {
+    Ok(Json(
+        server
+            .data_script(&script_type, &payload)
+            .await
+            .map_err(to_api_error)?,
+    ))
+}
+
+pub async fn data_script_txs(
+    ApiExtract(Path((script_type, payload))): ApiExtract<Path<(String, String)>>,
+    ApiExtract(Query(query)): ApiExtract<Query<HashMap<String, String>>>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonTxsResponse>, ApiError> {
+    Ok(Json(
+        server
+            .data_script_txs(&script_type, &payload, query)
+            .await
+            .map_err(to_api_error)?,
     ))
 }
 
+pub async fn data_script_utxos(
+    ApiExtract(Path((script_type, payload))): ApiExtract<Path<(String, String)>>,
+    ApiExtract(Query(query)): ApiExtract<Query<HashMap<String, String>>>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonUtxosResponse>, ApiError> {
+    Ok(Json(
+        server
+            .data_script_utxos(&script_type, &payload, query)
+            .await
+            .map_err(to_api_error)?,
+    ))
+}
+
+pub async fn data_wallet(
+    ApiExtract(Query(query)): ApiExtract<Query<HashMap<String, String>>>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonWalletResponse>, ApiError> {
+    Ok(Json(server.data_wallet(query).await.map_err(to_api_error)?))
+}
+
+/// Like `data_wallet`, for address lists too long for a query string.
+pub async fn data_wallet_form(
+    ApiExtract(Query(mut query)): ApiExtract<Query<HashMap<String, String>>>,
+    ApiExtract(Form(form)): ApiExtract<Form<HashMap<String, String>>>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonWalletResponse>, ApiError> {
+    query.extend(form);
+    Ok(Json(server.data_wallet(query).await.map_err(to_api_error)?))
+}
+
+pub async fn data_xpub(
+    ApiExtract(Path(xpub)): ApiExtract<Path<String>>,
+    ApiExtract(Query(query)): ApiExtract<Query<HashMap<String, String>>>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonXpubResponse>, ApiError> {
+    Ok(Json(
+        server.data_xpub(&xpub, query).await.map_err(to_api_error)?,
+    ))
+}
+
+pub async fn data_address_balance(
+    ApiExtract(Path(hash)): ApiExtract<Path<String>>,
+    ApiExtract(Query(query)): ApiExtract<Query<HashMap<String, String>>>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonAddressBalanceResponse>, ApiError> {
+    Ok(Json(
+        server
+            .data_address_balance(&hash, query)
+            .await
+            .map_err(to_api_error)?,
+    ))
+}
+
+pub async fn data_address_balance_history(
+    ApiExtract(Path(hash)): ApiExtract<Path<String>>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonBalanceHistoryResponse>, ApiError> {
+    Ok(Json(
+        server
+            .data_address_balance_history(&hash)
+            .await
+            .map_err(to_api_error)?,
+    ))
+}
+
+pub async fn data_address_utxos(
+    ApiExtract(Path(hash)): ApiExtract<Path<String>>,
+    ApiExtract(Query(query)): ApiExtract<Query<HashMap<String, String>>>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonUtxosResponse>, ApiError> {
+    Ok(Json(
+        server
+            .data_address_utxos(&hash, query)
+            .await
+            .map_err(to_api_error)?,
+    ))
+}
+
+pub async fn data_tx(
+    ApiExtract(Path(hash)): ApiExtract<Path<String>>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonTxDetails>, ApiError> {
+    Ok(Json(server.data_tx(&hash).await.map_err(to_api_error)?))
+}
+
+pub async fn data_token(
+    ApiExtract(Path(hash)): ApiExtract<Path<String>>,
+    ApiExtract(Query(query)): ApiExtract<Query<HashMap<String, String>>>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonTokenResponse>, ApiError> {
+    Ok(Json(
+        server
+            .data_token(&hash, query)
+            .await
+            .map_err(to_api_error)?,
+    ))
+}
+
+pub async fn data_nft_children(
+    ApiExtract(Path(hash)): ApiExtract<Path<String>>,
+    ApiExtract(Query(query)): ApiExtract<Query<HashMap<String, String>>>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonNftChildrenResponse>, ApiError> {
+    Ok(Json(
+        server
+            .data_nft_children(&hash, query)
+            .await
+            .map_err(to_api_error)?,
+    ))
+}
+
+pub async fn data_cache_stats(server: Extension<Arc<Server>>) -> Json<JsonCacheStats> {
+    Json(server.data_cache_stats())
+}
+
 pub fn serve_files(path: &std::path::Path) -> MethodRouter {
     get_service(ServeDir::new(path)).handle_error(|_| ready(StatusCode::INTERNAL_SERVER_ERROR))
 }
@@ -1,12 +1,15 @@
 use serde::Serialize;
 use std::collections::HashMap;
 
+use crate::reward::CoinbaseOutputKind;
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct JsonUtxo {
     pub tx_hash: String,
     pub out_idx: u32,
     pub sats_amount: i64,
+    pub token_id: Option<String>,
     pub token_amount: u64,
     pub is_coinbase: bool,
     pub block_height: i32,
@@ -21,6 +24,111 @@ pub struct JsonBalance {
     pub utxos: Vec<JsonUtxo>,
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTokenBalance {
+    pub token_id: String,
+    pub token: Option<JsonToken>,
+    pub token_amount: i128,
+    /// `token_amount` with the token's decimals applied, e.g. "12.50".
+    pub token_amount_str: String,
+    pub sats_amount: i64,
+    pub num_utxos: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonAddressBalanceResponse {
+    pub address: String,
+    pub total_xec: i64,
+    pub token_dust: i64,
+    pub tokens: Vec<JsonTokenBalance>,
+}
+
+/// Balances of an output script by its Chronik script type and payload, e.g.
+/// of a P2PK pubkey, which has no address.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonScriptResponse {
+    pub script_type: String,
+    pub payload: String,
+    pub script_hex: String,
+    pub total_xec: i64,
+    pub token_dust: i64,
+    pub tokens: Vec<JsonTokenBalance>,
+    pub num_txs: u32,
+}
+
+/// Combined balances, UTXOs and history of several addresses.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonWalletResponse {
+    pub addresses: Vec<JsonWalletAddress>,
+    pub total_xec: i64,
+    pub token_dust: i64,
+    pub tokens: Vec<JsonTokenBalance>,
+    pub utxos: Vec<JsonWalletUtxo>,
+    /// Page of the merged history, newest first; deltas are relative to the
+    /// whole wallet.
+    pub txs: Vec<JsonWalletTx>,
+    pub page: usize,
+    pub has_more: bool,
+    /// Whether `txs` covers the full history of every address.
+    pub is_complete: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonWalletAddress {
+    pub address: String,
+    pub total_xec: i64,
+    pub token_dust: i64,
+    pub num_utxos: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonWalletUtxo {
+    pub address: String,
+    #[serde(flatten)]
+    pub utxo: JsonUtxo,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonWalletTx {
+    #[serde(flatten)]
+    pub tx: JsonTx,
+    /// All inputs and outputs belong to the wallet, so only the fee left it.
+    pub is_internal: bool,
+}
+
+/// Used addresses derived from an xpub, and the wallet they form.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonXpubResponse {
+    pub xpub: String,
+    /// BIP44 path of the xpub, if it's an account-level key.
+    pub account_path: Option<String>,
+    pub gap_limit: u32,
+    pub addresses: Vec<JsonXpubAddress>,
+    /// First receive address after the last used one.
+    pub next_receive_address: String,
+    /// False if a chain has more used addresses than are scanned.
+    pub is_scan_complete: bool,
+    pub wallet: JsonWalletResponse,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonXpubAddress {
+    pub address: String,
+    /// 0 for receive and 1 for change addresses.
+    pub chain: u32,
+    pub index: u32,
+    pub num_txs: u32,
+}
+
 #[derive(Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct JsonToken {
@@ -34,6 +142,69 @@ pub struct JsonToken {
 
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
+pub struct JsonTokenResponse {
+    pub token: JsonToken,
+    pub token_type_name: String,
+    pub token_document_url: String,
+    pub token_document_hash: String,
+    pub genesis_block_height: Option<i32>,
+    pub time_first_seen: i64,
+    pub initial_token_quantity: u64,
+    pub total_minted: String,
+    pub total_burned: String,
+    pub mint_baton: Option<JsonMintBaton>,
+    /// The NFT1 group of a child NFT.
+    pub group: Option<JsonToken>,
+    pub txs: Vec<JsonTokenTx>,
+    pub page: usize,
+    pub has_more: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonNftChildrenResponse {
+    pub group: JsonToken,
+    pub children: Vec<JsonNftChild>,
+    pub page: usize,
+    pub has_more: bool,
+    /// False if the group has too many txs to look through for this page's
+    /// children.
+    pub is_complete: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonNftChild {
+    pub token_id: String,
+    pub token_ticker: String,
+    pub token_name: String,
+    pub token_document_url: String,
+    pub genesis_block_height: Option<i32>,
+    pub holder_address: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonMintBaton {
+    pub tx_hash: String,
+    pub out_idx: u32,
+    pub address: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTokenTx {
+    pub tx_hash: String,
+    pub block_height: Option<i32>,
+    pub timestamp: i64,
+    pub token_action: String,
+    pub token_input: i128,
+    pub token_output: i128,
+    pub is_valid: bool,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct JsonBlock {
     pub hash: String,
     pub height: i32,
@@ -41,6 +212,7 @@ pub struct JsonBlock {
     pub difficulty: f64,
     pub size: u64,
     pub num_txs: u64,
+    pub miner: Option<String>,
 }
 
 #[derive(Serialize, Clone)]
@@ -68,6 +240,144 @@ pub struct JsonTxStats {
     pub token_input: i128,
     pub token_output: i128,
     pub does_burn_slp: bool,
+    /// Zero for coinbase txs.
+    pub fee: i64,
+    /// Fee in sats per byte.
+    pub fee_rate: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTxDetails {
+    pub tx_hash: String,
+    pub version: i32,
+    pub lock_time: u32,
+    pub block_height: Option<i32>,
+    pub block_hash: Option<String>,
+    pub timestamp: i64,
+    pub time_first_seen: i64,
+    pub confirmations: i32,
+    pub is_coinbase: bool,
+    pub size: u32,
+    pub fee: i64,
+    pub stats: JsonTxStats,
+    pub token_id: Option<String>,
+    pub token: Option<JsonToken>,
+    pub token_action: Option<String>,
+    pub slp_error_msg: Option<String>,
+    pub inputs: Vec<JsonTxInput>,
+    pub outputs: Vec<JsonTxOutput>,
+    pub raw_tx: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTxInput {
+    pub prev_tx_hash: Option<String>,
+    pub prev_out_idx: Option<u32>,
+    pub input_script: String,
+    pub input_script_asm: String,
+    pub script_sig: JsonScriptSig,
+    pub output_script: String,
+    pub destination: JsonDestination,
+    pub sats_amount: i64,
+    pub token_amount: u64,
+    pub is_mint_baton: bool,
+    pub sequence_no: u32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTxOutput {
+    pub output_script: String,
+    pub output_script_asm: String,
+    pub destination: JsonDestination,
+    pub op_return: Option<JsonOpReturn>,
+    pub sats_amount: i64,
+    pub token_amount: u64,
+    pub is_mint_baton: bool,
+    pub spent_by_tx_hash: Option<String>,
+    pub spent_by_idx: Option<u32>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum JsonScriptSig {
+    P2pkh {
+        signature: String,
+        pubkey: String,
+    },
+    P2pk {
+        signature: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Multisig {
+        num_required: u8,
+        num_keys: u8,
+        signatures: Vec<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    P2sh {
+        redeem_script: String,
+        redeem_script_asm: String,
+        multisig: Option<JsonMultisig>,
+    },
+    Coinbase,
+    Unknown,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonMultisig {
+    pub num_required: u8,
+    pub num_keys: u8,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonOpReturn {
+    pub protocol: Option<String>,
+    pub fields: Vec<JsonOpReturnField>,
+    pub hex: String,
+    pub utf8_preview: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonOpReturnField {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum JsonDestination {
+    Address {
+        address: String,
+    },
+    P2pk {
+        pubkey: String,
+    },
+    Multisig {
+        num_required: u8,
+        pubkeys: Vec<String>,
+    },
+    P2shWrapped {
+        address: String,
+        redeem_script: Box<JsonDestination>,
+    },
+    TimeLocked {
+        locktime: i64,
+        destination: Box<JsonDestination>,
+    },
+    HashPuzzle {
+        hash_type: String,
+        hash: String,
+    },
+    AnyoneCanSpend,
+    Nulldata,
+    Malformed,
+    Unknown,
 }
 
 #[derive(Serialize)]
@@ -84,8 +394,247 @@ pub struct JsonBlocksResponse {
     pub data: Vec<JsonBlock>,
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonBlockStats {
+    pub hash: String,
+    pub height: i32,
+    pub total_fee: i64,
+    pub min_fee_rate: f64,
+    pub median_fee_rate: f64,
+    pub max_fee_rate: f64,
+    pub total_output: i64,
+    pub num_inputs: u64,
+    pub num_outputs: u64,
+    pub num_token_txs: JsonTokenTxCounts,
+    pub largest_tx: Option<JsonLargestTx>,
+    pub coinbase: JsonCoinbaseBreakdown,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonCoinbaseBreakdown {
+    pub subsidy: i64,
+    pub fees: i64,
+    pub outputs: Vec<JsonCoinbaseOutput>,
+    /// Deviations from the expected split, e.g. an underpaid miner fund.
+    pub warnings: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonCoinbaseOutput {
+    pub out_idx: usize,
+    pub kind: CoinbaseOutputKind,
+    pub address: Option<String>,
+    pub value: i64,
+}
+
+/// Number of token txs by type; `burn` counts every tx burning tokens.
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTokenTxCounts {
+    pub genesis: u64,
+    pub mint: u64,
+    pub send: u64,
+    pub burn: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonLargestTx {
+    pub tx_hash: String,
+    pub size: i32,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonMinerStatsResponse {
+    pub tip_height: i32,
+    pub windows: Vec<JsonMinerStatsWindow>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonMinerStatsWindow {
+    pub label: String,
+    pub start_height: i32,
+    pub end_height: i32,
+    pub num_blocks: u64,
+    /// Network hashrate estimated from the window's average difficulty.
+    pub hashrate: f64,
+    pub pools: Vec<JsonPoolShare>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonPoolShare {
+    /// Pool name, `None` for blocks not matching any known pool.
+    pub name: Option<String>,
+    pub url: Option<String>,
+    pub num_blocks: u64,
+    pub share: f64,
+    pub hashrate: f64,
+}
+
+/// Balances of an address after each of its txs, oldest first.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonBalanceHistoryResponse {
+    /// Balance in sats, including sats in token UTXOs.
+    pub xec: Vec<JsonBalancePoint>,
+    pub tokens: Vec<JsonTokenBalanceHistory>,
+    pub num_txs: usize,
+    /// False if the address has more txs than were walked; the series then
+    /// only cover its latest txs.
+    pub is_complete: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTokenBalanceHistory {
+    pub token_id: String,
+    pub token: Option<JsonToken>,
+    pub points: Vec<JsonBalancePoint>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonBalancePoint {
+    pub timestamp: i64,
+    pub tx_hash: String,
+    /// Balance after the tx, in sats or base token units.
+    pub balance: i128,
+}
+
+/// A tx of an address history export; amounts are decimal strings with the
+/// XEC or token decimals applied, deltas relative to the exported address.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonExportRow {
+    /// RFC 3339 date in UTC.
+    pub date: String,
+    pub timestamp: i64,
+    pub tx_hash: String,
+    pub block_height: Option<i32>,
+    pub delta_xec: String,
+    pub token_id: Option<String>,
+    pub token_ticker: Option<String>,
+    pub delta_tokens: Option<String>,
+    /// Fee the address paid; zero unless it funded the tx.
+    pub fee_xec: String,
+    pub counterparties: Vec<String>,
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct JsonTxsResponse {
     pub data: Vec<JsonTx>,
 }
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonUtxosResponse {
+    pub data: Vec<JsonUtxo>,
+}
+
+/// Network hashrate over recent blocks and ASERT's expectation of the next
+/// block.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonDifficultyResponse {
+    pub tip_height: i32,
+    pub difficulty: f64,
+    pub next_bits: u32,
+    pub next_difficulty: f64,
+    /// Seconds the chain is behind (positive) or ahead (negative) of one
+    /// block every 10 minutes since the ASERT anchor block.
+    pub schedule_offset_secs: i64,
+    pub num_blocks: usize,
+    /// Estimated from the work and timestamps of the last `num_blocks` blocks.
+    pub hashrate: f64,
+    pub avg_block_time: f64,
+}
+
+/// Daily values of a chart series; `labels` names each of `values`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonChartSeries {
+    pub series: String,
+    pub title: &'static str,
+    pub labels: Vec<String>,
+    pub points: Vec<JsonChartPoint>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonChartPoint {
+    /// Start of the UTC day.
+    pub timestamp: i64,
+    pub values: Vec<f64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonMempoolSummary {
+    pub num_txs: u64,
+    pub total_bytes: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub immutable_entries: usize,
+    pub immutable_bytes: usize,
+    pub volatile_entries: usize,
+    pub volatile_bytes: usize,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum JsonWsMessage {
+    BlockConnected {
+        block: JsonBlock,
+    },
+    BlockDisconnected {
+        hash: String,
+        height: i32,
+    },
+    #[serde(rename_all = "camelCase")]
+    AddressTx {
+        address: String,
+        tx_hash: String,
+        is_confirmed: bool,
+    },
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonMempoolResponse {
+    pub num_txs: u64,
+    pub total_bytes: u64,
+    pub total_fee: i64,
+    pub fee_rate_histogram: Vec<JsonFeeRateBucket>,
+    pub txs: Vec<JsonTx>,
+    pub page: usize,
+    pub has_more: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonFeeRateBucket {
+    pub min_fee_rate: f64,
+    /// `None` for the last, unbounded bucket.
+    pub max_fee_rate: Option<f64>,
+    pub num_txs: u64,
+    pub total_bytes: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonError {
+    pub error: &'static str,
+    pub message: String,
+}
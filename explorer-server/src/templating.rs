@@ -5,18 +5,58 @@ use bitcoinsuite_chronik_client::proto::{
 use chrono::{DateTime, Utc};
 use std::collections::HashMap;
 
-use crate::{blockchain::Destination, server_primitives::JsonBalance};
+use crate::{
+    blockchain::Destination,
+    charts::ChartSeries,
+    coinbase::CoinbaseInfo,
+    op_return::DecodedOpReturn,
+    pools::Pool,
+    server_primitives::{
+        JsonBalance, JsonBlock, JsonBlockStats, JsonDifficultyResponse, JsonMempoolResponse,
+        JsonMinerStatsResponse, JsonNftChildrenResponse, JsonScriptResponse, JsonTokenResponse,
+        JsonTx, JsonWalletResponse, JsonXpubResponse,
+    },
+};
 
 mod filters;
 
 #[derive(Template)]
 #[template(path = "pages/homepage.html")]
-pub struct HomepageTemplate {}
+pub struct HomepageTemplate {
+    pub tip_height: i32,
+    pub tip_hash: String,
+    /// Newest first.
+    pub blocks: Vec<JsonBlock>,
+    /// Newest mempool txs, followed by the txs of the tip block.
+    pub txs: Vec<JsonTx>,
+    pub mempool_num_txs: u64,
+    pub mempool_bytes: u64,
+    pub difficulty: JsonDifficultyResponse,
+}
 
 #[derive(Template)]
 #[template(path = "pages/blocks.html")]
 pub struct BlocksTemplate {
     pub last_block_height: u32,
+    pub difficulty: JsonDifficultyResponse,
+}
+
+#[derive(Template)]
+#[template(path = "pages/mempool.html")]
+pub struct MempoolTemplate {
+    pub mempool: JsonMempoolResponse,
+}
+
+#[derive(Template)]
+#[template(path = "pages/miners.html")]
+pub struct MinersTemplate {
+    pub stats: JsonMinerStatsResponse,
+}
+
+#[derive(Template)]
+#[template(path = "pages/charts.html")]
+pub struct ChartsTemplate {
+    pub series: Vec<ChartSeries>,
 }
 
 #[derive(Template)]
@@ -30,6 +70,9 @@ pub struct BlockTemplate<'a> {
     pub timestamp: DateTime<chrono::Utc>,
     pub difficulty: f64,
     pub coinbase_data: Vec<u8>,
+    pub coinbase: CoinbaseInfo,
+    pub miner: Option<Pool>,
+    pub stats: JsonBlockStats,
 }
 
 #[derive(Template)]
@@ -50,6 +93,25 @@ pub struct TransactionTemplate<'a> {
     pub sats_output: i64,
     pub token_input: i128,
     pub token_output: i128,
+    pub fee: i64,
+    pub fee_rate: f64,
+    pub redeem_scripts: HashMap<usize, Vec<u8>>,
+    pub op_returns: HashMap<usize, DecodedOpReturn>,
+}
+
+#[derive(Template)]
+#[template(path = "pages/token.html")]
+pub struct TokenTemplate<'a> {
+    pub token_hex: &'a str,
+    pub token: JsonTokenResponse,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Template)]
+#[template(path = "pages/nft_children.html")]
+pub struct NftChildrenTemplate<'a> {
+    pub group_hex: &'a str,
+    pub children: JsonNftChildrenResponse,
 }
 
 #[derive(Template)]
@@ -69,6 +131,32 @@ pub struct AddressTemplate<'a> {
     pub encoded_balances: String,
 }
 
+#[derive(Template)]
+#[template(path = "pages/script.html")]
+pub struct ScriptTemplate<'a> {
+    pub script: Vec<u8>,
+    pub destination: Destination<'a>,
+    pub summary: JsonScriptResponse,
+    /// Newest first.
+    pub txs: Vec<JsonTx>,
+    pub page: usize,
+    pub has_more: bool,
+}
+
+#[derive(Template)]
+#[template(path = "pages/wallet.html")]
+pub struct WalletTemplate {
+    /// Addresses as entered, to refill the form.
+    pub addresses: String,
+    pub wallet: Option<JsonWalletResponse>,
+}
+
+#[derive(Template)]
+#[template(path = "pages/xpub.html")]
+pub struct XpubTemplate {
+    pub xpub: JsonXpubResponse,
+}
+
 #[derive(Template)]
 #[template(path = "pages/error.html")]
 pub struct ErrorTemplate {
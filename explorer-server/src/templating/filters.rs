@@ -1,15 +1,22 @@
 use std::collections::HashMap;
 
-use chrono::DateTime;
+use chrono::{DateTime, TimeZone, Utc};
 use chrono_humanize::HumanTime;
-use maud::{html, PreEscaped};
+use maud::{html, Markup, PreEscaped};
 
 use bitcoinsuite_chronik_client::proto::{OutPoint, SlpToken, Token};
-use bitcoinsuite_core::Script;
+use bitcoinsuite_chronik_client::ScriptType;
 use humansize::{file_size_opts as options, FileSize};
 use num_format::{Locale, ToFormattedString};
 
-use crate::blockchain;
+use crate::{
+    blockchain::{self, Destination},
+    op_return::DecodedOpReturn,
+    script::{self, ScriptSig},
+};
+
+/// Locktimes below this are block heights, the rest UNIX timestamps.
+const LOCKTIME_THRESHOLD: i64 = 500_000_000;
 
 fn render_integer_with_small_flag(int: i128, smallify: bool) -> askama::Result<String> {
     let string = int.to_formatted_string(&Locale::en);
@@ -27,25 +34,156 @@ fn render_integer_with_small_flag(int: i128, smallify: bool) -> askama::Result<S
     Ok(output.into_string())
 }
 
-pub fn max(value: &i64, maximum: &i64) -> askama::Result<i64> {
-    Ok(*value.max(maximum))
-}
-
 pub fn check_is_coinbase(outpoint: &OutPoint) -> askama::Result<bool> {
     Ok(outpoint.txid == [0; 32] && outpoint.out_idx == 0xffff_ffff)
 }
 
-pub fn destination_from_script<'a>(
-    script: &'a [u8],
+pub fn input_destination<'a>(
+    output_script: &'a [u8],
+    is_token: &bool,
+    input_script: &[u8],
+) -> askama::Result<Destination<'a>> {
+    let prefix = if *is_token { "etoken" } else { "ecash" };
+    let redeem_script = blockchain::redeem_script(input_script, output_script);
+    Ok(blockchain::destination_from_spent_script(
+        prefix,
+        output_script,
+        redeem_script.as_deref(),
+    ))
+}
+
+pub fn output_destination<'a>(
+    output_script: &'a [u8],
     is_token: &bool,
-) -> askama::Result<blockchain::Destination<'a>> {
+    index: &usize,
+    redeem_scripts: &HashMap<usize, Vec<u8>>,
+) -> askama::Result<Destination<'a>> {
     let prefix = if *is_token { "etoken" } else { "ecash" };
-    Ok(blockchain::destination_from_script(prefix, script))
+    Ok(blockchain::destination_from_spent_script(
+        prefix,
+        output_script,
+        redeem_scripts.get(index).map(Vec::as_slice),
+    ))
+}
+
+pub fn render_destination(destination: &Destination) -> askama::Result<String> {
+    Ok(destination_markup(destination).into_string())
+}
+
+fn destination_markup(destination: &Destination) -> Markup {
+    match destination {
+        Destination::Address(address) => html! {
+            a href={ "/address/" (address.as_str()) } { (address.as_str()) }
+        },
+        Destination::P2PK(pubkey) => html! { "Pubkey: " (hex::encode(pubkey)) },
+        Destination::Multisig(num_required, pubkeys) => html! {
+            div.ui.horizontal.label { "Multisig " (num_required) "-of-" (pubkeys.len()) }
+            @for pubkey in pubkeys {
+                div { "Pubkey: " (hex::encode(pubkey)) }
+            }
+        },
+        Destination::P2SHWrapped(address, redeem) => html! {
+            a href={ "/address/" (address.as_str()) } { (address.as_str()) }
+            div {
+                small { "Redeem script: " }
+                (destination_markup(redeem))
+            }
+        },
+        Destination::TimeLocked(locktime, destination) => html! {
+            div.ui.horizontal.label {
+                @if *locktime < LOCKTIME_THRESHOLD {
+                    "Locked until block " (locktime)
+                } @else {
+                    @match Utc.timestamp_opt(*locktime, 0).single() {
+                        Some(time) => { "Locked until " (time.format("%Y-%m-%d %H:%M:%S UTC")) }
+                        None => { "Locked until timestamp " (locktime) }
+                    }
+                }
+            }
+            (destination_markup(destination))
+        },
+        Destination::HashPuzzle(hash_op, hash) => html! {
+            (script::opcode_name(*hash_op)) " hash puzzle: " (hex::encode(hash))
+        },
+        Destination::AnyoneCanSpend => html! { "Anyone can spend" },
+        Destination::Nulldata(_) => html! { "OP_RETURN data" },
+        Destination::Malformed(_) => html! { div.ui.red.horizontal.label { "Malformed script" } },
+        Destination::Unknown(_) => html! { "Unknown" },
+    }
+}
+
+/// Page listing the history of `script`, for scripts without an address
+/// like P2PK; empty and OP_RETURN scripts get none.
+pub fn script_page_url(script: &[u8]) -> askama::Result<Option<String>> {
+    let (script_type, payload) = blockchain::script_type_payload(script);
+    match script_type {
+        ScriptType::P2pkh | ScriptType::P2sh => Ok(None),
+        _ if matches!(script.first(), None | Some(&script::OP_RETURN)) => Ok(None),
+        _ => Ok(Some(format!(
+            "/script/{}/{}",
+            blockchain::script_type_name(script_type),
+            hex::encode(payload)
+        ))),
+    }
+}
+
+pub fn render_op_return(
+    op_returns: &HashMap<usize, DecodedOpReturn>,
+    index: &usize,
+) -> askama::Result<String> {
+    let op_return = match op_returns.get(index) {
+        Some(op_return) => op_return,
+        None => return Ok(String::new()),
+    };
+    let output = html! {
+        div.op-return {
+            @match op_return.protocol {
+                Some(protocol) => div.ui.blue.horizontal.label { (protocol) },
+                None => {
+                    div { "Hex: " (op_return.hex) }
+                    div { "Text: " (op_return.utf8_preview) }
+                },
+            }
+            @for field in &op_return.fields {
+                div {
+                    small { (field.name) ": " }
+                    (field.value)
+                }
+            }
+        }
+    };
+    Ok(output.into_string())
 }
 
-pub fn get_script(signature_script: &[u8]) -> askama::Result<String> {
-    let script = Script::from_slice(signature_script);
-    Ok(script.hex())
+pub fn disassemble_script(script: &[u8]) -> askama::Result<String> {
+    Ok(script::disassemble(script))
+}
+
+pub fn render_script_sig(input_script: &[u8], output_script: &[u8]) -> askama::Result<String> {
+    let output = match script::classify_script_sig(input_script, output_script) {
+        ScriptSig::P2pkh { .. } => html! { "P2PKH (signature and public key)" },
+        ScriptSig::P2pk { .. } => html! { "P2PK (signature)" },
+        ScriptSig::Multisig {
+            num_required,
+            num_keys,
+            ..
+        } => html! { "Bare multisig (" (num_required) "-of-" (num_keys) ")" },
+        ScriptSig::P2sh {
+            redeem_script,
+            multisig,
+        } => html! {
+            "P2SH"
+            @if let Some((num_required, num_keys)) = multisig {
+                " (" (num_required) "-of-" (num_keys) " multisig)"
+            }
+            p {
+                strong { "Redeem Script Decoded" }
+                div.hex { (script::disassemble(&redeem_script)) }
+            }
+        },
+        ScriptSig::Unknown => html! { "Unknown" },
+    };
+    Ok(output.into_string())
 }
 
 pub fn check_is_token(slp_token: &Option<SlpToken>) -> askama::Result<bool> {
@@ -59,6 +197,10 @@ pub fn human_time(timestamp: &DateTime<chrono::Utc>) -> askama::Result<HumanTime
     Ok(HumanTime::from(*timestamp))
 }
 
+pub fn render_age(timestamp: &i64) -> askama::Result<HumanTime> {
+    Ok(HumanTime::from(Utc.timestamp(*timestamp, 0)))
+}
+
 pub fn render_integer(int: &i128) -> askama::Result<String> {
     render_integer_with_small_flag(*int, false)
 }
@@ -67,6 +209,40 @@ pub fn render_integer_smallify(int: &i128) -> askama::Result<String> {
     render_integer_with_small_flag(*int, true)
 }
 
+pub fn render_hashrate(hashrate: &f64) -> askama::Result<String> {
+    let units = ["H/s", "kH/s", "MH/s", "GH/s", "TH/s", "PH/s", "EH/s"];
+    let mut hashrate = *hashrate;
+    let mut unit_idx = 0;
+    while hashrate >= 1000.0 && unit_idx < units.len() - 1 {
+        hashrate /= 1000.0;
+        unit_idx += 1;
+    }
+    Ok(format!("{:.2} {}", hashrate, units[unit_idx]))
+}
+
+pub fn render_percent(fraction: &f64) -> askama::Result<String> {
+    Ok(format!("{:.2}%", fraction * 100.0))
+}
+
+/// Describes how far the chain is behind or ahead of its block schedule.
+pub fn render_schedule_offset(offset_secs: &i64) -> askama::Result<String> {
+    let hours = offset_secs.abs() as f64 / 3600.0;
+    let blocks = offset_secs.abs() as f64 / 600.0;
+    Ok(match offset_secs.signum() {
+        0 => "On schedule".to_string(),
+        1 => format!("{:.1} h ({:.1} blocks) behind schedule", hours, blocks),
+        _ => format!("{:.1} h ({:.1} blocks) ahead of schedule", hours, blocks),
+    })
+}
+
+pub fn render_block_time(secs: &f64) -> askama::Result<String> {
+    Ok(format!(
+        "{}m {:02}s",
+        (*secs as i64) / 60,
+        (*secs as i64) % 60
+    ))
+}
+
 pub fn render_human_size(value: &u64) -> askama::Result<String> {
     Ok(value.file_size(options::CONVENTIONAL).unwrap())
 }
@@ -143,6 +319,18 @@ pub fn render_sats(sats: &i64) -> askama::Result<String> {
     Ok(output)
 }
 
+/// Renders a balance change in sats as signed XEC, e.g. "-1,000.50".
+pub fn render_sats_delta(sats: &i64) -> askama::Result<String> {
+    let sign = if *sats < 0 {
+        "-"
+    } else if *sats > 0 {
+        "+"
+    } else {
+        ""
+    };
+    Ok(format!("{}{}", sign, render_sats(&sats.abs())?))
+}
+
 pub fn hexify_u8_vector(value: &[u8]) -> askama::Result<String> {
     Ok(hex::encode(value))
 }
@@ -192,6 +380,24 @@ pub fn render_token_amount(base_amount: &i128, decimals: &u32) -> askama::Result
     Ok(output.into_string())
 }
 
+pub fn render_token_amount_str(base_amount: &str, decimals: &u32) -> askama::Result<String> {
+    let base_amount: i128 = base_amount.parse().unwrap_or_default();
+    render_token_amount(&base_amount, decimals)
+}
+
+pub fn render_tx_hash(tx_hash: &str) -> askama::Result<String> {
+    if tx_hash.len() < 64 {
+        return Ok(tx_hash.to_string());
+    }
+    Ok(format!("{}…{}", &tx_hash[..10], &tx_hash[60..]))
+}
+
+/// Renders a fee rate given in sats per byte as sats per kB.
+pub fn render_fee_rate(fee_rate: &f64) -> askama::Result<String> {
+    let sats_per_kb = (fee_rate * 1000.0).round() as i128;
+    Ok(format!("{}/kB", render_integer(&sats_per_kb)?))
+}
+
 pub fn get_token<'a>(
     tokens: &'a HashMap<String, Token>,
     token_id: &str,
@@ -0,0 +1,77 @@
+use std::str::FromStr;
+
+use bitcoin::{
+    hashes::{hash160, Hash},
+    secp256k1::{Secp256k1, VerifyOnly},
+    util::bip32::{ChildNumber, ExtendedPubKey},
+};
+use bitcoinsuite_core::{AddressType, CashAddress, ShaRmd160};
+use bitcoinsuite_error::Result;
+use eyre::{eyre, Report};
+
+/// SLIP-44 coin type of eCash.
+pub const ECASH_COIN_TYPE: u32 = 899;
+
+/// BIP44 chain of addresses handed out to receive payments.
+pub const RECEIVE_CHAIN: u32 = 0;
+/// BIP44 chain of addresses wallets send their change to.
+pub const CHANGE_CHAIN: u32 = 1;
+
+/// Extended public key of a BIP44 account, deriving the P2PKH addresses of
+/// its receive and change chains.
+pub struct Xpub {
+    xpub: ExtendedPubKey,
+    /// Keys of the receive and change chains.
+    chains: [ExtendedPubKey; 2],
+    secp: Secp256k1<VerifyOnly>,
+}
+
+/// Address at `chain/index` below an xpub.
+pub struct DerivedAddress {
+    pub chain: u32,
+    pub index: u32,
+    pub address: CashAddress<'static>,
+}
+
+impl FromStr for Xpub {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let xpub = ExtendedPubKey::from_str(s).map_err(|err| eyre!("Invalid xpub: {}", err))?;
+        let secp = Secp256k1::verification_only();
+        let chains = [
+            xpub.ckd_pub(&secp, ChildNumber::from_normal_idx(RECEIVE_CHAIN)?)?,
+            xpub.ckd_pub(&secp, ChildNumber::from_normal_idx(CHANGE_CHAIN)?)?,
+        ];
+        Ok(Xpub { xpub, chains, secp })
+    }
+}
+
+impl Xpub {
+    /// BIP44 path of the key, e.g. `m/44'/899'/0'`, if it's at account depth.
+    ///
+    /// The coin type isn't part of an xpub, so eCash's is assumed.
+    pub fn account_path(&self) -> Option<String> {
+        match (self.xpub.depth, self.xpub.child_number) {
+            (3, ChildNumber::Hardened { index }) => {
+                Some(format!("m/44'/{}'/{}'", ECASH_COIN_TYPE, index))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn derive(&self, chain: u32, index: u32, prefix: &'static str) -> Result<DerivedAddress> {
+        let chain_key = self
+            .chains
+            .get(chain as usize)
+            .ok_or_else(|| eyre!("Unknown chain {}", chain))?;
+        let key = chain_key.ckd_pub(&self.secp, ChildNumber::from_normal_idx(index)?)?;
+        let hash = hash160::Hash::hash(&key.public_key.key.serialize());
+        let hash = ShaRmd160::from_slice(&hash.into_inner()).expect("Impossible");
+        Ok(DerivedAddress {
+            chain,
+            index,
+            address: CashAddress::from_hash(prefix, AddressType::P2PKH, hash),
+        })
+    }
+}
@@ -0,0 +1,159 @@
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use bitcoinsuite_core::Sha256d;
+use explorer_server::{
+    backend::FixtureBackend,
+    config::CacheConfig,
+    events::{ChannelEventSource, ExplorerEvent, RelayedEvent, ScriptSubscription},
+    server::Server,
+};
+use tokio::sync::mpsc;
+
+const BLOCK_1: &str = "00000000000000000000000000000000000000000000000000000000000000b1";
+const COINBASE_1: &str = "00000000000000000000000000000000000000000000000000000000000000c1";
+const TX_A1: &str = "00000000000000000000000000000000000000000000000000000000000000a1";
+/// Mempool tx paying `ADDRESS_B`.
+const TX_D1: &str = "00000000000000000000000000000000000000000000000000000000000000d1";
+const UNKNOWN_HASH: &str = "00000000000000000000000000000000000000000000000000000000000000ff";
+const ADDRESS_B: &str = "ecash:qq3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg69pxshy7";
+
+fn address_b_script() -> Vec<u8> {
+    let mut script = vec![0x76, 0xa9, 0x14];
+    script.extend_from_slice(&[0x22; 20]);
+    script.extend_from_slice(&[0x88, 0xac]);
+    script
+}
+
+async fn setup() -> (Arc<Server>, mpsc::UnboundedReceiver<ScriptSubscription>) {
+    let base_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let backend = FixtureBackend::load_json(&base_dir.join("tests/fixtures/chain.json"))
+        .expect("Invalid fixture");
+    let (script_subscriptions, scripts) = mpsc::unbounded_channel();
+    let server = Server::setup(backend, base_dir.to_path_buf(), CacheConfig::default())
+        .await
+        .unwrap()
+        .with_script_subscriptions(script_subscriptions);
+    (Arc::new(server), scripts)
+}
+
+#[tokio::test]
+async fn test_relay_block_connected() {
+    let (server, mut scripts) = setup().await;
+    let query = HashMap::from([("addresses".to_string(), ADDRESS_B.to_string())]);
+    let (mut events, watched_addresses) = server.ws_subscription(&query).unwrap();
+    assert_eq!(watched_addresses.len(), 1);
+    assert_eq!(watched_addresses[0].address, ADDRESS_B);
+    assert_eq!(watched_addresses[0].script, address_b_script());
+    assert_eq!(
+        scripts.try_recv().unwrap(),
+        ScriptSubscription::Watch(address_b_script()),
+    );
+
+    let (sender, source) = ChannelEventSource::new();
+    let relay = tokio::spawn(server.clone().relay_events(source));
+    let block_hash = Sha256d::from_hex_be(BLOCK_1).unwrap();
+    sender
+        .send(ExplorerEvent::BlockConnected(block_hash))
+        .unwrap();
+    // Closing the channel ends the relay once the event is handled
+    drop(sender);
+    relay.await.unwrap();
+
+    let mut block_messages = Vec::new();
+    let mut tx_hashes = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        match event {
+            RelayedEvent::Block(message) => {
+                block_messages.push(serde_json::to_value(message).unwrap())
+            }
+            RelayedEvent::Tx {
+                tx_hash,
+                scripts,
+                is_confirmed,
+            } => {
+                assert!(is_confirmed);
+                assert!(scripts.contains(&address_b_script()));
+                tx_hashes.push(tx_hash);
+            }
+        }
+    }
+    assert_eq!(block_messages.len(), 1);
+    assert_eq!(block_messages[0]["type"], "blockConnected");
+    assert_eq!(block_messages[0]["block"]["hash"], BLOCK_1);
+    assert_eq!(block_messages[0]["block"]["height"], 1);
+    assert_eq!(tx_hashes, vec![COINBASE_1, TX_A1]);
+    // Only watched scripts are subscribed, not those of every block
+    assert!(scripts.try_recv().is_err());
+
+    // Unsubscribed once the client disconnects
+    drop(watched_addresses);
+    assert_eq!(
+        scripts.try_recv().unwrap(),
+        ScriptSubscription::Unwatch(address_b_script()),
+    );
+}
+
+#[tokio::test]
+async fn test_relay_block_disconnected() {
+    let (server, _scripts) = setup().await;
+    let (mut events, _) = server.ws_subscription(&HashMap::new()).unwrap();
+
+    let (sender, source) = ChannelEventSource::new();
+    let relay = tokio::spawn(server.clone().relay_events(source));
+    sender
+        .send(ExplorerEvent::BlockDisconnected {
+            hash: Sha256d::from_hex_be(BLOCK_1).unwrap(),
+            height: 1,
+        })
+        .unwrap();
+    drop(sender);
+    relay.await.unwrap();
+
+    let message = match events.try_recv().unwrap() {
+        RelayedEvent::Block(message) => serde_json::to_value(message).unwrap(),
+        RelayedEvent::Tx { .. } => panic!("Expected a block message"),
+    };
+    assert_eq!(message["type"], "blockDisconnected");
+    assert_eq!(message["hash"], BLOCK_1);
+    assert_eq!(message["height"], 1);
+    assert!(events.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_relay_mempool() {
+    let (server, mut scripts) = setup().await;
+    let mempool_tx_hashes = |server: Arc<Server>| async move {
+        let mempool = server.data_mempool(HashMap::new()).await.unwrap();
+        mempool
+            .txs
+            .into_iter()
+            .map(|tx| tx.tx_hash)
+            .collect::<Vec<_>>()
+    };
+    assert!(mempool_tx_hashes(server.clone()).await.is_empty());
+
+    let (sender, source) = ChannelEventSource::new();
+    let relay = tokio::spawn(server.clone().relay_events(source));
+    let txid = Sha256d::from_hex_be(TX_D1).unwrap();
+    sender
+        .send(ExplorerEvent::AddedToMempool(txid.clone()))
+        .unwrap();
+    sender
+        .send(ExplorerEvent::RemovedFromMempool(
+            Sha256d::from_hex_be(UNKNOWN_HASH).unwrap(),
+        ))
+        .unwrap();
+    drop(sender);
+    relay.await.unwrap();
+    assert_eq!(mempool_tx_hashes(server.clone()).await, vec![TX_D1]);
+    assert!(scripts.try_recv().is_err());
+
+    let (sender, source) = ChannelEventSource::new();
+    let relay = tokio::spawn(server.clone().relay_events(source));
+    sender
+        .send(ExplorerEvent::RemovedFromMempool(txid))
+        .unwrap();
+    drop(sender);
+    relay.await.unwrap();
+    assert!(mempool_tx_hashes(server.clone()).await.is_empty());
+}
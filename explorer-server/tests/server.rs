@@ -0,0 +1,279 @@
+use std::{path::Path, sync::Arc};
+
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+    Extension, Router,
+};
+use explorer_server::{backend::FixtureBackend, config::CacheConfig, server::Server};
+use serde_json::Value;
+use tower::ServiceExt;
+
+const BLOCK_0: &str = "00000000000000000000000000000000000000000000000000000000000000b0";
+const BLOCK_1: &str = "00000000000000000000000000000000000000000000000000000000000000b1";
+const COINBASE_1: &str = "00000000000000000000000000000000000000000000000000000000000000c1";
+/// Spends the coinbase of block 0 from `ADDRESS_A`, paying `ADDRESS_B`.
+const TX_A1: &str = "00000000000000000000000000000000000000000000000000000000000000a1";
+/// Mempool tx spending the change of `TX_A1`.
+const TX_D1: &str = "00000000000000000000000000000000000000000000000000000000000000d1";
+const UNKNOWN_HASH: &str = "00000000000000000000000000000000000000000000000000000000000000ff";
+const ADDRESS_A: &str = "ecash:qqg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyquz9y96w";
+const ADDRESS_B: &str = "ecash:qq3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg69pxshy7";
+
+async fn setup() -> Router {
+    let base_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let backend = FixtureBackend::load_json(&base_dir.join("tests/fixtures/chain.json"))
+        .expect("Invalid fixture");
+    let server = Server::setup(backend, base_dir.to_path_buf(), CacheConfig::default())
+        .await
+        .unwrap();
+    let server = Arc::new(server);
+    server.router().layer(Extension(server))
+}
+
+async fn get(app: &Router, uri: &str) -> (StatusCode, Option<String>, Vec<u8>) {
+    let request = Request::get(uri).body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let location = response
+        .headers()
+        .get(header::LOCATION)
+        .map(|location| location.to_str().unwrap().to_string());
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, location, body.to_vec())
+}
+
+async fn get_html(app: &Router, uri: &str) -> (StatusCode, String) {
+    let (status, _, body) = get(app, uri).await;
+    (status, String::from_utf8(body).unwrap())
+}
+
+async fn get_json(app: &Router, uri: &str) -> (StatusCode, Value) {
+    let (status, _, body) = get(app, uri).await;
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn test_block_page() {
+    let app = setup().await;
+    let (status, html) = get_html(&app, &format!("/block/{}", BLOCK_1)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(html.contains(BLOCK_1));
+
+    let (status, _) = get_html(&app, &format!("/block/{}", UNKNOWN_HASH)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = get_html(&app, "/block/not-a-hash").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_block_height_redirect() {
+    let app = setup().await;
+    let (status, location, _) = get(&app, "/block-height/1").await;
+    assert_eq!(status, StatusCode::PERMANENT_REDIRECT);
+    assert_eq!(location, Some(format!("/block/{}", BLOCK_1)));
+
+    let (status, html) = get_html(&app, "/block-height/abc").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(html.contains("<html"));
+}
+
+#[tokio::test]
+async fn test_tx_page() {
+    let app = setup().await;
+    let (status, html) = get_html(&app, &format!("/tx/{}", TX_A1)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(html.contains(TX_A1));
+    assert!(html.contains(ADDRESS_B));
+
+    let (status, _) = get_html(&app, &format!("/tx/{}", UNKNOWN_HASH)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_address_page() {
+    let app = setup().await;
+    let (status, html) = get_html(&app, &format!("/address/{}", ADDRESS_B)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(html.contains(ADDRESS_B));
+
+    let (status, _) = get_html(&app, "/address/ecash:invalid").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_api_blocks() {
+    let app = setup().await;
+    let (status, json) = get_json(&app, "/api/blocks/0/1").await;
+    assert_eq!(status, StatusCode::OK);
+    let blocks = json["data"].as_array().unwrap();
+    let hashes = blocks
+        .iter()
+        .map(|block| block["hash"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(hashes.len(), 2);
+    assert!(hashes.contains(&BLOCK_0));
+    assert!(hashes.contains(&BLOCK_1));
+
+    let (status, json) = get_json(&app, "/api/blocks/x/y").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["error"], "bad-request");
+}
+
+#[tokio::test]
+async fn test_api_block_txs() {
+    let app = setup().await;
+    let (status, json) = get_json(&app, &format!("/api/block/{}/transactions", BLOCK_1)).await;
+    assert_eq!(status, StatusCode::OK);
+    let tx_hashes = json["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tx| tx["txHash"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(tx_hashes, vec![COINBASE_1, TX_A1]);
+}
+
+#[tokio::test]
+async fn test_api_tx() {
+    let app = setup().await;
+    let (status, json) = get_json(&app, &format!("/api/tx/{}", TX_A1)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["txHash"], TX_A1);
+    assert_eq!(json["blockHeight"], 1);
+    assert_eq!(json["blockHash"], BLOCK_1);
+    assert_eq!(json["confirmations"], 1);
+    assert_eq!(json["fee"], 1000);
+    assert_eq!(json["stats"]["fee"], 1000);
+    assert_eq!(json["inputs"][0]["destination"]["address"], ADDRESS_A);
+    assert_eq!(json["outputs"][0]["destination"]["address"], ADDRESS_B);
+    assert_eq!(json["outputs"][0]["satsAmount"], 1_000_000_000i64);
+    assert_eq!(json["outputs"][1]["spentByTxHash"], TX_D1);
+
+    let (status, json) = get_json(&app, &format!("/api/tx/{}", TX_D1)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["blockHeight"], Value::Null);
+    assert_eq!(json["confirmations"], 0);
+
+    let (status, json) = get_json(&app, &format!("/api/tx/{}", UNKNOWN_HASH)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(json["error"], "not-found");
+}
+
+#[tokio::test]
+async fn test_api_address() {
+    let app = setup().await;
+    let (status, json) = get_json(&app, &format!("/api/address/{}/balance", ADDRESS_B)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["totalXec"], 12_499_999_000i64);
+    assert_eq!(json["tokenDust"], 0);
+
+    let (status, json) = get_json(&app, &format!("/api/address/{}/balance", ADDRESS_A)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["totalXec"], 0);
+
+    let (status, json) = get_json(&app, &format!("/api/address/{}/utxos", ADDRESS_B)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"].as_array().unwrap().len(), 3);
+
+    let (status, json) = get_json(&app, &format!("/api/address/{}/transactions", ADDRESS_A)).await;
+    assert_eq!(status, StatusCode::OK);
+    let tx_hashes = json["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tx| tx["txHash"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    // Newest first, with the mempool tx on top
+    assert_eq!(tx_hashes[0], TX_D1);
+    assert_eq!(tx_hashes.len(), 3);
+
+    let (status, json) = get_json(&app, "/api/address/ecash:invalid/balance").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["error"], "bad-request");
+}
+
+#[tokio::test]
+async fn test_api_miners() {
+    let app = setup().await;
+    let (status, json) = get_json(&app, "/api/miners").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["tipHeight"], 1);
+    let labels = json["windows"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|window| window["label"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(labels.len(), 3);
+
+    // Custom windows are computed once per tip
+    for _ in 0..2 {
+        let (status, json) = get_json(&app, "/api/miners?window=2").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["windows"].as_array().unwrap().len(), 1);
+        assert_eq!(json["windows"][0]["numBlocks"], 2);
+    }
+
+    let (status, json) = get_json(&app, "/api/miners?window=0").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["error"], "bad-request");
+}
+
+#[tokio::test]
+async fn test_homepage() {
+    let app = setup().await;
+    let (status, html) = get_html(&app, "/").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(html.contains(BLOCK_1));
+    // With an empty mempool, the latest txs are those of the tip
+    assert!(html.contains(TX_A1));
+    assert!(html.contains(COINBASE_1));
+
+    let (status, json) = get_json(&app, "/api/mempool/summary").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["numTxs"], 0);
+    assert_eq!(json["totalBytes"], 0);
+}
+
+#[tokio::test]
+async fn test_api_wallet() {
+    let app = setup().await;
+    let uri = format!("/api/wallet?addresses={},{}", ADDRESS_A, ADDRESS_B);
+    let (status, json) = get_json(&app, &uri).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["addresses"][0]["address"], ADDRESS_A);
+    assert_eq!(json["addresses"][1]["address"], ADDRESS_B);
+
+    let (status, json) = get_json(&app, &format!("{}&take=201", uri)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["error"], "bad-request");
+}
+
+#[tokio::test]
+async fn test_api_mempool() {
+    let app = setup().await;
+    let (status, json) = get_json(&app, "/api/mempool?page=18446744073709551615&take=200").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["numTxs"], 0);
+    assert_eq!(json["txs"].as_array().unwrap().len(), 0);
+    assert_eq!(json["hasMore"], false);
+
+    let (status, json) = get_json(&app, "/api/mempool?take=201").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["error"], "bad-request");
+}
+
+#[tokio::test]
+async fn test_api_script_txs() {
+    let app = setup().await;
+    let uri = format!("/api/script/p2pkh/{}/transactions", "22".repeat(20));
+    let (status, json) = get_json(&app, &uri).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(!json["data"].as_array().unwrap().is_empty());
+
+    let (status, json) = get_json(&app, &format!("{}?take=201", uri)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["error"], "bad-request");
+}